@@ -0,0 +1,244 @@
+use crate::{config, network};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// Loopback plus the private and link-local ranges of both address families
+pub const DEFAULT_ALLOWLIST: [&str; 8] = [
+    "127.0.0.0/8",
+    "::1/128",
+    "10.0.0.0/8",
+    "172.16.0.0/12",
+    "192.168.0.0/16",
+    "169.254.0.0/16",
+    "fc00::/7",
+    "fe80::/10",
+];
+
+/// How many denied requests are kept for the settings screen
+const MAX_DENIALS: usize = 50;
+
+/// An address range such as `192.168.1.0/24`; a bare address matches only itself
+#[derive(Clone, Copy)]
+pub struct Cidr {
+    address: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    pub fn parse(range: &str) -> Result<Cidr, String> {
+        let range = range.trim();
+        let (address, prefix) = match range.split_once('/') {
+            Some((address, prefix)) => (address, Some(prefix)),
+            None => (range, None),
+        };
+
+        let address: IpAddr = address
+            .parse()
+            .map_err(|_| format!("Invalid address in {}", range))?;
+        let max = if address.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix
+                .parse::<u8>()
+                .ok()
+                .filter(|prefix| *prefix <= max)
+                .ok_or_else(|| format!("Invalid prefix length in {}", range))?,
+            None => max,
+        };
+
+        Ok(Cidr { address, prefix })
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.address, ip) {
+            (IpAddr::V4(range), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(range) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(range), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(range) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct Denial {
+    pub ip: String,
+    pub method: String,
+    pub path: String,
+    /// Unix time in milliseconds
+    pub time: u64,
+}
+
+static DENIALS: Mutex<VecDeque<Denial>> = Mutex::new(VecDeque::new());
+
+/// Ranges allowed to use `/api/*`
+pub fn allowlist() -> Vec<String> {
+    config::get().api_allowlist
+}
+
+/// Validate and save the ranges allowed to use `/api/*`
+pub fn set_allowlist(ranges: Vec<String>) -> Result<(), String> {
+    let ranges: Vec<String> = ranges
+        .iter()
+        .map(|range| range.trim().to_string())
+        .filter(|range| !range.is_empty())
+        .collect();
+    for range in &ranges {
+        Cidr::parse(range)?;
+    }
+    config::update(|config| config.api_allowlist = ranges).map(|_| ())
+}
+
+/// Whether a client may use the API. IPv4 clients arriving as IPv4-mapped IPv6
+/// are matched against the IPv4 ranges.
+pub fn is_allowed(ip: IpAddr) -> bool {
+    let ip = network::client_ip(ip);
+    // Ranges are validated when saved, but the config file can be edited by hand
+    allowlist()
+        .iter()
+        .filter_map(|range| Cidr::parse(range).ok())
+        .any(|range| range.contains(ip))
+}
+
+/// Log and remember a rejected request
+pub fn record_denial(ip: Option<IpAddr>, method: &str, path: &str) {
+    let ip = ip
+        .map(|ip| network::client_ip(ip).to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    warn!(%ip, "Denied {} {}: not in the API allowlist", method, path);
+
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    let mut denials = DENIALS.lock().unwrap();
+    if denials.len() == MAX_DENIALS {
+        denials.pop_front();
+    }
+    denials.push_back(Denial {
+        ip,
+        method: method.to_string(),
+        path: path.to_string(),
+        time,
+    });
+}
+
+/// Recently denied requests, newest first
+pub fn denials() -> Vec<Denial> {
+    DENIALS.lock().unwrap().iter().rev().cloned().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(address: &str) -> IpAddr {
+        address.parse().unwrap()
+    }
+
+    fn contains(range: &str, address: &str) -> bool {
+        Cidr::parse(range).unwrap().contains(ip(address))
+    }
+
+    #[test]
+    fn zero_prefix_matches_the_whole_family() {
+        assert!(contains("0.0.0.0/0", "1.2.3.4"));
+        assert!(contains("0.0.0.0/0", "255.255.255.255"));
+        assert!(!contains("0.0.0.0/0", "::1"));
+        assert!(contains("::/0", "2001:db8::1"));
+        assert!(!contains("::/0", "1.2.3.4"));
+    }
+
+    #[test]
+    fn full_prefix_matches_one_address() {
+        assert!(contains("192.168.1.20/32", "192.168.1.20"));
+        assert!(!contains("192.168.1.20/32", "192.168.1.21"));
+        assert!(contains("2001:db8::1/128", "2001:db8::1"));
+        assert!(!contains("2001:db8::1/128", "2001:db8::2"));
+        // A bare address is a single-address range
+        assert!(contains("10.1.2.3", "10.1.2.3"));
+        assert!(!contains("10.1.2.3", "10.1.2.4"));
+    }
+
+    #[test]
+    fn matches_on_the_prefix_only() {
+        assert!(contains("172.16.0.0/12", "172.31.255.255"));
+        assert!(!contains("172.16.0.0/12", "172.32.0.0"));
+        // Host bits in the range are ignored
+        assert!(contains("192.168.1.77/24", "192.168.1.1"));
+        assert!(contains("fe80::/10", "febf::1"));
+        assert!(!contains("fe80::/10", "fec0::1"));
+    }
+
+    #[test]
+    fn rejects_invalid_ranges() {
+        for range in [
+            "",
+            "10.0.0.0/33",
+            "::/129",
+            "10.0.0.0/-1",
+            "10.0.0.0/",
+            "10.0.0.0/8/8",
+            "10.0.0/8",
+            "example.com/24",
+        ] {
+            assert!(Cidr::parse(range).is_err(), "{:?}", range);
+        }
+        assert_eq!(
+            Cidr::parse("10.0.0.0/33").err().as_deref(),
+            Some("Invalid prefix length in 10.0.0.0/33")
+        );
+    }
+
+    #[test]
+    fn ipv4_mapped_clients_match_ipv4_ranges() {
+        let mapped = network::client_ip(ip("::ffff:192.168.1.20"));
+        assert!(Cidr::parse("192.168.0.0/16").unwrap().contains(mapped));
+        // Without unmapping they would only match IPv6 ranges
+        assert!(!contains("192.168.0.0/16", "::ffff:192.168.1.20"));
+
+        config::use_test_dir();
+        assert!(is_allowed(ip("::ffff:192.168.1.20")));
+        assert!(is_allowed(ip("::ffff:127.0.0.1")));
+        assert!(!is_allowed(ip("::ffff:8.8.8.8")));
+    }
+
+    #[test]
+    fn default_allowlist_covers_local_networks_only() {
+        let ranges: Vec<Cidr> = DEFAULT_ALLOWLIST
+            .iter()
+            .map(|range| Cidr::parse(range).unwrap())
+            .collect();
+        let allowed = |address: &str| ranges.iter().any(|range| range.contains(ip(address)));
+
+        for address in [
+            "127.0.0.1",
+            "::1",
+            "10.20.30.40",
+            "172.16.0.1",
+            "192.168.178.2",
+            "169.254.10.10",
+            "fd12:3456::1",
+            "fe80::1",
+        ] {
+            assert!(allowed(address), "{}", address);
+        }
+        for address in [
+            "8.8.8.8",
+            "172.32.0.1",
+            "192.169.0.1",
+            "100.64.0.1",
+            "2001:db8::1",
+            "::",
+        ] {
+            assert!(!allowed(address), "{}", address);
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use crate::{notify, processes, speech};
+use serde::{Deserialize, Serialize};
+
+/// An action that can be triggered without a client attached, e.g. by a schedule
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Action {
+    Shutdown,
+    Restart,
+    Sleep,
+    CancelShutdown,
+    SetVolume {
+        level: i32,
+    },
+    SetBrightness {
+        level: i32,
+        display: Option<String>,
+    },
+    /// Launch an application from the launch allowlist
+    Launch {
+        name: String,
+    },
+    Notify {
+        title: String,
+        #[serde(default)]
+        body: String,
+    },
+    Speak {
+        text: String,
+    },
+}
+
+impl Action {
+    /// The action's `type`, e.g. `set_volume`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Shutdown => "shutdown",
+            Action::Restart => "restart",
+            Action::Sleep => "sleep",
+            Action::CancelShutdown => "cancel_shutdown",
+            Action::SetVolume { .. } => "set_volume",
+            Action::SetBrightness { .. } => "set_brightness",
+            Action::Launch { .. } => "launch",
+            Action::Notify { .. } => "notify",
+            Action::Speak { .. } => "speak",
+        }
+    }
+
+    /// Short description for logs and listings
+    pub fn describe(&self) -> String {
+        match self {
+            Action::Shutdown => "Shutdown".to_string(),
+            Action::Restart => "Restart".to_string(),
+            Action::Sleep => "Sleep".to_string(),
+            Action::CancelShutdown => "Cancel shutdown".to_string(),
+            Action::SetVolume { level } => format!("Set volume to {}%", level),
+            Action::SetBrightness { level, .. } => format!("Set brightness to {}%", level),
+            Action::Launch { name } => format!("Launch {}", name),
+            Action::Notify { title, .. } => format!("Notify \"{}\"", title),
+            Action::Speak { text } => format!("Say \"{}\"", text),
+        }
+    }
+
+    /// Catch mistakes when the action is saved rather than when it runs
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Action::SetVolume { level } | Action::SetBrightness { level, .. }
+                if !(0..=100).contains(level) =>
+            {
+                Err("Level must be between 0 and 100".to_string())
+            }
+            Action::Launch { name } if !processes::launchable().contains(name) => {
+                Err(format!("{} is not in the launch allowlist", name))
+            }
+            Action::Notify { title, .. } if title.trim().is_empty() => {
+                Err("Title is required".to_string())
+            }
+            Action::Speak { text } if text.trim().is_empty() => Err("Nothing to say".to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Run an action, returning a message describing the outcome
+#[tracing::instrument(name = "action", skip_all, fields(action = action.name()))]
+pub fn run(action: &Action) -> Result<String, String> {
+    let response = match action {
+        Action::Shutdown => crate::perform_shutdown(),
+        Action::Restart => crate::perform_restart(),
+        Action::Sleep => crate::perform_sleep(),
+        Action::CancelShutdown => crate::perform_cancel_shutdown(),
+        Action::SetVolume { level } => crate::perform_set_volume(*level),
+        Action::SetBrightness { level, display } => {
+            crate::perform_set_brightness(*level, display.clone())
+        }
+        Action::Launch { name } => {
+            return processes::launch(name)
+                .map(|pid| format!("Launched {} (PID {})", name, pid))
+                .map_err(|e| e.to_string());
+        }
+        Action::Notify { title, body } => {
+            return notify::show(title, body, notify::Urgency::Normal)
+                .map(|_| "Notification shown".to_string());
+        }
+        Action::Speak { text } => {
+            return speech::speak(text, &speech::SpeakOptions::default())
+                .map(|_| "Speaking".to_string());
+        }
+    };
+
+    if response.success {
+        Ok(response.message)
+    } else {
+        Err(response.message)
+    }
+}
@@ -0,0 +1,392 @@
+use crate::config;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::error;
+
+/// Longest string kept in recorded parameters, so pasted text doesn't bloat the log
+const MAX_PARAM_LEN: usize = 200;
+
+/// Most entries returned by one query
+pub const MAX_QUERY_LIMIT: usize = 1000;
+
+/// Where an action came from
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Origin {
+    /// The desktop app, via a Tauri command
+    Desktop,
+    /// The web API
+    Web,
+    Schedule,
+    PowerRule,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Unix time in milliseconds
+    pub time: u64,
+    pub origin: Origin,
+    pub action: String,
+    pub params: Value,
+    /// Client IP address, for web requests
+    pub client: Option<String>,
+    /// Name of the paired device, or its IP address if unpaired, for web requests
+    pub device: Option<String>,
+    pub success: bool,
+    pub result: String,
+}
+
+/// Filters for `query`; every given filter must match
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct AuditQuery {
+    pub origin: Option<Origin>,
+    /// Case-insensitive substring of the action
+    pub action: Option<String>,
+    pub client: Option<String>,
+    pub success: Option<bool>,
+    /// Unix time in milliseconds
+    pub since: Option<u64>,
+    /// Unix time in milliseconds
+    pub until: Option<u64>,
+    /// Defaults to 100
+    pub limit: Option<usize>,
+}
+
+/// Serializes appends and rotation
+static WRITER: Mutex<()> = Mutex::new(());
+
+fn log_path(dir: &Path, index: u32) -> PathBuf {
+    let name = if index == 0 {
+        "audit.log".to_string()
+    } else {
+        format!("audit.{}.log", index)
+    };
+    dir.join(name)
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn truncate_strings(value: Value) -> Value {
+    match value {
+        Value::String(s) if s.chars().count() > MAX_PARAM_LEN => {
+            let mut short: String = s.chars().take(MAX_PARAM_LEN).collect();
+            short.push('…');
+            Value::String(short)
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(truncate_strings).collect()),
+        Value::Object(fields) => Value::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key, truncate_strings(value)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+/// Shift `audit.log` to `audit.1.log` and so on, dropping the oldest file
+fn rotate(dir: &Path, max_files: u32) {
+    let _ = fs::remove_file(log_path(dir, max_files));
+    for index in (0..max_files).rev() {
+        let _ = fs::rename(log_path(dir, index), log_path(dir, index + 1));
+    }
+}
+
+fn append(dir: &Path, settings: &config::AuditConfig, entry: &AuditEntry) -> Result<(), String> {
+    let mut line =
+        serde_json::to_string(entry).map_err(|e| format!("Failed to encode entry: {}", e))?;
+    line.push('\n');
+
+    let _guard = WRITER.lock().unwrap();
+    let path = log_path(dir, 0);
+    let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+    if size > 0 && size + line.len() as u64 > settings.max_file_kb * 1024 {
+        rotate(dir, settings.max_files.max(1));
+    }
+
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create config directory: {}", e))?;
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Append an entry to the audit log
+pub fn record(entry: AuditEntry) {
+    let settings = config::get().audit;
+    if !settings.enabled {
+        return;
+    }
+    let entry = AuditEntry {
+        params: truncate_strings(entry.params),
+        ..entry
+    };
+    if let Err(e) = append(&config::config_dir(), &settings, &entry) {
+        error!("Failed to write audit log: {}", e);
+    }
+}
+
+/// Record an action taken on this machine
+pub fn record_action(origin: Origin, action: &str, params: Value, success: bool, result: &str) {
+    record(AuditEntry {
+        time: now_millis(),
+        origin,
+        action: action.to_string(),
+        params,
+        client: None,
+        device: None,
+        success,
+        result: result.to_string(),
+    });
+}
+
+/// Record a request to the web API
+pub fn record_web(
+    action: &str,
+    params: Value,
+    client: Option<String>,
+    device: Option<String>,
+    success: bool,
+    result: &str,
+) {
+    record(AuditEntry {
+        time: now_millis(),
+        origin: Origin::Web,
+        action: action.to_string(),
+        params,
+        client,
+        device,
+        success,
+        result: result.to_string(),
+    });
+}
+
+fn matches(entry: &AuditEntry, query: &AuditQuery) -> bool {
+    query.origin.is_none_or(|origin| entry.origin == origin)
+        && query
+            .action
+            .as_deref()
+            .is_none_or(|action| entry.action.to_lowercase().contains(&action.to_lowercase()))
+        && query
+            .client
+            .as_deref()
+            .is_none_or(|client| entry.client.as_deref() == Some(client))
+        && query.success.is_none_or(|success| entry.success == success)
+        && query.since.is_none_or(|since| entry.time >= since)
+        && query.until.is_none_or(|until| entry.time <= until)
+}
+
+/// Entries matching the query, newest first
+pub fn query(query: &AuditQuery) -> Vec<AuditEntry> {
+    query_in(&config::config_dir(), config::get().audit.max_files, query)
+}
+
+fn query_in(dir: &Path, max_files: u32, query: &AuditQuery) -> Vec<AuditEntry> {
+    let limit = query.limit.unwrap_or(100).min(MAX_QUERY_LIMIT);
+    let max_files = max_files.max(1);
+    let mut found = Vec::new();
+
+    // Newest file first; each file is read oldest to newest and then reversed
+    for index in 0..=max_files {
+        let Ok(file) = fs::File::open(log_path(dir, index)) else {
+            continue;
+        };
+        let mut entries: Vec<AuditEntry> = BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str(&line).ok())
+            .filter(|entry| matches(entry, query))
+            .collect();
+        entries.reverse();
+        found.extend(entries);
+        if found.len() >= limit {
+            break;
+        }
+    }
+
+    found.truncate(limit);
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entry(
+        time: u64,
+        origin: Origin,
+        action: &str,
+        client: Option<&str>,
+        success: bool,
+    ) -> AuditEntry {
+        AuditEntry {
+            time,
+            origin,
+            action: action.to_string(),
+            params: json!({}),
+            client: client.map(str::to_string),
+            device: None,
+            success,
+            result: String::new(),
+        }
+    }
+
+    fn line_len(entry: &AuditEntry) -> u64 {
+        serde_json::to_string(entry).unwrap().len() as u64 + 1
+    }
+
+    #[test]
+    fn rotates_when_the_next_entry_would_pass_the_size_limit() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let settings = config::AuditConfig {
+            enabled: true,
+            max_file_kb: 1,
+            max_files: 3,
+        };
+        let first = entry(1, Origin::Desktop, "first", None, true);
+        let per_file = 1024 / line_len(&first);
+
+        for _ in 0..per_file {
+            append(dir.path(), &settings, &first).unwrap();
+        }
+        assert!(
+            !log_path(dir.path(), 1).exists(),
+            "rotated before the file was full"
+        );
+        assert!(fs::metadata(log_path(dir.path(), 0)).unwrap().len() <= 1024);
+
+        append(dir.path(), &settings, &first).unwrap();
+        let rotated = fs::read_to_string(log_path(dir.path(), 1)).unwrap();
+        assert_eq!(rotated.lines().count() as u64, per_file);
+        let current = fs::read_to_string(log_path(dir.path(), 0)).unwrap();
+        assert_eq!(current.lines().count(), 1);
+    }
+
+    #[test]
+    fn keeps_only_max_files_rotated_logs() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let settings = config::AuditConfig {
+            enabled: true,
+            max_file_kb: 1,
+            max_files: 2,
+        };
+        // Each entry fills most of a file, so every append rotates
+        let big = |time| AuditEntry {
+            params: json!({ "text": "x".repeat(700) }),
+            ..entry(time, Origin::Desktop, "big", None, true)
+        };
+        for time in 1..=6 {
+            append(dir.path(), &settings, &big(time)).unwrap();
+        }
+
+        assert!(log_path(dir.path(), 1).exists());
+        assert!(log_path(dir.path(), 2).exists());
+        assert!(!log_path(dir.path(), 3).exists());
+        let times: Vec<u64> = query_in(dir.path(), 2, &AuditQuery::default())
+            .iter()
+            .map(|entry| entry.time)
+            .collect();
+        assert_eq!(times, vec![6, 5, 4]);
+    }
+
+    #[test]
+    fn query_applies_every_filter_newest_first() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let settings = config::AuditConfig::default();
+        let entries = [
+            entry(100, Origin::Desktop, "Set volume", None, true),
+            entry(
+                200,
+                Origin::Web,
+                "POST /api/volume",
+                Some("192.0.2.1"),
+                true,
+            ),
+            entry(
+                300,
+                Origin::Web,
+                "POST /api/volume",
+                Some("192.0.2.2"),
+                false,
+            ),
+            entry(400, Origin::Schedule, "Set volume to 10%", None, true),
+            entry(
+                500,
+                Origin::Web,
+                "POST /api/shutdown",
+                Some("192.0.2.1"),
+                true,
+            ),
+        ];
+        for entry in &entries {
+            append(dir.path(), &settings, entry).unwrap();
+        }
+        let times = |query: AuditQuery| -> Vec<u64> {
+            query_in(dir.path(), settings.max_files, &query)
+                .iter()
+                .map(|entry| entry.time)
+                .collect()
+        };
+
+        assert_eq!(times(AuditQuery::default()), vec![500, 400, 300, 200, 100]);
+        assert_eq!(
+            times(AuditQuery {
+                origin: Some(Origin::Web),
+                ..Default::default()
+            }),
+            vec![500, 300, 200]
+        );
+        assert_eq!(
+            times(AuditQuery {
+                action: Some("VOLUME".to_string()),
+                ..Default::default()
+            }),
+            vec![400, 300, 200, 100]
+        );
+        assert_eq!(
+            times(AuditQuery {
+                client: Some("192.0.2.1".to_string()),
+                ..Default::default()
+            }),
+            vec![500, 200]
+        );
+        assert_eq!(
+            times(AuditQuery {
+                success: Some(false),
+                ..Default::default()
+            }),
+            vec![300]
+        );
+        assert_eq!(
+            times(AuditQuery {
+                since: Some(200),
+                until: Some(400),
+                ..Default::default()
+            }),
+            vec![400, 300, 200]
+        );
+        assert_eq!(
+            times(AuditQuery {
+                origin: Some(Origin::Web),
+                success: Some(true),
+                limit: Some(1),
+                ..Default::default()
+            }),
+            vec![500]
+        );
+    }
+}
@@ -0,0 +1,386 @@
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+#[derive(Clone, Serialize)]
+pub struct DisplayBrightness {
+    /// Backend-prefixed identifier, e.g. `backlight:intel_backlight` or `ddc:1`
+    pub id: String,
+    pub name: String,
+    pub brightness: i32,
+}
+
+/// List the displays whose brightness can be controlled
+pub fn list() -> Result<Vec<DisplayBrightness>, String> {
+    let mut displays = Vec::new();
+
+    if cfg!(target_os = "linux") {
+        displays.extend(read_backlights(Path::new("/")));
+        displays.extend(ddc_displays());
+    } else if cfg!(target_os = "windows") {
+        displays.extend(wmi_brightness());
+    } else {
+        return Err("Unsupported operating system".to_string());
+    }
+
+    Ok(displays)
+}
+
+/// Set the brightness (0-100) of one display, or of every display when `id` is `None`
+pub fn set(id: Option<&str>, level: i32) -> Result<Vec<DisplayBrightness>, String> {
+    let level = level.clamp(0, 100);
+    let targets: Vec<DisplayBrightness> = list()?
+        .into_iter()
+        .filter(|display| id.is_none_or(|id| display.id == id))
+        .collect();
+
+    if targets.is_empty() {
+        return Err(match id {
+            Some(id) => format!("Display {} not found", id),
+            None => "No display with brightness control found".to_string(),
+        });
+    }
+
+    for display in &targets {
+        let (backend, name) = display.id.split_once(':').unwrap_or(("", &display.id));
+        match backend {
+            "backlight" => write_backlight(Path::new("/"), name, level)?,
+            "ddc" => set_ddc(name, level)?,
+            "wmi" => set_wmi(level)?,
+            _ => return Err(format!("Unknown display {}", display.id)),
+        }
+    }
+
+    Ok(targets
+        .into_iter()
+        .map(|display| DisplayBrightness {
+            brightness: level,
+            ..display
+        })
+        .collect())
+}
+
+/// Read the backlights below `<root>/sys/class/backlight`.
+///
+/// Passing a fixture directory instead of `/` allows using a fake sysfs tree.
+pub fn read_backlights(root: &Path) -> Vec<DisplayBrightness> {
+    let Ok(entries) = fs::read_dir(root.join("sys/class/backlight")) else {
+        return Vec::new();
+    };
+
+    let mut displays: Vec<DisplayBrightness> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let (current, max) = read_backlight_values(&entry.path())?;
+            Some(DisplayBrightness {
+                id: format!("backlight:{}", name),
+                name,
+                brightness: to_percent(current, max),
+            })
+        })
+        .collect();
+
+    displays.sort_by(|a, b| a.id.cmp(&b.id));
+    displays
+}
+
+fn read_backlight_values(device: &Path) -> Option<(i32, i32)> {
+    let read = |file: &str| -> Option<i32> {
+        fs::read_to_string(device.join(file)).ok()?.trim().parse().ok()
+    };
+
+    let max = read("max_brightness").filter(|max| *max > 0)?;
+    let current = read("actual_brightness").or_else(|| read("brightness"))?;
+    Some((current.clamp(0, max), max))
+}
+
+/// A raw value out of `max` as the nearest percentage.
+///
+/// Computed in `i64`, since some panels report a `max_brightness` large
+/// enough to overflow `i32` once multiplied by 100.
+fn to_percent(value: i32, max: i32) -> i32 {
+    let percent = (i64::from(value) * 100 + i64::from(max) / 2) / i64::from(max);
+    percent.clamp(0, 100) as i32
+}
+
+/// A percentage as the nearest raw value out of `max`
+fn from_percent(level: i32, max: i32) -> i32 {
+    let raw = (i64::from(level.clamp(0, 100)) * i64::from(max) + 50) / 100;
+    raw.clamp(0, i64::from(max)) as i32
+}
+
+/// Set a backlight below `<root>/sys/class/backlight` to a percentage
+pub fn write_backlight(root: &Path, name: &str, level: i32) -> Result<(), String> {
+    if name.contains('/') || name.contains("..") {
+        return Err(format!("Invalid backlight name {}", name));
+    }
+
+    let device = root.join("sys/class/backlight").join(name);
+    let (_, max) = read_backlight_values(&device)
+        .ok_or_else(|| format!("Backlight {} not found", name))?;
+
+    let raw = from_percent(level, max);
+    fs::write(device.join("brightness"), raw.to_string()).map_err(|e| {
+        format!(
+            "Failed to set brightness of {}: {} (writing the backlight usually needs a udev rule or the video group)",
+            name, e
+        )
+    })
+}
+
+/// External monitors reachable over DDC/CI, via `ddcutil`
+fn ddc_displays() -> Vec<DisplayBrightness> {
+    let Ok(output) = Command::new("ddcutil").args(["detect", "--brief"]).output() else {
+        return Vec::new();
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    // Output looks like:
+    // Display 1
+    //    I2C bus:  /dev/i2c-4
+    //    Monitor:  DEL:DELL U2415:ABC123
+    let mut detected: Vec<(String, String)> = Vec::new();
+    for line in stdout.lines() {
+        let line = line.trim();
+        if let Some(number) = line.strip_prefix("Display ") {
+            detected.push((number.trim().to_string(), format!("Display {}", number.trim())));
+        } else if let Some(monitor) = line.strip_prefix("Monitor:") {
+            if let Some((_, name)) = detected.last_mut() {
+                *name = monitor
+                    .split(':')
+                    .nth(1)
+                    .map(|model| model.trim().to_string())
+                    .filter(|model| !model.is_empty())
+                    .unwrap_or_else(|| name.clone());
+            }
+        }
+    }
+
+    detected
+        .into_iter()
+        .filter_map(|(number, name)| {
+            let brightness = get_ddc(&number)?;
+            Some(DisplayBrightness {
+                id: format!("ddc:{}", number),
+                name,
+                brightness,
+            })
+        })
+        .collect()
+}
+
+fn get_ddc(display: &str) -> Option<i32> {
+    let output = Command::new("ddcutil")
+        .args(["getvcp", "10", "--brief", "--display", display])
+        .output()
+        .ok()?;
+
+    // Output looks like: "VCP 10 C 50 100" (feature, type, current, max)
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let fields: Vec<&str> = stdout.split_whitespace().collect();
+    let current: i32 = fields.get(3)?.parse().ok()?;
+    let max: i32 = fields.get(4)?.parse().ok().filter(|max| *max > 0)?;
+    Some(to_percent(current, max))
+}
+
+fn set_ddc(display: &str, level: i32) -> Result<(), String> {
+    let output = Command::new("ddcutil")
+        .args(["setvcp", "10", &level.to_string(), "--display", display])
+        .output()
+        .map_err(|e| format!("Failed to run ddcutil: {}", e))?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Failed to set brightness of display {}: {}",
+            display,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+/// Built-in laptop panels on Windows, via WMI
+fn wmi_brightness() -> Option<DisplayBrightness> {
+    let output = Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            "(Get-CimInstance -Namespace root/WMI -ClassName WmiMonitorBrightness | Select-Object -First 1).CurrentBrightness",
+        ])
+        .output()
+        .ok()?;
+
+    let brightness = String::from_utf8_lossy(&output.stdout).trim().parse().ok()?;
+    Some(DisplayBrightness {
+        id: "wmi:0".to_string(),
+        name: "Built-in display".to_string(),
+        brightness,
+    })
+}
+
+fn set_wmi(level: i32) -> Result<(), String> {
+    let script = format!(
+        "Get-CimInstance -Namespace root/WMI -ClassName WmiMonitorBrightnessMethods | Invoke-CimMethod -MethodName WmiSetBrightness -Arguments @{{Timeout=1; Brightness={}}}",
+        level
+    );
+    let output = Command::new("powershell")
+        .args(["-NoProfile", "-Command", &script])
+        .output()
+        .map_err(|e| format!("Failed to run powershell: {}", e))?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Failed to set brightness: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn add_backlight(root: &Path, name: &str, files: &[(&str, &str)]) {
+        let device = root.join("sys/class/backlight").join(name);
+        fs::create_dir_all(&device).unwrap();
+        for (file, contents) in files {
+            fs::write(device.join(file), contents).unwrap();
+        }
+    }
+
+    fn raw_brightness(root: &Path, name: &str) -> String {
+        fs::read_to_string(
+            root.join("sys/class/backlight")
+                .join(name)
+                .join("brightness"),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn reads_backlights_as_percentages() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        add_backlight(
+            root,
+            "intel_backlight",
+            &[("brightness", "960\n"), ("max_brightness", "1200\n")],
+        );
+        add_backlight(
+            root,
+            "acpi_video0",
+            &[("brightness", "3\n"), ("max_brightness", "7\n")],
+        );
+
+        let displays = read_backlights(root);
+        let levels: Vec<(&str, i32)> = displays
+            .iter()
+            .map(|display| (display.id.as_str(), display.brightness))
+            .collect();
+        // Sorted by id, rounded to the nearest percent
+        assert_eq!(
+            levels,
+            [
+                ("backlight:acpi_video0", 43),
+                ("backlight:intel_backlight", 80)
+            ]
+        );
+        assert_eq!(displays[1].name, "intel_backlight");
+    }
+
+    #[test]
+    fn prefers_actual_brightness_and_clamps_it() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        add_backlight(
+            root,
+            "panel",
+            &[
+                ("brightness", "10\n"),
+                ("actual_brightness", "150\n"),
+                ("max_brightness", "100\n"),
+            ],
+        );
+
+        assert_eq!(read_backlights(root)[0].brightness, 100);
+    }
+
+    #[test]
+    fn skips_unreadable_backlights() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        add_backlight(
+            root,
+            "zero_max",
+            &[("brightness", "5\n"), ("max_brightness", "0\n")],
+        );
+        add_backlight(root, "no_max", &[("brightness", "5\n")]);
+        add_backlight(
+            root,
+            "garbage",
+            &[("brightness", "bright\n"), ("max_brightness", "10\n")],
+        );
+
+        assert!(read_backlights(root).is_empty());
+        assert!(read_backlights(&root.join("missing")).is_empty());
+    }
+
+    #[test]
+    fn writes_scaled_and_clamped_levels() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        add_backlight(
+            root,
+            "panel",
+            &[("brightness", "0\n"), ("max_brightness", "255\n")],
+        );
+
+        write_backlight(root, "panel", 50).unwrap();
+        assert_eq!(raw_brightness(root, "panel"), "128");
+
+        write_backlight(root, "panel", 150).unwrap();
+        assert_eq!(raw_brightness(root, "panel"), "255");
+
+        write_backlight(root, "panel", -20).unwrap();
+        assert_eq!(raw_brightness(root, "panel"), "0");
+    }
+
+    #[test]
+    fn scales_large_ranges_without_overflow() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        let max = i32::MAX.to_string();
+        add_backlight(
+            root,
+            "panel",
+            &[("brightness", &max), ("max_brightness", &max)],
+        );
+
+        assert_eq!(read_backlights(root)[0].brightness, 100);
+
+        write_backlight(root, "panel", 50).unwrap();
+        assert_eq!(raw_brightness(root, "panel"), "1073741824");
+
+        write_backlight(root, "panel", 100).unwrap();
+        assert_eq!(raw_brightness(root, "panel"), max);
+
+        assert_eq!(to_percent(150, 100), 100);
+        assert_eq!(to_percent(-5, 100), 0);
+    }
+
+    #[test]
+    fn rejects_unknown_and_escaping_backlights() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+
+        assert!(write_backlight(root, "missing", 50).is_err());
+        assert!(write_backlight(root, "../../etc", 50).is_err());
+        assert!(write_backlight(root, "a/b", 50).is_err());
+    }
+}
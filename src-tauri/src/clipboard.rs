@@ -0,0 +1,146 @@
+use arboard::{Clipboard, ImageData};
+use futures_util::stream::Stream;
+use image::RgbaImage;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+/// How often the watcher checks the clipboard for changes
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Serialize)]
+pub struct ClipboardImage {
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Clone, Serialize)]
+pub struct ClipboardContent {
+    pub text: Option<String>,
+    /// Size of the image on the clipboard; the pixels are served separately as PNG
+    pub image: Option<ClipboardImage>,
+}
+
+/// One clipboard instance kept alive for the whole process, since on X11 the
+/// contents we set are only available while their owner exists
+static CLIPBOARD: Mutex<Option<Clipboard>> = Mutex::new(None);
+
+fn with_clipboard<T>(
+    f: impl FnOnce(&mut Clipboard) -> Result<T, arboard::Error>,
+) -> Result<T, String> {
+    let mut clipboard = CLIPBOARD.lock().unwrap();
+    if clipboard.is_none() {
+        *clipboard =
+            Some(Clipboard::new().map_err(|e| format!("Failed to open clipboard: {}", e))?);
+    }
+
+    f(clipboard.as_mut().unwrap()).map_err(|e| format!("Clipboard error: {}", e))
+}
+
+/// Read the current clipboard text and image size
+pub fn get() -> Result<ClipboardContent, String> {
+    // An empty clipboard or one holding another format is not an error
+    let text = with_clipboard(|clipboard| clipboard.get_text()).ok();
+    let image = with_clipboard(|clipboard| clipboard.get_image())
+        .ok()
+        .map(|image| ClipboardImage {
+            width: image.width as u32,
+            height: image.height as u32,
+        });
+
+    Ok(ClipboardContent { text, image })
+}
+
+pub fn set_text(text: &str) -> Result<(), String> {
+    with_clipboard(|clipboard| clipboard.set_text(text))
+}
+
+/// Read the clipboard image encoded as PNG
+pub fn get_image_png() -> Result<Vec<u8>, String> {
+    let image = with_clipboard(|clipboard| clipboard.get_image())?;
+    let pixels = RgbaImage::from_raw(
+        image.width as u32,
+        image.height as u32,
+        image.bytes.into_owned(),
+    )
+    .ok_or("Clipboard image has an unexpected size")?;
+
+    crate::screen::encode(pixels, None, crate::screen::ImageFormat::Png, 100)
+}
+
+/// Put a PNG (or any other supported image format) on the clipboard
+pub fn set_image(data: &[u8]) -> Result<(), String> {
+    let image = image::load_from_memory(data)
+        .map_err(|e| format!("Invalid image: {}", e))?
+        .to_rgba8();
+
+    with_clipboard(|clipboard| {
+        clipboard.set_image(ImageData {
+            width: image.width() as usize,
+            height: image.height() as usize,
+            bytes: Cow::Borrowed(image.as_raw()),
+        })
+    })
+}
+
+/// Start the clipboard watcher (once) and return the change channel
+fn channel() -> &'static broadcast::Sender<ClipboardContent> {
+    static CHANNEL: OnceLock<broadcast::Sender<ClipboardContent>> = OnceLock::new();
+
+    CHANNEL.get_or_init(|| {
+        let (sender, _) = broadcast::channel(16);
+        let watcher = sender.clone();
+
+        std::thread::spawn(move || {
+            let mut last = None;
+            loop {
+                std::thread::sleep(POLL_INTERVAL);
+                if watcher.receiver_count() == 0 {
+                    last = None;
+                    continue;
+                }
+
+                let Ok(content) = get() else {
+                    continue;
+                };
+                let fingerprint = fingerprint(&content);
+                if last.is_some_and(|last| last != fingerprint) {
+                    let _ = watcher.send(content);
+                }
+                last = Some(fingerprint);
+            }
+        });
+
+        sender
+    })
+}
+
+fn fingerprint(content: &ClipboardContent) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.text.hash(&mut hasher);
+    if content.image.is_some() {
+        if let Ok(image) = with_clipboard(|clipboard| clipboard.get_image()) {
+            image.bytes.hash(&mut hasher);
+        }
+    }
+    hasher.finish()
+}
+
+/// Stream of clipboard contents, one item per change
+pub fn changes() -> impl Stream<Item = ClipboardContent> {
+    let receiver = channel().subscribe();
+
+    futures_util::stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(content) => return Some((content, receiver)),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    })
+}
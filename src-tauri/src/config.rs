@@ -0,0 +1,364 @@
+use crate::roles::Role;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{OnceLock, RwLock};
+
+/// Must match `identifier` in tauri.conf.json so the file sits next to Tauri's own app data
+const APP_IDENTIFIER: &str = "com.rudresh.ferrous-control";
+
+const CONFIG_FILE: &str = "config.json";
+
+/// An executable that may be launched remotely
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LaunchEntry {
+    pub name: String,
+    pub path: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+/// A machine that can be woken with a Wake-on-LAN magic packet
+#[derive(Clone, Serialize, Deserialize)]
+pub struct WolMachine {
+    pub name: String,
+    /// MAC address, e.g. `00:11:22:33:44:55`
+    pub mac: String,
+    /// Where the magic packet is sent; the subnet's broadcast address works best
+    #[serde(default = "default_wol_broadcast")]
+    pub broadcast: String,
+    #[serde(default = "default_wol_port")]
+    pub port: u16,
+}
+
+fn default_wol_broadcast() -> String {
+    "255.255.255.255".to_string()
+}
+
+fn default_wol_port() -> u16 {
+    9
+}
+
+/// Another Ferrous Control instance managed from this one
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Peer {
+    pub name: String,
+    /// Base URL of the peer's web server, e.g. `http://192.168.1.20:7777`
+    pub url: String,
+    /// Token from the peer's device roles, sent as a bearer token; without one
+    /// the peer gives this PC its default role
+    #[serde(default)]
+    pub token: Option<String>,
+    /// Group used for bulk actions, e.g. `lab`
+    #[serde(default)]
+    pub group: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FileTransferConfig {
+    /// Where uploads are saved; defaults to `Downloads/Ferrous Control`
+    pub inbox_dir: Option<PathBuf>,
+    /// Directory that can be browsed and downloaded from; sharing is off when unset
+    pub share_dir: Option<PathBuf>,
+    /// Largest accepted upload, in megabytes
+    pub max_upload_mb: u64,
+}
+
+impl Default for FileTransferConfig {
+    fn default() -> Self {
+        FileTransferConfig {
+            inbox_dir: None,
+            share_dir: None,
+            max_upload_mb: 1024,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OpenUrlConfig {
+    /// URL schemes that may be opened remotely
+    pub allowed_schemes: Vec<String>,
+    /// Ask on the desktop before opening a URL
+    pub confirm: bool,
+}
+
+impl Default for OpenUrlConfig {
+    fn default() -> Self {
+        OpenUrlConfig {
+            allowed_schemes: vec!["http".to_string(), "https".to_string()],
+            confirm: false,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuditConfig {
+    pub enabled: bool,
+    /// Size at which `audit.log` is rotated, in kilobytes
+    pub max_file_kb: u64,
+    /// Rotated files kept next to `audit.log`
+    pub max_files: u32,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        AuditConfig {
+            enabled: true,
+            max_file_kb: 1024,
+            max_files: 5,
+        }
+    }
+}
+
+/// Application log written to `logs/` in the config directory
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// Minimum level or filter directives, e.g. `debug` or `info,actix_web=warn`.
+    /// `RUST_LOG` takes precedence when set.
+    pub level: String,
+    /// Write one JSON object per line instead of plain text
+    pub json: bool,
+    /// Daily log files kept
+    pub max_files: usize,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        LoggingConfig {
+            level: "info".to_string(),
+            json: false,
+            max_files: 7,
+        }
+    }
+}
+
+/// A phone, tablet or PC paired with this one
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DeviceRole {
+    pub name: String,
+    /// Secret the device presents, as a bearer token or in the cookie set when
+    /// a browser is paired; generated when the device is saved without one
+    #[serde(default)]
+    pub token: String,
+    pub role: Role,
+}
+
+/// What each device may do over the web API
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RolesConfig {
+    /// Role of devices that present no token
+    pub default_role: Role,
+    pub devices: Vec<DeviceRole>,
+}
+
+impl Default for RolesConfig {
+    fn default() -> Self {
+        RolesConfig {
+            default_role: Role::Viewer,
+            devices: Vec::new(),
+        }
+    }
+}
+
+/// Per-client request budgets for the web API
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    /// Shutdown, restart, sleep, Wake-on-LAN and peer actions, per minute
+    pub power_per_minute: u32,
+    /// Volume and brightness changes, per minute
+    pub media_per_minute: u32,
+    /// Any other API request, per minute
+    pub default_per_minute: u32,
+    /// Failed authentications within the lockout time that lock a client out
+    pub max_auth_failures: u32,
+    pub lockout_minutes: u64,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        RateLimitConfig {
+            enabled: true,
+            power_per_minute: 6,
+            media_per_minute: 300,
+            default_per_minute: 120,
+            max_auth_failures: 5,
+            lockout_minutes: 15,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub launch_allowlist: Vec<LaunchEntry>,
+    /// Serve Prometheus metrics on `/metrics`
+    pub metrics_enabled: bool,
+    /// Default frame rate of the live screen view
+    pub stream_fps: u32,
+    /// Default JPEG quality (1-100) of the live screen view
+    pub stream_quality: u8,
+    pub file_transfer: FileTransferConfig,
+    pub open_url: OpenUrlConfig,
+    /// Most desktop notifications shown per minute
+    pub notify_limit_per_minute: u32,
+    /// Text-to-speech engine: `auto`, `sapi`, `speech-dispatcher`, `espeak`, `say` or `mock`
+    pub tts_backend: String,
+    /// Warn whoever is at the PC with a countdown when a shutdown is scheduled
+    pub shutdown_warning: bool,
+    /// Custom text for the shutdown warning
+    pub shutdown_message: Option<String>,
+    /// Machines that can be woken from this one
+    pub wol_machines: Vec<WolMachine>,
+    /// Other instances shown in the multi-machine dashboard
+    pub peers: Vec<Peer>,
+    /// Advertise this instance on the LAN as `_ferrous-control._tcp`
+    pub mdns_enabled: bool,
+    /// Network interface whose address is shown in the QR code; picked automatically when unset
+    pub preferred_interface: Option<String>,
+    /// Accept web clients over IPv4 (`0.0.0.0`)
+    pub listen_ipv4: bool,
+    /// Accept web clients over IPv6 (`::`)
+    pub listen_ipv6: bool,
+    /// CIDR ranges allowed to use `/api/*`; everything else gets 403
+    pub api_allowlist: Vec<String>,
+    pub rate_limit: RateLimitConfig,
+    pub audit: AuditConfig,
+    pub logging: LoggingConfig,
+    pub roles: RolesConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            launch_allowlist: Vec::new(),
+            metrics_enabled: false,
+            stream_fps: 2,
+            stream_quality: 60,
+            file_transfer: FileTransferConfig::default(),
+            open_url: OpenUrlConfig::default(),
+            notify_limit_per_minute: 10,
+            tts_backend: "auto".to_string(),
+            shutdown_warning: true,
+            shutdown_message: None,
+            wol_machines: Vec::new(),
+            peers: Vec::new(),
+            mdns_enabled: true,
+            preferred_interface: None,
+            listen_ipv4: true,
+            listen_ipv6: true,
+            api_allowlist: crate::access::DEFAULT_ALLOWLIST
+                .iter()
+                .map(|range| range.to_string())
+                .collect(),
+            rate_limit: RateLimitConfig::default(),
+            audit: AuditConfig::default(),
+            logging: LoggingConfig::default(),
+            roles: RolesConfig::default(),
+        }
+    }
+}
+
+/// Directory holding the config file and other persisted state.
+///
+/// Can be overridden with the `FERROUS_CONTROL_CONFIG_DIR` environment variable.
+pub fn config_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os("FERROUS_CONTROL_CONFIG_DIR") {
+        return PathBuf::from(dir);
+    }
+
+    dirs::config_dir()
+        .map(|dir| dir.join(APP_IDENTIFIER))
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// Set when an invalid config file couldn't be moved aside; saving would overwrite it
+static INVALID_FILE_KEPT: AtomicBool = AtomicBool::new(false);
+
+fn load() -> Config {
+    let path = config_dir().join(CONFIG_FILE);
+
+    match fs::read_to_string(&path) {
+        Ok(contents) => match serde_json::from_str(&contents) {
+            Ok(config) => config,
+            Err(e) => {
+                // Move the file aside so the next save doesn't replace the user's
+                // settings with defaults. Logging is configured from this file, so
+                // it isn't set up yet.
+                let backup = path.with_extension("json.bak");
+                match fs::rename(&path, &backup) {
+                    Ok(()) => eprintln!(
+                        "Invalid config file {}: {}; moved it to {}",
+                        path.display(),
+                        e,
+                        backup.display()
+                    ),
+                    Err(rename_error) => {
+                        eprintln!(
+                            "Invalid config file {}: {}; failed to move it aside: {}",
+                            path.display(),
+                            e,
+                            rename_error
+                        );
+                        INVALID_FILE_KEPT.store(true, Ordering::Relaxed);
+                    }
+                }
+                Config::default()
+            }
+        },
+        Err(_) => Config::default(),
+    }
+}
+
+fn state() -> &'static RwLock<Config> {
+    static CONFIG: OnceLock<RwLock<Config>> = OnceLock::new();
+    CONFIG.get_or_init(|| RwLock::new(load()))
+}
+
+/// Keep the config in a fresh directory for the rest of the test run, so tests
+/// never read or overwrite the user's settings
+#[cfg(test)]
+pub fn use_test_dir() {
+    static DIR: OnceLock<tempfile::TempDir> = OnceLock::new();
+    DIR.get_or_init(|| {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::env::set_var("FERROUS_CONTROL_CONFIG_DIR", dir.path());
+        dir
+    });
+}
+
+/// Get a snapshot of the current config
+pub fn get() -> Config {
+    state().read().unwrap().clone()
+}
+
+/// Modify the config and persist it to disk
+pub fn update<F: FnOnce(&mut Config)>(f: F) -> Result<Config, String> {
+    let mut config = state().write().unwrap();
+    if INVALID_FILE_KEPT.load(Ordering::Relaxed) {
+        return Err(format!(
+            "Not saving: {} is invalid; fix or remove it and restart",
+            config_dir().join(CONFIG_FILE).display()
+        ));
+    }
+    let mut updated = config.clone();
+    f(&mut updated);
+
+    let dir = config_dir();
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create config directory: {}", e))?;
+
+    let contents = serde_json::to_string_pretty(&updated)
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+    fs::write(dir.join(CONFIG_FILE), contents)
+        .map_err(|e| format!("Failed to write config: {}", e))?;
+
+    *config = updated.clone();
+    Ok(updated)
+}
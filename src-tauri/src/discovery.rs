@@ -0,0 +1,189 @@
+use crate::config;
+use mdns_sd::{IfKind, ServiceDaemon, ServiceEvent, ServiceInfo};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use sysinfo::System;
+use tracing::info;
+
+/// DNS-SD service type advertised by every instance
+pub const SERVICE_TYPE: &str = "_ferrous-control._tcp.local.";
+
+/// Longest accepted browse time
+pub const MAX_BROWSE_TIME: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Serialize)]
+pub struct DiscoveredInstance {
+    /// DNS-SD instance name, normally the hostname
+    pub name: String,
+    pub hostname: String,
+    pub addresses: Vec<String>,
+    pub port: u16,
+    pub tls: bool,
+    pub version: Option<String>,
+    /// Whether this is the instance doing the browsing
+    pub is_self: bool,
+}
+
+/// Full name of our own registration, used to mark ourselves in browse results
+static REGISTERED: OnceLock<String> = OnceLock::new();
+
+/// `stop_browse` ends every browse of the service type, so browses take turns
+static BROWSING: Mutex<()> = Mutex::new(());
+
+/// The shared daemon, started on first use. The lock is held while starting
+/// it, so concurrent first calls don't start two.
+fn daemon() -> Result<ServiceDaemon, String> {
+    static DAEMON: Mutex<Option<ServiceDaemon>> = Mutex::new(None);
+    let mut shared = DAEMON.lock().unwrap();
+    if let Some(daemon) = shared.as_ref() {
+        return Ok(daemon.clone());
+    }
+
+    let daemon = ServiceDaemon::new().map_err(|e| format!("Failed to start mDNS: {}", e))?;
+    // Loopback is off by default; tests and single-machine setups can turn it on
+    if std::env::var_os("FERROUS_CONTROL_MDNS_LOOPBACK").is_some() {
+        daemon
+            .enable_interface(IfKind::LoopbackV4)
+            .map_err(|e| format!("Failed to enable mDNS on loopback: {}", e))?;
+    }
+    *shared = Some(daemon.clone());
+    Ok(daemon)
+}
+
+/// Hostname reduced to characters that are valid in a `.local` name
+fn local_hostname() -> String {
+    let hostname = System::host_name().unwrap_or_else(|| "ferrous-control".to_string());
+    let cleaned: String = hostname
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    cleaned.trim_matches('-').to_string()
+}
+
+/// Advertise the web server on the LAN, unless disabled in the config
+pub fn advertise(port: u16, tls: bool) -> Result<(), String> {
+    if !config::get().mdns_enabled {
+        return Ok(());
+    }
+
+    let hostname = local_hostname();
+    let properties = [
+        ("hostname", hostname.clone()),
+        ("port", port.to_string()),
+        ("tls", tls.to_string()),
+        ("version", env!("CARGO_PKG_VERSION").to_string()),
+    ];
+    let service = ServiceInfo::new(
+        SERVICE_TYPE,
+        &hostname,
+        &format!("{}.local.", hostname),
+        "",
+        port,
+        &properties[..],
+    )
+    .map_err(|e| format!("Invalid mDNS service: {}", e))?
+    // Addresses follow the machine's interfaces as they come and go
+    .enable_addr_auto();
+
+    let fullname = service.get_fullname().to_string();
+    daemon()?
+        .register(service)
+        .map_err(|e| format!("Failed to advertise via mDNS: {}", e))?;
+    info!("Advertising {} via mDNS", fullname);
+    let _ = REGISTERED.set(fullname);
+    Ok(())
+}
+
+/// Look for instances on the LAN for `duration`
+pub fn browse(duration: Duration) -> Result<Vec<DiscoveredInstance>, String> {
+    let daemon = daemon()?;
+    let _browsing = BROWSING.lock().unwrap();
+    let receiver = daemon
+        .browse(SERVICE_TYPE)
+        .map_err(|e| format!("Failed to browse via mDNS: {}", e))?;
+
+    let deadline = Instant::now() + duration.min(MAX_BROWSE_TIME);
+    let mut found = HashMap::new();
+    while let Ok(event) = receiver.recv_deadline(deadline) {
+        match event {
+            ServiceEvent::ServiceResolved(info) => {
+                let name = info
+                    .get_fullname()
+                    .trim_end_matches(SERVICE_TYPE)
+                    .trim_end_matches('.')
+                    .to_string();
+                let mut addresses: Vec<String> = info
+                    .get_addresses()
+                    .iter()
+                    .map(|address| address.to_string())
+                    .collect();
+                addresses.sort();
+
+                let instance = DiscoveredInstance {
+                    name,
+                    hostname: info
+                        .get_property_val_str("hostname")
+                        .unwrap_or(info.get_hostname())
+                        .to_string(),
+                    addresses,
+                    port: info.get_port(),
+                    tls: info.get_property_val_str("tls") == Some("true"),
+                    version: info.get_property_val_str("version").map(str::to_string),
+                    is_self: REGISTERED.get().map(String::as_str) == Some(info.get_fullname()),
+                };
+                found.insert(info.get_fullname().to_string(), instance);
+            }
+            ServiceEvent::ServiceRemoved(_, fullname) => {
+                found.remove(&fullname);
+            }
+            _ => {}
+        }
+    }
+    let _ = daemon.stop_browse(SERVICE_TYPE);
+
+    let mut instances: Vec<DiscoveredInstance> = found.into_values().collect();
+    instances.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(instances)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn browsing_finds_our_own_advertisement_on_loopback() {
+        config::use_test_dir();
+        std::env::set_var("FERROUS_CONTROL_MDNS_LOOPBACK", "1");
+
+        advertise(47777, true).unwrap();
+        let instances = browse(Duration::from_secs(3)).unwrap();
+
+        let ours = instances
+            .iter()
+            .find(|instance| instance.is_self)
+            .expect("our own instance was not found");
+        assert_eq!(ours.name, local_hostname());
+        assert_eq!(ours.port, 47777);
+        assert!(ours.tls);
+        assert_eq!(ours.version.as_deref(), Some(env!("CARGO_PKG_VERSION")));
+        assert!(!ours.addresses.is_empty());
+    }
+
+    #[test]
+    fn overlapping_browses_take_turns() {
+        config::use_test_dir();
+        std::env::set_var("FERROUS_CONTROL_MDNS_LOOPBACK", "1");
+
+        advertise(47777, true).unwrap();
+        // Without taking turns, the first browse to end would stop the other
+        let browses: Vec<_> = (0..2)
+            .map(|_| std::thread::spawn(|| browse(Duration::from_secs(2))))
+            .collect();
+        for browse in browses {
+            let instances = browse.join().unwrap().unwrap();
+            assert!(instances.iter().any(|instance| instance.is_self));
+        }
+    }
+}
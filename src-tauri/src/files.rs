@@ -0,0 +1,378 @@
+use crate::config;
+use serde::Serialize;
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::UNIX_EPOCH;
+use tracing::info;
+
+/// Longest file name kept when saving an upload, in bytes
+const MAX_FILENAME_LEN: usize = 200;
+
+/// Device names Windows refuses to use as file names
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+#[derive(Clone, Serialize)]
+pub struct ReceivedFile {
+    pub name: String,
+    pub path: String,
+    pub size: u64,
+}
+
+#[derive(Clone, Serialize)]
+pub struct ShareEntry {
+    pub name: String,
+    /// Path relative to the share directory, using `/` separators
+    pub path: String,
+    pub is_dir: bool,
+    pub size: u64,
+    /// Last modification time in unix milliseconds
+    pub modified: Option<u64>,
+}
+
+/// Directory uploads are saved to
+pub fn inbox_dir() -> PathBuf {
+    config::get()
+        .file_transfer
+        .inbox_dir
+        .or_else(|| dirs::download_dir().map(|dir| dir.join("Ferrous Control")))
+        .unwrap_or_else(|| config::config_dir().join("inbox"))
+}
+
+/// Largest accepted upload in bytes
+pub fn max_upload_bytes() -> u64 {
+    config::get()
+        .file_transfer
+        .max_upload_mb
+        .saturating_mul(1024 * 1024)
+}
+
+/// Turn a client-supplied file name into a safe name without any directory part
+pub fn sanitize_filename(name: &str) -> Option<String> {
+    // Browsers may send a full path, so keep only the last component of either style
+    let name = name.rsplit(['/', '\\']).next().unwrap_or(name);
+
+    let cleaned: String = name
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let mut cleaned = cleaned.trim().trim_end_matches(['.', ' ']).to_string();
+    if cleaned.is_empty() || cleaned.chars().all(|c| c == '.') {
+        return None;
+    }
+
+    let stem = cleaned.split('.').next().unwrap_or_default();
+    if RESERVED_NAMES
+        .iter()
+        .any(|reserved| stem.eq_ignore_ascii_case(reserved))
+    {
+        cleaned.insert(0, '_');
+    }
+
+    if cleaned.len() > MAX_FILENAME_LEN {
+        // Shorten the stem but keep the extension
+        let extension = Path::new(&cleaned)
+            .extension()
+            .map(|ext| format!(".{}", ext.to_string_lossy()))
+            .filter(|ext| ext.len() < MAX_FILENAME_LEN / 2)
+            .unwrap_or_default();
+        let mut end = MAX_FILENAME_LEN - extension.len();
+        while !cleaned.is_char_boundary(end) {
+            end -= 1;
+        }
+        cleaned = format!("{}{}", &cleaned[..end], extension);
+    }
+
+    Some(cleaned)
+}
+
+/// Create a new file for `name` in `dir`, adding ` (1)`, ` (2)`, ... if the name is taken
+pub fn create_unique(dir: &Path, name: &str) -> io::Result<(PathBuf, File)> {
+    let path = Path::new(name);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+
+    for n in 0.. {
+        let candidate = match n {
+            0 => dir.join(name),
+            n => dir.join(format!("{} ({}){}", stem, n, extension)),
+        };
+        // create_new fails instead of overwriting, so concurrent uploads never clash
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&candidate)
+        {
+            Ok(file) => return Ok((candidate, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    unreachable!()
+}
+
+fn share_root() -> Result<PathBuf, String> {
+    let dir = config::get()
+        .file_transfer
+        .share_dir
+        .ok_or("No share directory configured")?;
+    dir.canonicalize()
+        .map_err(|e| format!("Share directory {} is unavailable: {}", dir.display(), e))
+}
+
+/// Resolve a path relative to the share directory, refusing anything outside of it
+pub fn resolve_share(relative: &str) -> Result<PathBuf, String> {
+    resolve_in(&share_root()?, relative)
+}
+
+/// Resolve `relative` below `root` (which must be canonical).
+///
+/// `..`, absolute paths and symlinks leading out of `root` are rejected.
+pub fn resolve_in(root: &Path, relative: &str) -> Result<PathBuf, String> {
+    let relative = Path::new(relative.trim_start_matches(['/', '\\']));
+    if relative
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir))
+    {
+        return Err("Invalid path".to_string());
+    }
+
+    let path = root
+        .join(relative)
+        .canonicalize()
+        .map_err(|_| "File not found".to_string())?;
+    if !path.starts_with(root) {
+        return Err("Invalid path".to_string());
+    }
+
+    Ok(path)
+}
+
+/// List a directory of the share, directories first
+pub fn list_share(relative: &str) -> Result<Vec<ShareEntry>, String> {
+    let root = share_root()?;
+    let dir = resolve_in(&root, relative)?;
+
+    let entries = fs::read_dir(&dir).map_err(|e| format!("Failed to read directory: {}", e))?;
+    let mut listing: Vec<ShareEntry> = entries
+        .flatten()
+        .filter_map(|entry| {
+            // Follows symlinks, so links pointing outside the share are hidden
+            let path = entry.path().canonicalize().ok()?;
+            if !path.starts_with(&root) {
+                return None;
+            }
+            let metadata = fs::metadata(&path).ok()?;
+            let relative = path.strip_prefix(&root).ok()?;
+
+            Some(ShareEntry {
+                name: entry.file_name().to_string_lossy().into_owned(),
+                path: relative
+                    .components()
+                    .map(|component| component.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/"),
+                is_dir: metadata.is_dir(),
+                size: if metadata.is_dir() { 0 } else { metadata.len() },
+                modified: metadata
+                    .modified()
+                    .ok()
+                    .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                    .map(|duration| duration.as_millis() as u64),
+            })
+        })
+        .collect();
+
+    listing.sort_by(|a, b| {
+        b.is_dir
+            .cmp(&a.is_dir)
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+    Ok(listing)
+}
+
+type ReceivedListener = Box<dyn Fn(&ReceivedFile) + Send + Sync>;
+
+static LISTENER: OnceLock<ReceivedListener> = OnceLock::new();
+
+/// Register the callback run whenever an upload completes (used to notify the desktop app)
+pub fn on_received<F: Fn(&ReceivedFile) + Send + Sync + 'static>(listener: F) {
+    let _ = LISTENER.set(Box::new(listener));
+}
+
+/// Report a completed upload
+pub fn received(file: ReceivedFile) {
+    info!(path = %file.path, size = file.size, "Received file");
+    if let Some(listener) = LISTENER.get() {
+        listener(&file);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn keeps_only_the_last_path_component() {
+        assert_eq!(
+            sanitize_filename("../../etc/passwd").as_deref(),
+            Some("passwd")
+        );
+        assert_eq!(sanitize_filename("/etc/passwd").as_deref(), Some("passwd"));
+        assert_eq!(
+            sanitize_filename("C:\\Users\\me\\report.pdf").as_deref(),
+            Some("report.pdf")
+        );
+        assert_eq!(
+            sanitize_filename("\\\\server\\share\\notes.txt").as_deref(),
+            Some("notes.txt")
+        );
+        // A drive-relative name keeps no drive
+        assert_eq!(
+            sanitize_filename("C:evil.txt").as_deref(),
+            Some("C_evil.txt")
+        );
+    }
+
+    #[test]
+    fn rejects_names_without_a_file_part() {
+        for name in ["", "   ", ".", "..", "...", "dir/", "dir/..", "C:\\"] {
+            assert_eq!(sanitize_filename(name), None, "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn replaces_unsafe_characters_and_trailing_dots() {
+        assert_eq!(
+            sanitize_filename("a<b>:c?\"d|e*.txt").as_deref(),
+            Some("a_b__c__d_e_.txt")
+        );
+        assert_eq!(
+            sanitize_filename("line\nbreak.txt").as_deref(),
+            Some("line_break.txt")
+        );
+        assert_eq!(
+            sanitize_filename(" report.pdf. . ").as_deref(),
+            Some("report.pdf")
+        );
+    }
+
+    #[test]
+    fn prefixes_reserved_device_names() {
+        assert_eq!(sanitize_filename("CON").as_deref(), Some("_CON"));
+        assert_eq!(sanitize_filename("nul.txt").as_deref(), Some("_nul.txt"));
+        assert_eq!(
+            sanitize_filename("Lpt9.tar.gz").as_deref(),
+            Some("_Lpt9.tar.gz")
+        );
+        // Only exact stems are reserved
+        assert_eq!(
+            sanitize_filename("console.txt").as_deref(),
+            Some("console.txt")
+        );
+        assert_eq!(sanitize_filename("COM10").as_deref(), Some("COM10"));
+    }
+
+    #[test]
+    fn shortens_long_names_but_keeps_the_extension() {
+        let name = sanitize_filename(&format!("{}.txt", "a".repeat(300))).unwrap();
+        assert_eq!(name.len(), MAX_FILENAME_LEN);
+        assert!(name.ends_with("a.txt"));
+
+        // Cut on a character boundary
+        let name = sanitize_filename(&format!("{}.txt", "é".repeat(150))).unwrap();
+        assert!(name.len() <= MAX_FILENAME_LEN);
+        assert!(name.ends_with("é.txt"));
+    }
+
+    #[test]
+    fn numbers_colliding_names() {
+        let dir = TempDir::new().unwrap();
+        let created: Vec<String> = ["report.pdf", "report.pdf", "report.pdf", "notes", "notes"]
+            .iter()
+            .map(|name| {
+                let (path, _) = create_unique(dir.path(), name).unwrap();
+                path.file_name().unwrap().to_string_lossy().into_owned()
+            })
+            .collect();
+
+        assert_eq!(
+            created,
+            [
+                "report.pdf",
+                "report (1).pdf",
+                "report (2).pdf",
+                "notes",
+                "notes (1)"
+            ]
+        );
+    }
+
+    fn share() -> (TempDir, PathBuf) {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().join("share");
+        fs::create_dir_all(root.join("docs")).unwrap();
+        fs::write(root.join("docs/readme.txt"), "hello").unwrap();
+        fs::write(dir.path().join("secret.txt"), "secret").unwrap();
+        let root = root.canonicalize().unwrap();
+        (dir, root)
+    }
+
+    #[test]
+    fn resolves_paths_inside_the_share() {
+        let (_dir, root) = share();
+        let file = root.join("docs/readme.txt");
+
+        assert_eq!(resolve_in(&root, "docs/readme.txt"), Ok(file.clone()));
+        assert_eq!(resolve_in(&root, "/docs/./readme.txt"), Ok(file));
+        assert_eq!(resolve_in(&root, ""), Ok(root.clone()));
+        assert_eq!(
+            resolve_in(&root, "docs/missing.txt"),
+            Err("File not found".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_paths_leaving_the_share() {
+        let (dir, root) = share();
+        let outside = dir.path().join("secret.txt");
+
+        for relative in [
+            "..",
+            "../secret.txt",
+            "docs/../../secret.txt",
+            "docs\\..\\..\\secret.txt",
+            "C:\\Windows\\win.ini",
+            "\\\\server\\share\\file.txt",
+            outside.to_str().unwrap(),
+        ] {
+            assert!(resolve_in(&root, relative).is_err(), "{:?}", relative);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_symlinks_leaving_the_share() {
+        let (dir, root) = share();
+        std::os::unix::fs::symlink(dir.path(), root.join("escape")).unwrap();
+        std::os::unix::fs::symlink(root.join("docs"), root.join("inside")).unwrap();
+
+        assert_eq!(
+            resolve_in(&root, "escape/secret.txt"),
+            Err("Invalid path".to_string())
+        );
+        assert!(resolve_in(&root, "inside/readme.txt").is_ok());
+    }
+}
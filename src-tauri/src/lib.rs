@@ -1,28 +1,97 @@
-use local_ip_address::local_ip;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{Emitter, Manager, WebviewUrl, WebviewWindowBuilder};
+use tauri_plugin_opener::OpenerExt;
+use tracing::{error, info};
+
+pub mod access;
+pub mod actions;
+pub mod audit;
+pub mod brightness;
+pub mod clipboard;
+pub mod config;
+pub mod discovery;
+pub mod files;
+pub mod logging;
+pub mod metrics;
+pub mod network;
+pub mod notify;
+pub mod open_url;
+pub mod peers;
+pub mod power;
+pub mod power_rules;
+pub mod processes;
+pub mod rate_limit;
+pub mod roles;
+pub mod runs;
+pub mod schedules;
+pub mod screen;
+pub mod screen_stream;
+pub mod speech;
+pub mod system_status;
+pub mod wol;
 
 #[cfg(target_os = "windows")]
 mod volume_control;
 
+/// Label of the window showing the shutdown countdown
+const SHUTDOWN_WARNING_WINDOW: &str = "shutdown-warning";
+
 #[derive(Serialize, Deserialize)]
 struct CommandResponse {
     success: bool,
     message: String,
 }
 
+/// Record a desktop command in the audit log and pass its response through
+fn audited(action: &str, params: serde_json::Value, response: CommandResponse) -> CommandResponse {
+    audit::record_action(
+        audit::Origin::Desktop,
+        action,
+        params,
+        response.success,
+        &response.message,
+    );
+    response
+}
+
+/// Like `audited`, for commands that return a `Result`
+fn audited_result<T>(
+    action: &str,
+    params: serde_json::Value,
+    result: Result<T, String>,
+) -> Result<T, String> {
+    let message = result.as_ref().err().map_or("Done", String::as_str);
+    audit::record_action(
+        audit::Origin::Desktop,
+        action,
+        params,
+        result.is_ok(),
+        message,
+    );
+    result
+}
+
 #[tauri::command]
 fn shutdown() -> CommandResponse {
-    println!("Shutdown request received via Tauri");
+    info!("Shutdown request received via Tauri");
+    audited("shutdown", json!({}), perform_shutdown())
+}
 
+#[tracing::instrument]
+fn perform_shutdown() -> CommandResponse {
     let result = if cfg!(target_os = "windows") {
-        Command::new("shutdown").args(["/s", "/t", "60"]).spawn()
+        runs::spawn("shutdown", Command::new("shutdown").args(["/s", "/t", "60"]))
     } else if cfg!(target_os = "linux") {
-        Command::new("shutdown").args(["-h", "+1"]).spawn()
+        runs::spawn("shutdown", Command::new("shutdown").args(["-h", "+1"]))
     } else if cfg!(target_os = "macos") {
-        Command::new("sh")
-            .args(["-c", "sleep 60 && osascript -e 'tell app \"System Events\" to shut down' &"])
-            .spawn()
+        runs::spawn(
+            "shutdown",
+            Command::new("sh")
+                .args(["-c", "sleep 60 && osascript -e 'tell app \"System Events\" to shut down' &"]),
+        )
     } else {
         return CommandResponse {
             success: false,
@@ -31,10 +100,13 @@ fn shutdown() -> CommandResponse {
     };
 
     match result {
-        Ok(_) => CommandResponse {
-            success: true,
-            message: "Shutdown command executed".to_string(),
-        },
+        Ok(_) => {
+            power::mark_shutdown_pending();
+            CommandResponse {
+                success: true,
+                message: "Shutdown command executed".to_string(),
+            }
+        }
         Err(e) => CommandResponse {
             success: false,
             message: format!("Failed to execute shutdown: {}", e),
@@ -44,14 +116,18 @@ fn shutdown() -> CommandResponse {
 
 #[tauri::command]
 fn restart() -> CommandResponse {
-    println!("Restart request received via Tauri");
+    info!("Restart request received via Tauri");
+    audited("restart", json!({}), perform_restart())
+}
 
+#[tracing::instrument]
+fn perform_restart() -> CommandResponse {
     let result = if cfg!(target_os = "windows") {
-        Command::new("shutdown").args(["/r", "/t", "0"]).spawn()
+        runs::spawn("restart", Command::new("shutdown").args(["/r", "/t", "0"]))
     } else if cfg!(target_os = "linux") {
-        Command::new("shutdown").args(["-r", "now"]).spawn()
+        runs::spawn("restart", Command::new("shutdown").args(["-r", "now"]))
     } else if cfg!(target_os = "macos") {
-        Command::new("shutdown").args(["-r", "now"]).spawn()
+        runs::spawn("restart", Command::new("shutdown").args(["-r", "now"]))
     } else {
         return CommandResponse {
             success: false,
@@ -73,16 +149,18 @@ fn restart() -> CommandResponse {
 
 #[tauri::command]
 fn cancel_shutdown() -> CommandResponse {
-    println!("Cancel shutdown request received via Tauri");
+    info!("Cancel shutdown request received via Tauri");
+    audited("cancel_shutdown", json!({}), perform_cancel_shutdown())
+}
 
+#[tracing::instrument]
+fn perform_cancel_shutdown() -> CommandResponse {
     let result = if cfg!(target_os = "windows") {
-        Command::new("shutdown").args(["/a"]).spawn()
+        runs::spawn("cancel_shutdown", Command::new("shutdown").args(["/a"]))
     } else if cfg!(target_os = "linux") {
-        Command::new("shutdown").args(["-c"]).spawn()
+        runs::spawn("cancel_shutdown", Command::new("shutdown").args(["-c"]))
     } else if cfg!(target_os = "macos") {
-        Command::new("pkill")
-            .args(["-f", "sleep 60 && osascript"])
-            .spawn()
+        runs::spawn("cancel_shutdown", Command::new("pkill").args(["-f", "sleep 60 && osascript"]))
     } else {
         return CommandResponse {
             success: false,
@@ -91,10 +169,13 @@ fn cancel_shutdown() -> CommandResponse {
     };
 
     match result {
-        Ok(_) => CommandResponse {
-            success: true,
-            message: "Shutdown cancelled".to_string(),
-        },
+        Ok(_) => {
+            power::clear_shutdown_pending();
+            CommandResponse {
+                success: true,
+                message: "Shutdown cancelled".to_string(),
+            }
+        }
         Err(e) => CommandResponse {
             success: false,
             message: format!("Failed to cancel shutdown: {}", e),
@@ -104,16 +185,21 @@ fn cancel_shutdown() -> CommandResponse {
 
 #[tauri::command]
 fn sleep() -> CommandResponse {
-    println!("Sleep request received via Tauri");
+    info!("Sleep request received via Tauri");
+    audited("sleep", json!({}), perform_sleep())
+}
 
+#[tracing::instrument]
+fn perform_sleep() -> CommandResponse {
     let result = if cfg!(target_os = "windows") {
-        Command::new("rundll32.exe")
-            .args(["powrprof.dll,SetSuspendState", "0,1,0"])
-            .spawn()
+        runs::spawn(
+            "sleep",
+            Command::new("rundll32.exe").args(["powrprof.dll,SetSuspendState", "0,1,0"]),
+        )
     } else if cfg!(target_os = "linux") {
-        Command::new("systemctl").args(["suspend"]).spawn()
+        runs::spawn("sleep", Command::new("systemctl").args(["suspend"]))
     } else if cfg!(target_os = "macos") {
-        Command::new("pmset").args(["sleepnow"]).spawn()
+        runs::spawn("sleep", Command::new("pmset").args(["sleepnow"]))
     } else {
         return CommandResponse {
             success: false,
@@ -136,9 +222,9 @@ fn sleep() -> CommandResponse {
 #[tauri::command]
 fn increase_volume(amount: Option<i32>) -> CommandResponse {
     let volume_change = amount.unwrap_or(2);
-    println!("Increase volume request received via Tauri (amount: {})", volume_change);
+    info!(amount = volume_change, "Increase volume request received via Tauri");
 
-    if cfg!(target_os = "windows") {
+    let response = if cfg!(target_os = "windows") {
         #[cfg(target_os = "windows")]
         {
             match volume_control::VolumeControl::increase_volume(volume_change) {
@@ -209,15 +295,17 @@ fn increase_volume(amount: Option<i32>) -> CommandResponse {
             success: false,
             message: "Unsupported operating system".to_string(),
         }
-    }
+    };
+    let params = json!({ "amount": volume_change });
+    audited("increase_volume", params, response)
 }
 
 #[tauri::command]
 fn decrease_volume(amount: Option<i32>) -> CommandResponse {
     let volume_change = amount.unwrap_or(2);
-    println!("Decrease volume request received via Tauri (amount: {})", volume_change);
+    info!(amount = volume_change, "Decrease volume request received via Tauri");
 
-    if cfg!(target_os = "windows") {
+    let response = if cfg!(target_os = "windows") {
         #[cfg(target_os = "windows")]
         {
             match volume_control::VolumeControl::decrease_volume(volume_change) {
@@ -288,23 +376,29 @@ fn decrease_volume(amount: Option<i32>) -> CommandResponse {
             success: false,
             message: "Unsupported operating system".to_string(),
         }
-    }
+    };
+    let params = json!({ "amount": volume_change });
+    audited("decrease_volume", params, response)
 }
 
 #[tauri::command]
 fn get_volume() -> Result<i32, String> {
-    println!("Get volume request received via Tauri");
+    info!("Get volume request received via Tauri");
+    read_volume()
+}
 
+/// Read the current master volume (0-100)
+pub fn read_volume() -> Result<i32, String> {
     if cfg!(target_os = "windows") {
         #[cfg(target_os = "windows")]
         {
             match volume_control::VolumeControl::get_volume() {
                 Ok(volume) => {
-                    println!("Successfully retrieved volume: {}%", volume);
+                    tracing::debug!(volume, "Retrieved volume");
                     Ok(volume)
                 },
                 Err(e) => {
-                    println!("Failed to get volume: {}", e);
+                    tracing::warn!("Failed to get volume: {}", e);
                     Err(format!("Failed to get volume: {}", e))
                 }
             }
@@ -385,8 +479,17 @@ fn get_volume() -> Result<i32, String> {
 
 #[tauri::command]
 fn set_volume(level: i32) -> CommandResponse {
+    info!(
+        level = level.clamp(0, 100),
+        "Set volume request received via Tauri"
+    );
+    let params = json!({ "level": level });
+    audited("set_volume", params, perform_set_volume(level))
+}
+
+#[tracing::instrument]
+fn perform_set_volume(level: i32) -> CommandResponse {
     let volume_level = level.clamp(0, 100);
-    println!("Set volume request received via Tauri: {}%", volume_level);
 
     if cfg!(target_os = "windows") {
         #[cfg(target_os = "windows")]
@@ -460,13 +563,699 @@ fn set_volume(level: i32) -> CommandResponse {
 }
 
 #[tauri::command]
-fn get_local_ip() -> String {
-    match local_ip() {
-        Ok(ip) => ip.to_string(),
-        Err(_) => "Unable to get IP".to_string(),
+fn get_brightness() -> Result<Vec<brightness::DisplayBrightness>, String> {
+    info!("Get brightness request received via Tauri");
+    brightness::list()
+}
+
+#[tauri::command]
+fn set_brightness(level: i32, display: Option<String>) -> CommandResponse {
+    info!(level, "Set brightness request received via Tauri");
+    let params = json!({ "level": level, "display": display });
+    let response = perform_set_brightness(level, display);
+    audited("set_brightness", params, response)
+}
+
+#[tracing::instrument(skip(display))]
+fn perform_set_brightness(level: i32, display: Option<String>) -> CommandResponse {
+    match brightness::set(display.as_deref(), level) {
+        Ok(displays) => CommandResponse {
+            success: true,
+            message: format!(
+                "Brightness set to {}% on {} display(s)",
+                level.clamp(0, 100),
+                displays.len()
+            ),
+        },
+        Err(message) => CommandResponse {
+            success: false,
+            message,
+        },
+    }
+}
+
+#[tauri::command]
+fn get_clipboard() -> Result<clipboard::ClipboardContent, String> {
+    info!("Get clipboard request received via Tauri");
+    clipboard::get()
+}
+
+#[tauri::command]
+fn set_clipboard_text(text: String) -> CommandResponse {
+    info!("Set clipboard request received via Tauri");
+
+    let response = match clipboard::set_text(&text) {
+        Ok(()) => CommandResponse {
+            success: true,
+            message: format!("Copied {} characters to the clipboard", text.chars().count()),
+        },
+        Err(message) => CommandResponse {
+            success: false,
+            message,
+        },
+    };
+    let params = json!({ "chars": text.chars().count() });
+    audited("set_clipboard_text", params, response)
+}
+
+#[tauri::command]
+fn get_clipboard_image() -> Result<Vec<u8>, String> {
+    info!("Get clipboard image request received via Tauri");
+    clipboard::get_image_png()
+}
+
+#[tauri::command]
+fn set_clipboard_image(png: Vec<u8>) -> CommandResponse {
+    info!("Set clipboard image request received via Tauri");
+
+    let response = match clipboard::set_image(&png) {
+        Ok(()) => CommandResponse {
+            success: true,
+            message: "Image copied to the clipboard".to_string(),
+        },
+        Err(message) => CommandResponse {
+            success: false,
+            message,
+        },
+    };
+    let params = json!({ "bytes": png.len() });
+    audited("set_clipboard_image", params, response)
+}
+
+#[tauri::command]
+fn get_local_ip() -> Result<Vec<network::NetworkInterface>, String> {
+    network::interfaces()
+}
+
+#[tauri::command]
+fn set_preferred_interface(name: Option<String>) -> CommandResponse {
+    let params = json!({ "name": name });
+    let response = match network::set_preferred_interface(name) {
+        Ok(()) => CommandResponse {
+            success: true,
+            message: "Preferred interface saved".to_string(),
+        },
+        Err(message) => CommandResponse {
+            success: false,
+            message,
+        },
+    };
+    audited("set_preferred_interface", params, response)
+}
+
+#[tauri::command]
+fn list_processes() -> Vec<processes::ProcessInfo> {
+    processes::list()
+}
+
+#[tauri::command]
+fn kill_process(pid: Option<u32>, name: Option<String>) -> CommandResponse {
+    info!(?pid, ?name, "Kill process request received via Tauri");
+
+    let params = json!({ "pid": pid, "name": name });
+    let result = match (pid, name) {
+        (Some(pid), _) => processes::kill_pid(pid).map_err(|e| e.to_string()),
+        (None, Some(name)) => processes::kill_name(&name).map_err(|e| e.to_string()),
+        (None, None) => Err("Either pid or name is required".to_string()),
+    };
+
+    let response = match result {
+        Ok(message) => CommandResponse {
+            success: true,
+            message,
+        },
+        Err(message) => CommandResponse {
+            success: false,
+            message,
+        },
+    };
+    audited("kill_process", params, response)
+}
+
+#[tauri::command]
+fn launch_application(name: String) -> CommandResponse {
+    info!(%name, "Launch request received via Tauri");
+
+    let response = match processes::launch(&name) {
+        Ok(pid) => CommandResponse {
+            success: true,
+            message: format!("Launched {} (PID {})", name, pid),
+        },
+        Err(e) => CommandResponse {
+            success: false,
+            message: e.to_string(),
+        },
+    };
+    let params = json!({ "name": name });
+    audited("launch_application", params, response)
+}
+
+#[tauri::command]
+fn get_launch_allowlist() -> Vec<config::LaunchEntry> {
+    config::get().launch_allowlist
+}
+
+#[tauri::command]
+fn set_launch_allowlist(entries: Vec<config::LaunchEntry>) -> CommandResponse {
+    let params = json!({ "entries": entries });
+    let response = match config::update(|config| config.launch_allowlist = entries) {
+        Ok(_) => CommandResponse {
+            success: true,
+            message: "Launch allowlist saved".to_string(),
+        },
+        Err(message) => CommandResponse {
+            success: false,
+            message,
+        },
+    };
+    audited("set_launch_allowlist", params, response)
+}
+
+#[tauri::command]
+fn get_api_allowlist() -> Vec<String> {
+    access::allowlist()
+}
+
+#[tauri::command]
+fn set_api_allowlist(ranges: Vec<String>) -> CommandResponse {
+    info!("Set API allowlist request received via Tauri");
+
+    let params = json!({ "ranges": ranges });
+    let response = match access::set_allowlist(ranges) {
+        Ok(()) => CommandResponse {
+            success: true,
+            message: "API allowlist saved".to_string(),
+        },
+        Err(message) => CommandResponse {
+            success: false,
+            message,
+        },
+    };
+    audited("set_api_allowlist", params, response)
+}
+
+#[tauri::command]
+fn list_api_denials() -> Vec<access::Denial> {
+    access::denials()
+}
+
+#[tauri::command]
+fn get_rate_limit_stats() -> Vec<rate_limit::ClientStats> {
+    rate_limit::stats()
+}
+
+#[tauri::command]
+fn unlock_client(ip: String) -> CommandResponse {
+    info!(%ip, "Unlock client request received via Tauri");
+
+    let response = match ip.parse() {
+        Ok(address) if rate_limit::unlock(address) => CommandResponse {
+            success: true,
+            message: format!("{} unlocked", ip),
+        },
+        Ok(_) => CommandResponse {
+            success: false,
+            message: format!("No requests seen from {}", ip),
+        },
+        Err(_) => CommandResponse {
+            success: false,
+            message: format!("Invalid address {}", ip),
+        },
+    };
+    let params = json!({ "ip": ip });
+    audited("unlock_client", params, response)
+}
+
+#[tauri::command]
+fn get_device_roles() -> config::RolesConfig {
+    roles::settings()
+}
+
+#[tauri::command]
+fn set_device_roles(settings: config::RolesConfig) -> CommandResponse {
+    info!("Set device roles request received via Tauri");
+
+    let params = json!(settings);
+    let response = match roles::set_settings(settings) {
+        Ok(()) => CommandResponse {
+            success: true,
+            message: "Device roles saved".to_string(),
+        },
+        Err(message) => CommandResponse {
+            success: false,
+            message,
+        },
+    };
+    audited("set_device_roles", params, response)
+}
+
+#[tauri::command]
+fn get_file_transfer_config() -> config::FileTransferConfig {
+    info!("Get file transfer config request received via Tauri");
+    config::get().file_transfer
+}
+
+#[tauri::command]
+fn set_file_transfer_config(file_transfer: config::FileTransferConfig) -> CommandResponse {
+    info!("Set file transfer config request received via Tauri");
+
+    let params = json!(file_transfer);
+    let response = match config::update(|config| config.file_transfer = file_transfer) {
+        Ok(_) => CommandResponse {
+            success: true,
+            message: format!(
+                "File transfer settings saved, uploads go to {}",
+                files::inbox_dir().display()
+            ),
+        },
+        Err(message) => CommandResponse {
+            success: false,
+            message,
+        },
+    };
+    audited("set_file_transfer_config", params, response)
+}
+
+#[tauri::command]
+fn respond_open_url(id: u64, approved: bool) -> CommandResponse {
+    info!(id, approved, "Open URL confirmation received via Tauri");
+
+    let response = if open_url::respond(id, approved) {
+        CommandResponse {
+            success: true,
+            message: if approved { "URL approved" } else { "URL declined" }.to_string(),
+        }
+    } else {
+        CommandResponse {
+            success: false,
+            message: "The request has expired".to_string(),
+        }
+    };
+    let params = json!({ "id": id, "approved": approved });
+    audited("respond_open_url", params, response)
+}
+
+#[tauri::command]
+fn get_open_url_config() -> config::OpenUrlConfig {
+    info!("Get open URL config request received via Tauri");
+    config::get().open_url
+}
+
+#[tauri::command]
+fn set_open_url_config(open_url: config::OpenUrlConfig) -> CommandResponse {
+    info!("Set open URL config request received via Tauri");
+
+    let params = json!(open_url);
+    let response = match config::update(|config| config.open_url = open_url) {
+        Ok(config) => CommandResponse {
+            success: true,
+            message: format!(
+                "Allowed schemes: {}",
+                config.open_url.allowed_schemes.join(", ")
+            ),
+        },
+        Err(message) => CommandResponse {
+            success: false,
+            message,
+        },
+    };
+    audited("set_open_url_config", params, response)
+}
+
+#[tauri::command]
+fn send_notification(
+    title: String,
+    body: Option<String>,
+    urgency: Option<notify::Urgency>,
+) -> CommandResponse {
+    info!(%title, "Notification request received via Tauri");
+
+    let response = match notify::send(
+        &title,
+        body.as_deref().unwrap_or_default(),
+        urgency.unwrap_or_default(),
+    ) {
+        Ok(()) => CommandResponse {
+            success: true,
+            message: "Notification shown".to_string(),
+        },
+        Err(e) => CommandResponse {
+            success: false,
+            message: e.to_string(),
+        },
+    };
+    let params = json!({ "title": title, "body": body });
+    audited("send_notification", params, response)
+}
+
+#[tauri::command]
+fn speak(text: String, voice: Option<String>, rate: Option<i32>) -> CommandResponse {
+    info!("Speak request received via Tauri");
+
+    let response = match speech::speak(&text, &speech::SpeakOptions { voice, rate }) {
+        Ok(Some(id)) => CommandResponse {
+            success: true,
+            message: format!("Speaking (run {})", id),
+        },
+        Ok(None) => CommandResponse {
+            success: true,
+            message: "Speaking".to_string(),
+        },
+        Err(message) => CommandResponse {
+            success: false,
+            message,
+        },
+    };
+    let params = json!({ "text": text });
+    audited("speak", params, response)
+}
+
+#[derive(Serialize)]
+struct ShutdownWarning {
+    /// When the PC shuts down, in unix milliseconds
+    deadline: u64,
+    message: String,
+}
+
+#[tauri::command]
+fn get_shutdown_warning() -> Option<ShutdownWarning> {
+    let deadline = power::pending_shutdown()?;
+
+    Some(ShutdownWarning {
+        deadline: deadline
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis() as u64)
+            .unwrap_or_default(),
+        message: power::shutdown_message(),
+    })
+}
+
+#[tauri::command]
+fn set_shutdown_warning(enabled: bool, message: Option<String>) -> CommandResponse {
+    info!("Set shutdown warning request received via Tauri");
+
+    let params = json!({ "enabled": enabled, "message": message });
+    let response = match config::update(|config| {
+        config.shutdown_warning = enabled;
+        config.shutdown_message = message;
+    }) {
+        Ok(_) => CommandResponse {
+            success: true,
+            message: if enabled {
+                format!("Shutdown warning enabled: {}", power::shutdown_message())
+            } else {
+                "Shutdown warning disabled".to_string()
+            },
+        },
+        Err(message) => CommandResponse {
+            success: false,
+            message,
+        },
+    };
+    audited("set_shutdown_warning", params, response)
+}
+
+#[tauri::command]
+fn list_power_rules() -> Vec<power_rules::RuleStatus> {
+    power_rules::list()
+}
+
+#[tauri::command]
+fn add_power_rule(
+    condition: power_rules::Condition,
+    action: power_rules::PowerAction,
+) -> CommandResponse {
+    info!("Power rule request received via Tauri");
+
+    let params = json!({ "condition": condition, "action": action });
+    let response = match power_rules::add(condition, action) {
+        Ok(rule) => CommandResponse {
+            success: true,
+            message: format!("Power rule {} added", rule.id),
+        },
+        Err(message) => CommandResponse {
+            success: false,
+            message,
+        },
+    };
+    audited("add_power_rule", params, response)
+}
+
+#[tauri::command]
+fn remove_power_rule(id: u64) -> CommandResponse {
+    let response = if power_rules::remove(id) {
+        CommandResponse {
+            success: true,
+            message: format!("Power rule {} removed", id),
+        }
+    } else {
+        CommandResponse {
+            success: false,
+            message: format!("Power rule {} not found", id),
+        }
+    };
+    let params = json!({ "id": id });
+    audited("remove_power_rule", params, response)
+}
+
+#[tauri::command]
+fn list_schedules() -> Vec<schedules::ScheduleStatus> {
+    schedules::list()
+}
+
+#[tauri::command]
+fn create_schedule(schedule: schedules::ScheduleSpec) -> Result<schedules::ScheduleStatus, String> {
+    info!(name = %schedule.name, "Create schedule request received via Tauri");
+    let params = json!(schedule);
+    audited_result("create_schedule", params, schedules::create(schedule))
+}
+
+#[tauri::command]
+fn update_schedule(
+    id: u64,
+    schedule: schedules::ScheduleSpec,
+) -> Result<schedules::ScheduleStatus, String> {
+    info!(id, "Update schedule request received via Tauri");
+    let params = json!({ "id": id, "schedule": schedule });
+    let result = schedules::update(id, schedule)
+        .and_then(|updated| updated.ok_or_else(|| format!("Schedule {} not found", id)));
+    audited_result("update_schedule", params, result)
+}
+
+#[tauri::command]
+fn delete_schedule(id: u64) -> CommandResponse {
+    info!(id, "Delete schedule request received via Tauri");
+
+    let response = match schedules::delete(id) {
+        Ok(true) => CommandResponse {
+            success: true,
+            message: format!("Schedule {} deleted", id),
+        },
+        Ok(false) => CommandResponse {
+            success: false,
+            message: format!("Schedule {} not found", id),
+        },
+        Err(message) => CommandResponse {
+            success: false,
+            message,
+        },
+    };
+    let params = json!({ "id": id });
+    audited("delete_schedule", params, response)
+}
+
+#[tauri::command]
+fn preview_schedule(cron: String) -> Result<Vec<u64>, String> {
+    schedules::preview(&cron, 5)
+}
+
+#[tauri::command]
+fn get_wol_machines() -> Vec<config::WolMachine> {
+    wol::machines()
+}
+
+#[tauri::command]
+fn set_wol_machines(machines: Vec<config::WolMachine>) -> CommandResponse {
+    let params = json!({ "machines": machines });
+    let response = match wol::set_machines(machines) {
+        Ok(()) => CommandResponse {
+            success: true,
+            message: "Wake-on-LAN machines saved".to_string(),
+        },
+        Err(message) => CommandResponse {
+            success: false,
+            message,
+        },
+    };
+    audited("set_wol_machines", params, response)
+}
+
+#[tauri::command]
+fn wake_machine(name: String) -> CommandResponse {
+    info!(%name, "Wake-on-LAN request received via Tauri");
+
+    let response = match wol::wake(&name) {
+        Ok(message) => CommandResponse {
+            success: true,
+            message,
+        },
+        Err(e) => CommandResponse {
+            success: false,
+            message: e.to_string(),
+        },
+    };
+    let params = json!({ "name": name });
+    audited("wake_machine", params, response)
+}
+
+#[tauri::command]
+fn get_peers() -> Vec<config::Peer> {
+    peers::peers()
+}
+
+#[tauri::command]
+fn set_peers(peers: Vec<config::Peer>) -> CommandResponse {
+    let params = json!({ "peers": peers.iter().map(|peer| &peer.name).collect::<Vec<_>>() });
+    let response = match peers::set_peers(peers) {
+        Ok(()) => CommandResponse {
+            success: true,
+            message: "Peers saved".to_string(),
+        },
+        Err(message) => CommandResponse {
+            success: false,
+            message,
+        },
+    };
+    audited("set_peers", params, response)
+}
+
+#[tauri::command]
+async fn list_peer_status() -> Vec<peers::PeerStatus> {
+    // Async so waiting on slow peers does not block the main thread
+    peers::statuses()
+}
+
+#[tauri::command]
+async fn run_peer_action(name: String, action: peers::PeerAction) -> CommandResponse {
+    let response = match peers::run(&name, &action) {
+        Ok(message) => CommandResponse {
+            success: true,
+            message,
+        },
+        Err(e) => CommandResponse {
+            success: false,
+            message: e.to_string(),
+        },
+    };
+    let params = json!({ "name": name, "action": action });
+    audited("run_peer_action", params, response)
+}
+
+#[tauri::command]
+async fn run_bulk_peer_action(
+    group: Option<String>,
+    peers: Option<Vec<String>>,
+    action: peers::PeerAction,
+) -> Result<Vec<peers::ActionResult>, String> {
+    let params = json!({ "group": group, "peers": peers, "action": action });
+    let results = match peers::run_bulk(group.as_deref(), &peers.unwrap_or_default(), &action) {
+        Ok(results) => results,
+        Err(message) => return audited_result("run_bulk_peer_action", params, Err(message)),
+    };
+
+    let succeeded = results.iter().filter(|result| result.success).count();
+    audit::record_action(
+        audit::Origin::Desktop,
+        "run_bulk_peer_action",
+        params,
+        succeeded == results.len(),
+        &format!("{} of {} peers succeeded", succeeded, results.len()),
+    );
+    Ok(results)
+}
+
+#[tauri::command]
+async fn discover_instances(
+    seconds: Option<u64>,
+) -> Result<Vec<discovery::DiscoveredInstance>, String> {
+    let duration = Duration::from_secs(seconds.unwrap_or(3));
+    // Browsing blocks for the whole duration, so keep it off the async workers
+    tauri::async_runtime::spawn_blocking(move || discovery::browse(duration))
+        .await
+        .map_err(|e| format!("Failed to browse via mDNS: {}", e))?
+}
+
+#[tauri::command]
+fn query_audit(query: audit::AuditQuery) -> Vec<audit::AuditEntry> {
+    audit::query(&query)
+}
+
+#[tauri::command]
+fn open_log_folder(app: tauri::AppHandle) -> CommandResponse {
+    let dir = logging::log_dir();
+    match app.opener().open_path(dir.to_string_lossy(), None::<&str>) {
+        Ok(()) => CommandResponse {
+            success: true,
+            message: format!("Opened {}", dir.display()),
+        },
+        Err(e) => CommandResponse {
+            success: false,
+            message: format!("Failed to open {}: {}", dir.display(), e),
+        },
+    }
+}
+
+/// Combine the log files into one file in the Downloads folder, returning its path
+#[tauri::command]
+fn export_logs() -> Result<String, String> {
+    let dir = dirs::download_dir()
+        .or_else(dirs::home_dir)
+        .ok_or("No Downloads folder to export to")?;
+    logging::export(&dir).map(|path| path.display().to_string())
+}
+
+/// Show (or bring forward) the countdown window and a native notification
+fn show_shutdown_warning(handle: &tauri::AppHandle, deadline: SystemTime) {
+    let seconds = deadline
+        .duration_since(SystemTime::now())
+        .map(|remaining| remaining.as_secs())
+        .unwrap_or_default();
+    let _ = notify::show(
+        &format!("Shutting down in {} seconds", seconds),
+        &power::shutdown_message(),
+        notify::Urgency::Critical,
+    );
+
+    if let Some(window) = handle.get_webview_window(SHUTDOWN_WARNING_WINDOW) {
+        let _ = handle.emit_to(SHUTDOWN_WARNING_WINDOW, "shutdown-warning-changed", ());
+        let _ = window.show();
+        let _ = window.set_focus();
+        return;
+    }
+
+    let built = WebviewWindowBuilder::new(
+        handle,
+        SHUTDOWN_WARNING_WINDOW,
+        WebviewUrl::App("index.html#shutdown-warning".into()),
+    )
+    .title("Shutdown scheduled")
+    .inner_size(440.0, 300.0)
+    .resizable(false)
+    .always_on_top(true)
+    .center()
+    .focused(true)
+    .build();
+
+    if let Err(e) = built {
+        error!("Failed to open shutdown warning: {}", e);
     }
 }
 
+#[tauri::command]
+fn get_system_status() -> system_status::SystemStatus {
+    system_status::status()
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -482,8 +1271,102 @@ pub fn run() {
             decrease_volume,
             get_volume,
             set_volume,
-            get_local_ip
+            get_brightness,
+            set_brightness,
+            get_clipboard,
+            set_clipboard_text,
+            get_clipboard_image,
+            set_clipboard_image,
+            get_local_ip,
+            set_preferred_interface,
+            list_processes,
+            kill_process,
+            launch_application,
+            get_launch_allowlist,
+            set_launch_allowlist,
+            get_api_allowlist,
+            set_api_allowlist,
+            list_api_denials,
+            get_rate_limit_stats,
+            unlock_client,
+            get_device_roles,
+            set_device_roles,
+            get_file_transfer_config,
+            set_file_transfer_config,
+            respond_open_url,
+            get_open_url_config,
+            set_open_url_config,
+            send_notification,
+            speak,
+            get_shutdown_warning,
+            set_shutdown_warning,
+            list_power_rules,
+            add_power_rule,
+            remove_power_rule,
+            list_schedules,
+            create_schedule,
+            update_schedule,
+            delete_schedule,
+            preview_schedule,
+            get_wol_machines,
+            set_wol_machines,
+            wake_machine,
+            get_peers,
+            set_peers,
+            list_peer_status,
+            run_peer_action,
+            run_bulk_peer_action,
+            discover_instances,
+            query_audit,
+            open_log_folder,
+            export_logs,
+            get_system_status
         ])
+        .setup(|app| {
+            // Let the desktop window know when a file arrives from the web UI
+            let handle = app.handle().clone();
+            files::on_received(move |file| {
+                let _ = handle.emit("file-received", file);
+            });
+
+            // URLs sent from the web UI are opened with tauri-plugin-opener
+            let handle = app.handle().clone();
+            open_url::set_opener(move |url| {
+                handle
+                    .opener()
+                    .open_url(url, None::<&str>)
+                    .map_err(|e| format!("Failed to open URL: {}", e))
+            });
+
+            // Bring the window forward so the confirmation prompt is seen
+            let handle = app.handle().clone();
+            open_url::set_prompt(move |request| {
+                if let Some(window) = handle.get_webview_window("main") {
+                    let _ = window.show();
+                    let _ = window.set_focus();
+                }
+                let _ = handle.emit("open-url-request", request);
+            });
+
+            // Warn whoever is at the PC when a shutdown gets scheduled
+            let handle = app.handle().clone();
+            power::on_shutdown_change(move |deadline| {
+                let handle = handle.clone();
+                // Windows must not be created on the thread of a synchronous command
+                std::thread::spawn(move || match deadline {
+                    Some(deadline) if config::get().shutdown_warning => {
+                        show_shutdown_warning(&handle, deadline)
+                    }
+                    Some(_) => {}
+                    None => {
+                        if let Some(window) = handle.get_webview_window(SHUTDOWN_WARNING_WINDOW) {
+                            let _ = window.close();
+                        }
+                    }
+                });
+            });
+            Ok(())
+        })
         .on_window_event(|window, event| {
             if let tauri::WindowEvent::CloseRequested { api, .. } = event {
                 api.prevent_close();
@@ -0,0 +1,132 @@
+use crate::config;
+use chrono::Local;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tracing::Subscriber;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, EnvFilter, Layer};
+
+/// Log files are named `ferrous-control.YYYY-MM-DD.log`
+const FILE_PREFIX: &str = "ferrous-control";
+const FILE_SUFFIX: &str = "log";
+
+/// Keeps the file writer's background thread alive so buffered lines get written
+static FILE_GUARD: OnceLock<WorkerGuard> = OnceLock::new();
+
+/// Directory holding the log files
+pub fn log_dir() -> PathBuf {
+    config::config_dir().join("logs")
+}
+
+/// Both outputs share the formatted span fields, so neither uses colors; they
+/// would otherwise end up as escape codes in the file
+fn format_layer<S, W>(json: bool, writer: W) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = fmt::layer().with_ansi(false).with_writer(writer);
+    if json {
+        layer
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .boxed()
+    } else {
+        layer.boxed()
+    }
+}
+
+/// Send log events to stderr and to a daily log file.
+///
+/// Release builds have no console on Windows, so the file is the only place
+/// their logs end up.
+pub fn init() {
+    let settings = config::get().logging;
+    let filter = EnvFilter::try_from_default_env()
+        .or_else(|_| EnvFilter::try_new(&settings.level))
+        .unwrap_or_else(|_| EnvFilter::new("info"));
+
+    // The appender lists the directory to prune old files, so it must exist
+    let _ = fs::create_dir_all(log_dir());
+    let file = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(FILE_PREFIX)
+        .filename_suffix(FILE_SUFFIX)
+        .max_log_files(settings.max_files.max(1))
+        .build(log_dir());
+    let (file_layer, file_error) = match file {
+        Ok(appender) => {
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            let _ = FILE_GUARD.set(guard);
+            (Some(format_layer(settings.json, writer)), None)
+        }
+        Err(e) => (None, Some(e)),
+    };
+
+    let initialized = tracing_subscriber::registry()
+        .with(filter)
+        .with(format_layer(settings.json, io::stderr))
+        .with(file_layer)
+        .try_init();
+    if initialized.is_err() {
+        return;
+    }
+
+    if let Some(e) = file_error {
+        tracing::warn!("Not writing logs to {}: {}", log_dir().display(), e);
+    }
+}
+
+/// Log files, oldest first
+pub fn files() -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(log_dir()) else {
+        return Vec::new();
+    };
+
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(FILE_PREFIX))
+        })
+        .collect();
+    // The date in the name sorts them chronologically
+    files.sort();
+    files
+}
+
+/// Concatenate every log file into one file in `dir`, returning its path
+pub fn export(dir: &Path) -> Result<PathBuf, String> {
+    let files = files();
+    if files.is_empty() {
+        return Err("There are no logs yet".to_string());
+    }
+
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let name = format!(
+        "{}-logs-{}.log",
+        FILE_PREFIX,
+        Local::now().format("%Y%m%d-%H%M%S")
+    );
+    let path = dir.join(name);
+    let mut output =
+        File::create(&path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+
+    for file in files {
+        let mut input =
+            File::open(&file).map_err(|e| format!("Failed to read {}: {}", file.display(), e))?;
+        io::copy(&mut input, &mut output)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    }
+
+    Ok(path)
+}
@@ -1,10 +1,31 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use actix_web::{get, post, App, HttpResponse, HttpServer, Responder};
+use actix_files::NamedFile;
+use actix_multipart::{Field, Multipart};
+use actix_web::body::{to_bytes, EitherBody, MessageBody};
+use actix_web::cookie::{Cookie, SameSite};
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::http::header::{self, DispositionType};
+use actix_web::middleware::{from_fn, Next};
+use actix_web::{
+    delete, get, post, put, web, App, HttpRequest, HttpResponse, HttpServer, Responder,
+};
+use ferrous_control_lib::{
+    access, audit, brightness, clipboard, config, discovery, files, logging, metrics, network,
+    notify, open_url, peers, power, power_rules, processes, rate_limit, roles, runs, schedules,
+    screen, screen_stream, speech, system_status, wol,
+};
+use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::net::Ipv4Addr;
+use std::path::Path;
 use std::process::Command;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info, info_span, warn, Instrument};
 
 #[cfg(target_os = "windows")]
 mod volume_control;
@@ -15,6 +36,7 @@ const HTML_CONTENT: &str = r#"<!DOCTYPE html>
     <meta charset="UTF-8">
     <meta name="viewport" content="width=device-width, initial-scale=1.0">
     <title>Ferrous Control Web Interface</title>
+    <link rel="manifest" href="/manifest.webmanifest">
     <style>
         * {
             margin: 0;
@@ -424,6 +446,130 @@ const HTML_CONTENT: &str = r#"<!DOCTYPE html>
             background: transparent;
             border: none;
         }
+        .panel {
+            margin-top: 1.5rem;
+            padding: 1rem 1.5rem;
+            background: linear-gradient(135deg, #f5f7fa 0%, #c3cfe2 100%);
+            border-radius: 15px;
+            box-shadow: 0 4px 15px rgba(0, 0, 0, 0.1);
+            text-align: left;
+        }
+
+        .panel summary {
+            cursor: pointer;
+            color: #333;
+            font-size: 1.2rem;
+            font-weight: 600;
+        }
+
+        .panel[open] summary {
+            margin-bottom: 1rem;
+        }
+
+        .panel-toolbar {
+            display: flex;
+            gap: 0.5rem;
+            margin-bottom: 0.75rem;
+        }
+
+        .panel-input {
+            flex: 1;
+            padding: 0.5rem 0.75rem;
+            border: 1px solid #ccc;
+            border-radius: 8px;
+            font-size: 0.95rem;
+        }
+
+        .small-btn {
+            padding: 0.5rem 1rem;
+            border: none;
+            border-radius: 8px;
+            font-size: 0.9rem;
+            font-weight: 600;
+            cursor: pointer;
+            color: white;
+            background: linear-gradient(135deg, #4facfe 0%, #00f2fe 100%);
+            transition: all 0.3s ease;
+        }
+
+        .small-btn:hover {
+            transform: translateY(-2px);
+        }
+
+        .small-btn.danger {
+            background: linear-gradient(135deg, #f093fb 0%, #f5576c 100%);
+        }
+
+        .launch-list {
+            display: flex;
+            flex-wrap: wrap;
+            gap: 0.5rem;
+            margin-bottom: 0.75rem;
+        }
+
+        .table-wrapper {
+            max-height: 320px;
+            overflow-y: auto;
+            border-radius: 8px;
+        }
+
+        .data-table {
+            width: 100%;
+            border-collapse: collapse;
+            font-size: 0.9rem;
+            background: rgba(255, 255, 255, 0.6);
+        }
+
+        .data-table th,
+        .data-table td {
+            padding: 0.4rem 0.5rem;
+            border-bottom: 1px solid rgba(0, 0, 0, 0.08);
+        }
+
+        .data-table th {
+            position: sticky;
+            top: 0;
+            background: #eef1f6;
+            color: #333;
+        }
+
+        .data-table .row-label {
+            font-weight: 600;
+            color: #333;
+        }
+        .clipboard-text {
+            width: 100%;
+            min-height: 80px;
+            margin-bottom: 0.75rem;
+            resize: vertical;
+            font-family: inherit;
+            box-sizing: border-box;
+        }
+
+        .upload-progress {
+            width: 100%;
+            margin-bottom: 0.75rem;
+        }
+
+        .share-path {
+            flex: 1;
+            align-self: center;
+            overflow-wrap: anywhere;
+            font-family: monospace;
+        }
+
+        .screen-preview {
+            display: block;
+            width: 100%;
+            margin-top: 0.75rem;
+            border-radius: 8px;
+            background: #222;
+            min-height: 120px;
+        }
+
+        .role-hidden {
+            display: none !important;
+        }
     </style>
 </head>
 <body>
@@ -431,7 +577,7 @@ const HTML_CONTENT: &str = r#"<!DOCTYPE html>
         <h1>Ferrous Control</h1>
         <p class="subtitle">Remote PC Control Panel</p>
 
-        <div class="button-container">
+        <div class="button-container" data-role="power">
             <button id="shutdownBtn" class="control-btn shutdown-btn">
                 <span class="icon">🔴</span>
                 <span>Shutdown</span>
@@ -448,7 +594,7 @@ const HTML_CONTENT: &str = r#"<!DOCTYPE html>
             </button>
         </div>
 
-        <div class="button-container">
+        <div class="button-container" data-role="media">
             <button id="volumeUpBtn" class="control-btn volume-up-btn">
                 <span class="icon">🔊</span>
                 <span>Volume Up</span>
@@ -465,10 +611,190 @@ const HTML_CONTENT: &str = r#"<!DOCTYPE html>
                 <span class="volume-icon">🔊</span>
                 <span id="volumePercentage" class="volume-percentage">0%</span>
             </div>
-            <input type="range" id="volumeSlider" class="volume-slider" min="0" max="100" value="0" step="1">
+            <input type="range" id="volumeSlider" class="volume-slider" data-role="media" min="0" max="100" value="0" step="1">
+        </div>
+
+        <div id="brightnessDisplay" class="volume-display" style="display: none;">
+            <div class="volume-level">
+                <span class="volume-icon">☀️</span>
+                <span id="brightnessPercentage" class="volume-percentage">0%</span>
+            </div>
+            <input type="range" id="brightnessSlider" class="volume-slider" data-role="media" min="0" max="100" value="0" step="1">
         </div>
 
         <div id="status" class="status"></div>
+
+        <details id="powerRulesPanel" class="panel">
+            <summary>Power Rules</summary>
+            <div class="panel-toolbar" data-role="admin">
+                <select id="ruleCondition" class="panel-input">
+                    <option value="idle">When idle for</option>
+                    <option value="process_exit">When a process exits</option>
+                    <option value="low_cpu">When CPU stays below</option>
+                </select>
+                <input type="text" id="ruleProcess" class="panel-input" placeholder="PID or process name" style="display: none;">
+                <input type="number" id="rulePercent" class="panel-input" min="1" max="100" value="10" title="CPU %" style="display: none;">
+                <input type="number" id="ruleMinutes" class="panel-input" min="1" value="30" title="Minutes">
+                <select id="ruleAction" class="panel-input">
+                    <option value="shutdown">Shutdown</option>
+                    <option value="sleep">Sleep</option>
+                </select>
+                <button id="ruleAddBtn" class="small-btn">Add</button>
+            </div>
+            <div class="table-wrapper">
+                <table class="data-table">
+                    <thead>
+                        <tr><th>Condition</th><th>Action</th><th>Status</th><th></th></tr>
+                    </thead>
+                    <tbody id="ruleTableBody"></tbody>
+                </table>
+            </div>
+        </details>
+
+        <details id="peersPanel" class="panel" data-role="power">
+            <summary>Machines</summary>
+            <div class="panel-toolbar">
+                <select id="peerGroup" class="panel-input">
+                    <option value="">All machines</option>
+                </select>
+                <button id="peerBulkShutdownBtn" class="small-btn">Shut down all</button>
+                <button id="peerBulkSleepBtn" class="small-btn">Sleep all</button>
+                <button id="peerBulkCancelBtn" class="small-btn">Cancel all</button>
+            </div>
+            <div class="table-wrapper">
+                <table class="data-table">
+                    <thead>
+                        <tr><th>Name</th><th>Status</th><th>Volume</th><th></th></tr>
+                    </thead>
+                    <tbody id="peerTableBody"></tbody>
+                </table>
+            </div>
+        </details>
+
+        <details id="wolPanel" class="panel" data-role="power">
+            <summary>Wake Machines</summary>
+            <div class="table-wrapper">
+                <table class="data-table">
+                    <thead>
+                        <tr><th>Name</th><th>MAC</th><th></th></tr>
+                    </thead>
+                    <tbody id="wolTableBody"></tbody>
+                </table>
+            </div>
+        </details>
+
+        <details id="screenPanel" class="panel" data-role="media">
+            <summary>Screen Preview</summary>
+            <div class="panel-toolbar">
+                <select id="monitorSelect" class="panel-input"></select>
+                <button id="screenRefreshBtn" class="small-btn">Refresh</button>
+                <button id="screenLiveBtn" class="small-btn">Live</button>
+            </div>
+            <img id="screenPreview" class="screen-preview" alt="Screen preview">
+            <canvas id="screenCanvas" class="screen-preview" style="display: none;"></canvas>
+        </details>
+
+        <details id="openUrlPanel" class="panel" data-role="media">
+            <summary>Open Link on PC</summary>
+            <div class="panel-toolbar">
+                <input type="url" id="openUrlInput" class="panel-input" placeholder="https://example.com">
+                <button id="openUrlBtn" class="small-btn">Open</button>
+            </div>
+        </details>
+
+        <details id="notifyPanel" class="panel" data-role="media">
+            <summary>Send Notification</summary>
+            <div class="panel-toolbar">
+                <input type="text" id="notifyTitle" class="panel-input" placeholder="Title" maxlength="256">
+                <select id="notifyUrgency" class="panel-input">
+                    <option value="low">Low</option>
+                    <option value="normal" selected>Normal</option>
+                    <option value="critical">Critical</option>
+                </select>
+            </div>
+            <textarea id="notifyBody" class="panel-input clipboard-text" placeholder="Message" maxlength="4096"></textarea>
+            <div class="panel-toolbar">
+                <button id="notifySendBtn" class="small-btn">Send</button>
+            </div>
+        </details>
+
+        <details id="speakPanel" class="panel" data-role="media">
+            <summary>Announce</summary>
+            <textarea id="speakText" class="panel-input clipboard-text" placeholder="Message to read aloud on the PC" maxlength="1000"></textarea>
+            <div class="panel-toolbar">
+                <input type="text" id="speakVoice" class="panel-input" placeholder="Voice (optional)">
+                <input type="range" id="speakRate" min="-100" max="100" value="0" step="10" title="Speaking rate">
+                <button id="speakBtn" class="small-btn">Speak</button>
+            </div>
+        </details>
+
+        <details id="clipboardPanel" class="panel" data-role="media">
+            <summary>Clipboard</summary>
+            <textarea id="clipboardText" class="panel-input clipboard-text" placeholder="Clipboard text"></textarea>
+            <div class="panel-toolbar">
+                <button id="clipboardGetBtn" class="small-btn">Get from PC</button>
+                <button id="clipboardSendBtn" class="small-btn">Send to PC</button>
+                <button id="clipboardCopyBtn" class="small-btn">Copy here</button>
+                <label class="small-btn">
+                    Send image
+                    <input type="file" id="clipboardImageInput" accept="image/*" style="display: none;">
+                </label>
+            </div>
+            <img id="clipboardImage" class="screen-preview" alt="Clipboard image" style="display: none;">
+        </details>
+
+        <details id="filesPanel" class="panel" data-role="media">
+            <summary>File Transfer</summary>
+            <div class="panel-toolbar">
+                <input type="file" id="uploadInput" class="panel-input" multiple>
+                <button id="uploadBtn" class="small-btn">Upload</button>
+            </div>
+            <progress id="uploadProgress" class="upload-progress" max="100" value="0" style="display: none;"></progress>
+            <div class="panel-toolbar">
+                <button id="shareUpBtn" class="small-btn">Up</button>
+                <span id="sharePath" class="share-path">/</span>
+            </div>
+            <div class="table-wrapper">
+                <table class="data-table">
+                    <thead>
+                        <tr><th>Name</th><th>Size</th><th></th></tr>
+                    </thead>
+                    <tbody id="shareTableBody"></tbody>
+                </table>
+            </div>
+        </details>
+
+        <details id="systemPanel" class="panel">
+            <summary>System Status</summary>
+            <div class="table-wrapper">
+                <table class="data-table">
+                    <tbody id="systemTableBody"></tbody>
+                </table>
+            </div>
+        </details>
+
+        <details id="processPanel" class="panel">
+            <summary>Task Manager</summary>
+            <div id="launchList" class="launch-list" data-role="admin"></div>
+            <div class="panel-toolbar">
+                <input type="text" id="processFilter" class="panel-input" placeholder="Filter by name">
+                <button id="processRefreshBtn" class="small-btn">Refresh</button>
+            </div>
+            <div class="table-wrapper">
+                <table class="data-table">
+                    <thead>
+                        <tr>
+                            <th>Name</th>
+                            <th>PID</th>
+                            <th>CPU</th>
+                            <th>Memory</th>
+                            <th></th>
+                        </tr>
+                    </thead>
+                    <tbody id="processTableBody"></tbody>
+                </table>
+            </div>
+        </details>
     </div>
 
     <div id="modalOverlay" class="modal-overlay">
@@ -497,10 +823,95 @@ const HTML_CONTENT: &str = r#"<!DOCTYPE html>
         const volumeDisplay = document.getElementById('volumeDisplay');
         const volumePercentage = document.getElementById('volumePercentage');
         const volumeSlider = document.getElementById('volumeSlider');
+        const brightnessDisplay = document.getElementById('brightnessDisplay');
+        const brightnessPercentage = document.getElementById('brightnessPercentage');
+        const brightnessSlider = document.getElementById('brightnessSlider');
+        const processPanel = document.getElementById('processPanel');
+        const processFilter = document.getElementById('processFilter');
+        const processRefreshBtn = document.getElementById('processRefreshBtn');
+        const processTableBody = document.getElementById('processTableBody');
+        const launchList = document.getElementById('launchList');
+        const systemPanel = document.getElementById('systemPanel');
+        const screenPanel = document.getElementById('screenPanel');
+        const powerRulesPanel = document.getElementById('powerRulesPanel');
+        const wolPanel = document.getElementById('wolPanel');
+        const peersPanel = document.getElementById('peersPanel');
+        const peerGroup = document.getElementById('peerGroup');
+        const peerBulkShutdownBtn = document.getElementById('peerBulkShutdownBtn');
+        const peerBulkSleepBtn = document.getElementById('peerBulkSleepBtn');
+        const peerBulkCancelBtn = document.getElementById('peerBulkCancelBtn');
+        const peerTableBody = document.getElementById('peerTableBody');
+        const wolTableBody = document.getElementById('wolTableBody');
+        const ruleCondition = document.getElementById('ruleCondition');
+        const ruleProcess = document.getElementById('ruleProcess');
+        const rulePercent = document.getElementById('rulePercent');
+        const ruleMinutes = document.getElementById('ruleMinutes');
+        const ruleAction = document.getElementById('ruleAction');
+        const ruleAddBtn = document.getElementById('ruleAddBtn');
+        const ruleTableBody = document.getElementById('ruleTableBody');
+        const speakText = document.getElementById('speakText');
+        const speakVoice = document.getElementById('speakVoice');
+        const speakRate = document.getElementById('speakRate');
+        const speakBtn = document.getElementById('speakBtn');
+        const notifyTitle = document.getElementById('notifyTitle');
+        const notifyBody = document.getElementById('notifyBody');
+        const notifyUrgency = document.getElementById('notifyUrgency');
+        const notifySendBtn = document.getElementById('notifySendBtn');
+        const openUrlPanel = document.getElementById('openUrlPanel');
+        const openUrlInput = document.getElementById('openUrlInput');
+        const openUrlBtn = document.getElementById('openUrlBtn');
+        const filesPanel = document.getElementById('filesPanel');
+        const uploadInput = document.getElementById('uploadInput');
+        const uploadBtn = document.getElementById('uploadBtn');
+        const uploadProgress = document.getElementById('uploadProgress');
+        const shareUpBtn = document.getElementById('shareUpBtn');
+        const sharePath = document.getElementById('sharePath');
+        const shareTableBody = document.getElementById('shareTableBody');
+        const clipboardPanel = document.getElementById('clipboardPanel');
+        const clipboardText = document.getElementById('clipboardText');
+        const clipboardGetBtn = document.getElementById('clipboardGetBtn');
+        const clipboardSendBtn = document.getElementById('clipboardSendBtn');
+        const clipboardCopyBtn = document.getElementById('clipboardCopyBtn');
+        const clipboardImageInput = document.getElementById('clipboardImageInput');
+        const clipboardImage = document.getElementById('clipboardImage');
+        const monitorSelect = document.getElementById('monitorSelect');
+        const screenRefreshBtn = document.getElementById('screenRefreshBtn');
+        const screenPreview = document.getElementById('screenPreview');
+        const screenLiveBtn = document.getElementById('screenLiveBtn');
+        const screenCanvas = document.getElementById('screenCanvas');
+        const systemTableBody = document.getElementById('systemTableBody');
 
         let modalResolve = null;
         let isUpdatingVolume = false;
 
+        // Hide controls this device's role can't use; the server enforces the same rules
+        const ROLES = ['viewer', 'media', 'power', 'admin'];
+        let clientRole = 'admin';
+
+        function allows(role) {
+            return ROLES.indexOf(clientRole) >= ROLES.indexOf(role);
+        }
+
+        async function applyRole() {
+            try {
+                let response = await fetch('/api/role');
+                // A removed device's cookie is cleared with the 401; carry on unpaired
+                if (response.status === 401) {
+                    response = await fetch('/api/role');
+                }
+                clientRole = (await response.json()).role;
+            } catch (error) {
+                console.error('Failed to fetch role:', error);
+                return;
+            }
+
+            document.querySelectorAll('[data-role]').forEach((element) => {
+                element.classList.toggle('role-hidden', !allows(element.dataset.role));
+            });
+        }
+
+        applyRole();
+
         async function fetchVolume() {
             console.log('Fetching volume...');
             try {
@@ -574,6 +985,50 @@ const HTML_CONTENT: &str = r#"<!DOCTYPE html>
         // Fetch volume on page load
         fetchVolume();
 
+        async function fetchBrightness() {
+            try {
+                const response = await fetch('/api/display/brightness');
+                const displays = await response.json();
+
+                // The slider controls all displays together and shows the first one
+                if (Array.isArray(displays) && displays.length > 0) {
+                    brightnessPercentage.textContent = displays[0].brightness + '%';
+                    brightnessSlider.value = displays[0].brightness;
+                    brightnessDisplay.style.display = 'block';
+                }
+            } catch (error) {
+                console.error('Failed to fetch brightness:', error);
+            }
+        }
+
+        async function setBrightness(brightness) {
+            try {
+                const response = await fetch('/api/display/brightness', {
+                    method: 'POST',
+                    headers: {
+                        'Content-Type': 'application/json',
+                    },
+                    body: JSON.stringify({ brightness: parseInt(brightness) }),
+                });
+
+                const data = await response.json();
+                if (!data.success) {
+                    console.error('Failed to set brightness:', data.message);
+                }
+            } catch (error) {
+                console.error('Failed to set brightness:', error);
+            }
+        }
+
+        let brightnessTimeout = null;
+        brightnessSlider.addEventListener('input', (e) => {
+            brightnessPercentage.textContent = e.target.value + '%';
+            clearTimeout(brightnessTimeout);
+            brightnessTimeout = setTimeout(() => setBrightness(e.target.value), 150);
+        });
+
+        fetchBrightness();
+
         function showModal(title, message) {
             return new Promise((resolve) => {
                 modalTitle.textContent = title;
@@ -699,499 +1154,3108 @@ const HTML_CONTENT: &str = r#"<!DOCTYPE html>
         volumeDownBtn.addEventListener('click', () => {
             changeVolume('/api/volume/decrease');
         });
-    </script>
-</body>
-</html>"#;
 
-#[derive(Serialize, Deserialize)]
-struct ApiResponse {
-    success: bool,
-    message: String,
-}
+        let processes = [];
+        let processInterval = null;
 
-#[get("/")]
-async fn index() -> impl Responder {
-    HttpResponse::Ok()
-        .content_type("text/html; charset=utf-8")
-        .body(HTML_CONTENT)
-}
+        function formatBytes(bytes) {
+            const units = ['B', 'KB', 'MB', 'GB', 'TB'];
+            let value = bytes;
+            let unit = 0;
+            while (value >= 1024 && unit < units.length - 1) {
+                value /= 1024;
+                unit++;
+            }
+            return `${value.toFixed(unit === 0 ? 0 : 1)} ${units[unit]}`;
+        }
 
-#[post("/api/shutdown")]
-async fn shutdown() -> impl Responder {
-    println!("Shutdown request received via web API");
+        function renderProcesses() {
+            const filter = processFilter.value.trim().toLowerCase();
+            processTableBody.innerHTML = '';
+
+            processes
+                .filter((process) => !filter || process.name.toLowerCase().includes(filter))
+                .slice(0, 100)
+                .forEach((process) => {
+                    const row = document.createElement('tr');
+                    [process.name, process.pid, `${process.cpu_usage.toFixed(1)}%`, formatBytes(process.memory)]
+                        .forEach((value) => {
+                            const cell = document.createElement('td');
+                            cell.textContent = value;
+                            row.appendChild(cell);
+                        });
+
+                    const actionCell = document.createElement('td');
+                    if (allows('admin')) {
+                        const killBtn = document.createElement('button');
+                        killBtn.className = 'small-btn danger';
+                        killBtn.textContent = 'Kill';
+                        killBtn.addEventListener('click', () => killProcess(process));
+                        actionCell.appendChild(killBtn);
+                    }
+                    row.appendChild(actionCell);
 
-    let result = if cfg!(target_os = "windows") {
-        Command::new("shutdown").args(["/s", "/t", "60"]).spawn()
-    } else if cfg!(target_os = "linux") {
-        Command::new("shutdown").args(["-h", "+1"]).spawn()
-    } else if cfg!(target_os = "macos") {
-        Command::new("sh")
-            .args(["-c", "sleep 60 && osascript -e 'tell app \"System Events\" to shut down' &"])
-            .spawn()
-    } else {
-        return HttpResponse::InternalServerError().json(ApiResponse {
-            success: false,
-            message: "Unsupported operating system".to_string(),
-        });
-    };
+                    processTableBody.appendChild(row);
+                });
+        }
 
-    match result {
-        Ok(_) => HttpResponse::Ok().json(ApiResponse {
-            success: true,
-            message: "Shutdown command executed".to_string(),
-        }),
-        Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
-            success: false,
-            message: format!("Failed to execute shutdown: {}", e),
-        }),
-    }
-}
+        async function fetchProcesses() {
+            try {
+                const response = await fetch('/api/processes');
+                processes = await response.json();
+                renderProcesses();
+            } catch (error) {
+                console.error('Failed to fetch processes:', error);
+            }
+        }
 
-#[post("/api/restart")]
-async fn restart() -> impl Responder {
-    println!("Restart request received via web API");
+        async function killProcess(process) {
+            const confirmed = await showModal(
+                'Kill Process',
+                `Are you sure you want to kill ${process.name} (PID ${process.pid})?`
+            );
 
-    let result = if cfg!(target_os = "windows") {
-        Command::new("shutdown").args(["/r", "/t", "0"]).spawn()
-    } else if cfg!(target_os = "linux") {
-        Command::new("shutdown").args(["-r", "now"]).spawn()
-    } else if cfg!(target_os = "macos") {
-        Command::new("shutdown").args(["-r", "now"]).spawn()
-    } else {
-        return HttpResponse::InternalServerError().json(ApiResponse {
-            success: false,
-            message: "Unsupported operating system".to_string(),
-        });
-    };
+            if (!confirmed) {
+                return;
+            }
 
-    match result {
-        Ok(_) => HttpResponse::Ok().json(ApiResponse {
-            success: true,
-            message: "Restart command executed".to_string(),
-        }),
-        Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
-            success: false,
-            message: format!("Failed to execute restart: {}", e),
-        }),
-    }
-}
+            try {
+                const response = await fetch('/api/processes/kill', {
+                    method: 'POST',
+                    headers: {
+                        'Content-Type': 'application/json',
+                    },
+                    body: JSON.stringify({ pid: process.pid }),
+                });
 
-#[post("/api/cancel")]
-async fn cancel_shutdown() -> impl Responder {
-    println!("Cancel shutdown request received via web API");
+                const data = await response.json();
+                showStatus(data.success ? data.message : `Error: ${data.message}`, data.success ? 'success' : 'error');
+                fetchProcesses();
+            } catch (error) {
+                showStatus(`Network error: ${error.message}`, 'error');
+            }
+        }
 
-    let result = if cfg!(target_os = "windows") {
-        Command::new("shutdown").args(["/a"]).spawn()
-    } else if cfg!(target_os = "linux") {
-        Command::new("shutdown").args(["-c"]).spawn()
-    } else if cfg!(target_os = "macos") {
-        Command::new("pkill")
-            .args(["-f", "sleep 60 && osascript"])
-            .spawn()
-    } else {
-        return HttpResponse::InternalServerError().json(ApiResponse {
-            success: false,
-            message: "Unsupported operating system".to_string(),
+        async function launchApplication(name) {
+            try {
+                const response = await fetch('/api/processes/launch', {
+                    method: 'POST',
+                    headers: {
+                        'Content-Type': 'application/json',
+                    },
+                    body: JSON.stringify({ name }),
+                });
+
+                const data = await response.json();
+                showStatus(data.success ? data.message : `Error: ${data.message}`, data.success ? 'success' : 'error');
+                setTimeout(fetchProcesses, 500);
+            } catch (error) {
+                showStatus(`Network error: ${error.message}`, 'error');
+            }
+        }
+
+        async function fetchLaunchable() {
+            try {
+                const response = await fetch('/api/processes/launchable');
+                const names = await response.json();
+                launchList.innerHTML = '';
+
+                names.forEach((name) => {
+                    const launchBtn = document.createElement('button');
+                    launchBtn.className = 'small-btn';
+                    launchBtn.textContent = `▶ ${name}`;
+                    launchBtn.addEventListener('click', () => launchApplication(name));
+                    launchList.appendChild(launchBtn);
+                });
+            } catch (error) {
+                console.error('Failed to fetch launchable applications:', error);
+            }
+        }
+
+        // Only poll the process list while the task manager is open
+        processPanel.addEventListener('toggle', () => {
+            clearInterval(processInterval);
+            if (processPanel.open) {
+                fetchLaunchable();
+                fetchProcesses();
+                processInterval = setInterval(fetchProcesses, 3000);
+            }
         });
-    };
 
-    match result {
-        Ok(_) => HttpResponse::Ok().json(ApiResponse {
-            success: true,
-            message: "Shutdown cancelled".to_string(),
-        }),
-        Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
-            success: false,
-            message: format!("Failed to cancel shutdown: {}", e),
-        }),
-    }
-}
+        processFilter.addEventListener('input', renderProcesses);
+        processRefreshBtn.addEventListener('click', fetchProcesses);
 
-#[post("/api/sleep")]
-async fn sleep() -> impl Responder {
-    println!("Sleep request received via web API");
+        let systemInterval = null;
 
-    let result = if cfg!(target_os = "windows") {
-        Command::new("rundll32.exe")
-            .args(["powrprof.dll,SetSuspendState", "0,1,0"])
-            .spawn()
-    } else if cfg!(target_os = "linux") {
-        Command::new("systemctl").args(["suspend"]).spawn()
+        function formatUptime(seconds) {
+            const days = Math.floor(seconds / 86400);
+            const hours = Math.floor((seconds % 86400) / 3600);
+            const minutes = Math.floor((seconds % 3600) / 60);
+            return days > 0 ? `${days}d ${hours}h ${minutes}m` : `${hours}h ${minutes}m`;
+        }
+
+        function renderSystemStatus(status) {
+            const rows = [
+                ['Hostname', status.hostname],
+                ['OS', status.kernel ? `${status.os} (${status.kernel})` : status.os],
+                ['Uptime', formatUptime(status.uptime_seconds)],
+                ['CPU', `${status.cpu.usage_percent.toFixed(1)}% of ${status.cpu.cores} cores`],
+                ['Load', status.cpu.load_average.map((load) => load.toFixed(2)).join(' / ')],
+                ['Memory', `${formatBytes(status.memory.used)} / ${formatBytes(status.memory.total)}`],
+            ];
+
+            if (status.memory.swap_total > 0) {
+                rows.push(['Swap', `${formatBytes(status.memory.swap_used)} / ${formatBytes(status.memory.swap_total)}`]);
+            }
+
+            status.disks.forEach((disk) => {
+                rows.push([`Disk ${disk.mount_point}`, `${formatBytes(disk.used)} / ${formatBytes(disk.total)}`]);
+            });
+
+            if (status.battery) {
+                rows.push(['Battery', `${status.battery.level}% (${status.battery.status})`]);
+            }
+
+            status.temperatures.forEach((sensor) => {
+                rows.push([sensor.name, `${sensor.celsius.toFixed(1)} °C`]);
+            });
+
+            systemTableBody.innerHTML = '';
+            rows.forEach(([label, value]) => {
+                const row = document.createElement('tr');
+                const labelCell = document.createElement('td');
+                labelCell.className = 'row-label';
+                labelCell.textContent = label;
+                const valueCell = document.createElement('td');
+                valueCell.textContent = value;
+                row.appendChild(labelCell);
+                row.appendChild(valueCell);
+                systemTableBody.appendChild(row);
+            });
+        }
+
+        async function fetchSystemStatus() {
+            try {
+                const response = await fetch('/api/system/status');
+                renderSystemStatus(await response.json());
+            } catch (error) {
+                console.error('Failed to fetch system status:', error);
+            }
+        }
+
+        systemPanel.addEventListener('toggle', () => {
+            clearInterval(systemInterval);
+            if (systemPanel.open) {
+                fetchSystemStatus();
+                systemInterval = setInterval(fetchSystemStatus, 5000);
+            }
+        });
+
+        async function fetchMonitors() {
+            try {
+                const response = await fetch('/api/screen/monitors');
+                const monitors = await response.json();
+                if (!Array.isArray(monitors)) {
+                    showStatus(`Error: ${monitors.message}`, 'error');
+                    return;
+                }
+
+                monitorSelect.innerHTML = '';
+                monitors.forEach((monitor) => {
+                    const option = document.createElement('option');
+                    option.value = monitor.index;
+                    option.textContent = `${monitor.name} (${monitor.width}×${monitor.height})`;
+                    option.selected = monitor.primary;
+                    monitorSelect.appendChild(option);
+                });
+            } catch (error) {
+                console.error('Failed to fetch monitors:', error);
+            }
+        }
+
+        function refreshScreenshot() {
+            // Request roughly the size the preview is shown at to save bandwidth
+            const width = Math.min(Math.round(screenPreview.clientWidth * window.devicePixelRatio) || 800, 1920);
+            const params = new URLSearchParams({
+                monitor: monitorSelect.value || 0,
+                width,
+                format: 'jpeg',
+                t: Date.now(),
+            });
+            screenPreview.src = `/api/screen/screenshot?${params}`;
+        }
+
+        async function openUrl() {
+            const url = openUrlInput.value.trim();
+            if (!url) {
+                showStatus('Enter a URL to open', 'error');
+                return;
+            }
+
+            openUrlBtn.disabled = true;
+            showStatus('Opening link on the PC...', 'info');
+
+            try {
+                const response = await fetch('/api/open-url', {
+                    method: 'POST',
+                    headers: { 'Content-Type': 'application/json' },
+                    body: JSON.stringify({ url }),
+                });
+                const data = await response.json();
+                showStatus(data.success ? data.message : `Error: ${data.message}`, data.success ? 'success' : 'error');
+            } catch (error) {
+                showStatus(`Network error: ${error.message}`, 'error');
+            } finally {
+                openUrlBtn.disabled = false;
+            }
+        }
+
+        openUrlBtn.addEventListener('click', openUrl);
+        openUrlInput.addEventListener('keydown', (event) => {
+            if (event.key === 'Enter') {
+                openUrl();
+            }
+        });
+
+        // Share target: links shared to the installed web app arrive as /share?url=...&text=...
+        if (window.location.pathname === '/share') {
+            const params = new URLSearchParams(window.location.search);
+            // Many Android apps put the link in the text field instead of url
+            const shared = params.get('url') || (params.get('text') || '').match(/https?:\/\/\S+/)?.[0] || '';
+            openUrlInput.value = shared;
+            openUrlPanel.open = true;
+            history.replaceState(null, '', '/');
+        }
+
+        async function sendNotification() {
+            if (!notifyTitle.value.trim()) {
+                showStatus('Enter a notification title', 'error');
+                return;
+            }
+
+            try {
+                const response = await fetch('/api/notify', {
+                    method: 'POST',
+                    headers: { 'Content-Type': 'application/json' },
+                    body: JSON.stringify({
+                        title: notifyTitle.value,
+                        body: notifyBody.value,
+                        urgency: notifyUrgency.value,
+                    }),
+                });
+                const data = await response.json();
+                showStatus(data.success ? data.message : `Error: ${data.message}`, data.success ? 'success' : 'error');
+                if (data.success) {
+                    notifyBody.value = '';
+                }
+            } catch (error) {
+                showStatus(`Network error: ${error.message}`, 'error');
+            }
+        }
+
+        notifySendBtn.addEventListener('click', sendNotification);
+
+        async function speak() {
+            if (!speakText.value.trim()) {
+                showStatus('Enter a message to speak', 'error');
+                return;
+            }
+
+            try {
+                const response = await fetch('/api/speak', {
+                    method: 'POST',
+                    headers: { 'Content-Type': 'application/json' },
+                    body: JSON.stringify({
+                        text: speakText.value,
+                        voice: speakVoice.value.trim() || null,
+                        rate: parseInt(speakRate.value),
+                    }),
+                });
+                const data = await response.json();
+                showStatus(data.success ? data.message : `Error: ${data.message}`, data.success ? 'success' : 'error');
+            } catch (error) {
+                showStatus(`Network error: ${error.message}`, 'error');
+            }
+        }
+
+        speakBtn.addEventListener('click', speak);
+
+        let ruleInterval = null;
+
+        function describeCondition(condition) {
+            switch (condition.kind) {
+                case 'idle':
+                    return `Idle for ${condition.minutes} min`;
+                case 'process_exit':
+                    return condition.pid ? `PID ${condition.pid} exits` : `${condition.name} exits`;
+                case 'low_cpu':
+                    return `CPU below ${condition.percent}% for ${condition.minutes} min`;
+                default:
+                    return condition.kind;
+            }
+        }
+
+        function renderRules(rules) {
+            ruleTableBody.innerHTML = '';
+            rules.forEach((rule) => {
+                const row = document.createElement('tr');
+                [describeCondition(rule.condition), rule.action, `${rule.state}: ${rule.detail}`].forEach((text) => {
+                    const cell = document.createElement('td');
+                    cell.textContent = text;
+                    row.appendChild(cell);
+                });
+
+                const actionCell = document.createElement('td');
+                if (allows('admin')) {
+                    const removeBtn = document.createElement('button');
+                    removeBtn.className = 'small-btn';
+                    removeBtn.textContent = 'Remove';
+                    removeBtn.addEventListener('click', () => removeRule(rule.id));
+                    actionCell.appendChild(removeBtn);
+                }
+                row.appendChild(actionCell);
+
+                ruleTableBody.appendChild(row);
+            });
+        }
+
+        async function fetchRules() {
+            try {
+                const response = await fetch('/api/power/rules');
+                renderRules(await response.json());
+            } catch (error) {
+                console.error('Failed to fetch power rules:', error);
+            }
+        }
+
+        function buildCondition() {
+            const minutes = parseInt(ruleMinutes.value, 10);
+            switch (ruleCondition.value) {
+                case 'idle':
+                    return { kind: 'idle', minutes };
+                case 'process_exit': {
+                    const target = ruleProcess.value.trim();
+                    return /^\d+$/.test(target)
+                        ? { kind: 'process_exit', pid: parseInt(target, 10) }
+                        : { kind: 'process_exit', name: target };
+                }
+                default:
+                    return { kind: 'low_cpu', percent: parseFloat(rulePercent.value), minutes };
+            }
+        }
+
+        async function addRule() {
+            try {
+                const response = await fetch('/api/power/rules', {
+                    method: 'POST',
+                    headers: {
+                        'Content-Type': 'application/json',
+                    },
+                    body: JSON.stringify({ condition: buildCondition(), action: ruleAction.value }),
+                });
+
+                const data = await response.json();
+                if (response.ok) {
+                    showStatus(`Rule added: ${ruleAction.value} when ${describeCondition(data.condition).toLowerCase()}`, 'success');
+                    fetchRules();
+                } else {
+                    showStatus(`Error: ${data.message}`, 'error');
+                }
+            } catch (error) {
+                showStatus(`Network error: ${error.message}`, 'error');
+            }
+        }
+
+        async function removeRule(id) {
+            try {
+                const response = await fetch(`/api/power/rules/${id}`, { method: 'DELETE' });
+                const data = await response.json();
+                showStatus(data.success ? data.message : `Error: ${data.message}`, data.success ? 'success' : 'error');
+                fetchRules();
+            } catch (error) {
+                showStatus(`Network error: ${error.message}`, 'error');
+            }
+        }
+
+        // Show only the inputs the selected condition uses
+        ruleCondition.addEventListener('change', () => {
+            ruleProcess.style.display = ruleCondition.value === 'process_exit' ? '' : 'none';
+            rulePercent.style.display = ruleCondition.value === 'low_cpu' ? '' : 'none';
+            ruleMinutes.style.display = ruleCondition.value === 'process_exit' ? 'none' : '';
+        });
+
+        ruleAddBtn.addEventListener('click', addRule);
+
+        powerRulesPanel.addEventListener('toggle', () => {
+            clearInterval(ruleInterval);
+            if (powerRulesPanel.open) {
+                fetchRules();
+                ruleInterval = setInterval(fetchRules, 5000);
+            }
+        });
+
+        let peerInterval = null;
+        let peerCache = [];
+
+        function peerGroups(peers) {
+            return [...new Set(peers.map((peer) => peer.group).filter((group) => group))].sort();
+        }
+
+        function renderPeerGroups(peers) {
+            const selected = peerGroup.value;
+            peerGroup.innerHTML = '';
+            const allOption = document.createElement('option');
+            allOption.value = '';
+            allOption.textContent = 'All machines';
+            peerGroup.appendChild(allOption);
+
+            peerGroups(peers).forEach((group) => {
+                const option = document.createElement('option');
+                option.value = group;
+                option.textContent = `Group: ${group}`;
+                peerGroup.appendChild(option);
+            });
+            peerGroup.value = selected;
+        }
+
+        function renderPeers(peers) {
+            renderPeerGroups(peers);
+            peerTableBody.innerHTML = '';
+
+            if (peers.length === 0) {
+                const row = document.createElement('tr');
+                const cell = document.createElement('td');
+                cell.colSpan = 4;
+                cell.textContent = 'No machines registered. Add them in the desktop app.';
+                row.appendChild(cell);
+                peerTableBody.appendChild(row);
+            }
+
+            peers
+                .filter((peer) => !peerGroup.value || peer.group === peerGroup.value)
+                .forEach((peer) => {
+                    const row = document.createElement('tr');
+
+                    const nameCell = document.createElement('td');
+                    nameCell.textContent = peer.group ? `${peer.name} (${peer.group})` : peer.name;
+
+                    const statusCell = document.createElement('td');
+                    if (peer.online && peer.system) {
+                        statusCell.textContent = `Online, CPU ${peer.system.cpu.usage_percent.toFixed(0)}%`;
+                    } else {
+                        statusCell.textContent = 'Offline';
+                        statusCell.title = peer.error || '';
+                    }
+
+                    const volumeCell = document.createElement('td');
+                    const volumeInput = document.createElement('input');
+                    volumeInput.type = 'number';
+                    volumeInput.min = '0';
+                    volumeInput.max = '100';
+                    volumeInput.className = 'panel-input';
+                    volumeInput.value = peer.volume ?? '';
+                    volumeInput.disabled = !peer.online;
+                    volumeInput.addEventListener('change', () => {
+                        runPeerAction(peer.name, { type: 'set_volume', level: parseInt(volumeInput.value, 10) });
+                    });
+                    volumeCell.appendChild(volumeInput);
+
+                    const actionCell = document.createElement('td');
+                    [
+                        ['Shutdown', { type: 'shutdown' }],
+                        ['Sleep', { type: 'sleep' }],
+                        ['Cancel', { type: 'cancel_shutdown' }],
+                    ].forEach(([label, action]) => {
+                        const actionBtn = document.createElement('button');
+                        actionBtn.className = 'small-btn';
+                        actionBtn.textContent = label;
+                        actionBtn.disabled = !peer.online;
+                        actionBtn.addEventListener('click', () => confirmPeerAction(peer.name, label, action));
+                        actionCell.appendChild(actionBtn);
+                    });
+
+                    row.appendChild(nameCell);
+                    row.appendChild(statusCell);
+                    row.appendChild(volumeCell);
+                    row.appendChild(actionCell);
+                    peerTableBody.appendChild(row);
+                });
+        }
+
+        async function fetchPeers() {
+            try {
+                const response = await fetch('/api/peers');
+                peerCache = await response.json();
+                renderPeers(peerCache);
+            } catch (error) {
+                console.error('Failed to fetch peers:', error);
+            }
+        }
+
+        async function runPeerAction(name, action) {
+            try {
+                const response = await fetch(`/api/peers/${encodeURIComponent(name)}/action`, {
+                    method: 'POST',
+                    headers: {
+                        'Content-Type': 'application/json',
+                    },
+                    body: JSON.stringify(action),
+                });
+
+                const data = await response.json();
+                showStatus(data.success ? `${name}: ${data.message}` : `Error: ${data.message}`, data.success ? 'success' : 'error');
+            } catch (error) {
+                showStatus(`Network error: ${error.message}`, 'error');
+            }
+        }
+
+        async function confirmPeerAction(name, label, action) {
+            if (action.type !== 'cancel_shutdown') {
+                const confirmed = await showModal('Confirm Action', `${label} ${name}?`);
+                if (!confirmed) {
+                    return;
+                }
+            }
+            runPeerAction(name, action);
+        }
+
+        async function runBulkPeerAction(label, action) {
+            const group = peerGroup.value;
+            const target = group ? `all machines in ${group}` : 'all machines';
+            if (action.type !== 'cancel_shutdown') {
+                const confirmed = await showModal('Confirm Action', `${label} ${target}?`);
+                if (!confirmed) {
+                    return;
+                }
+            }
+
+            try {
+                const response = await fetch('/api/peers/bulk', {
+                    method: 'POST',
+                    headers: {
+                        'Content-Type': 'application/json',
+                    },
+                    body: JSON.stringify({
+                        group: group || null,
+                        peers: group ? [] : peerCache.map((peer) => peer.name),
+                        action,
+                    }),
+                });
+
+                const results = await response.json();
+                if (!response.ok) {
+                    showStatus(results.message, 'error');
+                    return;
+                }
+                const failed = results.filter((result) => !result.success);
+                if (failed.length === 0) {
+                    showStatus(`${label}: sent to ${results.length} machines`, 'success');
+                } else {
+                    showStatus(`${label} failed on ${failed.map((result) => `${result.name} (${result.message})`).join(', ')}`, 'error');
+                }
+            } catch (error) {
+                showStatus(`Network error: ${error.message}`, 'error');
+            }
+        }
+
+        peerGroup.addEventListener('change', () => renderPeers(peerCache));
+        peerBulkShutdownBtn.addEventListener('click', () => runBulkPeerAction('Shut down', { type: 'shutdown' }));
+        peerBulkSleepBtn.addEventListener('click', () => runBulkPeerAction('Sleep', { type: 'sleep' }));
+        peerBulkCancelBtn.addEventListener('click', () => runBulkPeerAction('Cancel shutdown', { type: 'cancel_shutdown' }));
+
+        peersPanel.addEventListener('toggle', () => {
+            clearInterval(peerInterval);
+            if (peersPanel.open) {
+                fetchPeers();
+                peerInterval = setInterval(fetchPeers, 10000);
+            }
+        });
+
+        async function wakeMachine(name) {
+            try {
+                const response = await fetch(`/api/wol/${encodeURIComponent(name)}`, { method: 'POST' });
+                const data = await response.json();
+                showStatus(data.success ? data.message : `Error: ${data.message}`, data.success ? 'success' : 'error');
+            } catch (error) {
+                showStatus(`Network error: ${error.message}`, 'error');
+            }
+        }
+
+        async function fetchWolMachines() {
+            try {
+                const response = await fetch('/api/wol');
+                const machines = await response.json();
+                wolTableBody.innerHTML = '';
+
+                if (machines.length === 0) {
+                    const row = document.createElement('tr');
+                    const cell = document.createElement('td');
+                    cell.colSpan = 3;
+                    cell.textContent = 'No machines configured. Add them in the desktop app.';
+                    row.appendChild(cell);
+                    wolTableBody.appendChild(row);
+                }
+
+                machines.forEach((machine) => {
+                    const row = document.createElement('tr');
+                    const nameCell = document.createElement('td');
+                    nameCell.textContent = machine.name;
+                    const macCell = document.createElement('td');
+                    macCell.textContent = machine.mac;
+
+                    const actionCell = document.createElement('td');
+                    const wakeBtn = document.createElement('button');
+                    wakeBtn.className = 'small-btn';
+                    wakeBtn.textContent = 'Wake';
+                    wakeBtn.addEventListener('click', () => wakeMachine(machine.name));
+                    actionCell.appendChild(wakeBtn);
+
+                    row.appendChild(nameCell);
+                    row.appendChild(macCell);
+                    row.appendChild(actionCell);
+                    wolTableBody.appendChild(row);
+                });
+            } catch (error) {
+                console.error('Failed to fetch Wake-on-LAN machines:', error);
+            }
+        }
+
+        wolPanel.addEventListener('toggle', () => {
+            if (wolPanel.open) {
+                fetchWolMachines();
+            }
+        });
+
+        function showClipboard(content) {
+            clipboardText.value = content.text || '';
+            if (content.image) {
+                clipboardImage.src = `/api/clipboard/image?t=${Date.now()}`;
+                clipboardImage.style.display = 'block';
+            } else {
+                clipboardImage.removeAttribute('src');
+                clipboardImage.style.display = 'none';
+            }
+        }
+
+        async function fetchClipboard() {
+            try {
+                const response = await fetch('/api/clipboard');
+                const data = await response.json();
+                if (response.ok) {
+                    showClipboard(data);
+                } else {
+                    showStatus(`Error: ${data.message}`, 'error');
+                }
+            } catch (error) {
+                showStatus('Failed to read clipboard', 'error');
+                console.error('Failed to read clipboard:', error);
+            }
+        }
+
+        async function sendClipboardText() {
+            try {
+                const response = await fetch('/api/clipboard', {
+                    method: 'POST',
+                    headers: { 'Content-Type': 'application/json' },
+                    body: JSON.stringify({ text: clipboardText.value }),
+                });
+                const data = await response.json();
+                showStatus(data.success ? data.message : `Error: ${data.message}`, data.success ? 'success' : 'error');
+            } catch (error) {
+                showStatus('Failed to set clipboard', 'error');
+                console.error('Failed to set clipboard:', error);
+            }
+        }
+
+        async function sendClipboardImage(file) {
+            try {
+                const response = await fetch('/api/clipboard/image', {
+                    method: 'POST',
+                    headers: { 'Content-Type': file.type || 'application/octet-stream' },
+                    body: file,
+                });
+                const data = await response.json();
+                showStatus(data.success ? data.message : `Error: ${data.message}`, data.success ? 'success' : 'error');
+            } catch (error) {
+                showStatus('Failed to set clipboard image', 'error');
+                console.error('Failed to set clipboard image:', error);
+            }
+        }
+
+        // Keep the panel in sync with the PC clipboard while it is open
+        let clipboardEvents = null;
+
+        clipboardPanel.addEventListener('toggle', () => {
+            if (clipboardPanel.open) {
+                fetchClipboard();
+                clipboardEvents = new EventSource('/api/clipboard/events');
+                clipboardEvents.onmessage = (event) => showClipboard(JSON.parse(event.data));
+            } else if (clipboardEvents) {
+                clipboardEvents.close();
+                clipboardEvents = null;
+            }
+        });
+
+        clipboardGetBtn.addEventListener('click', fetchClipboard);
+        clipboardSendBtn.addEventListener('click', sendClipboardText);
+        clipboardCopyBtn.addEventListener('click', async () => {
+            try {
+                await navigator.clipboard.writeText(clipboardText.value);
+                showStatus('Copied to this device', 'success');
+            } catch (error) {
+                // The async clipboard API needs a secure context, so fall back to a selection copy
+                clipboardText.select();
+                const copied = document.execCommand('copy');
+                showStatus(copied ? 'Copied to this device' : 'Copy failed', copied ? 'success' : 'error');
+            }
+        });
+        clipboardImageInput.addEventListener('change', () => {
+            if (clipboardImageInput.files.length > 0) {
+                sendClipboardImage(clipboardImageInput.files[0]);
+                clipboardImageInput.value = '';
+            }
+        });
+
+        function uploadFiles() {
+            if (uploadInput.files.length === 0) {
+                showStatus('Choose a file to upload', 'error');
+                return;
+            }
+
+            const form = new FormData();
+            Array.from(uploadInput.files).forEach((file) => form.append('file', file, file.name));
+
+            // XMLHttpRequest rather than fetch to get upload progress
+            const request = new XMLHttpRequest();
+            request.open('POST', '/api/files/upload');
+            request.upload.onprogress = (event) => {
+                if (event.lengthComputable) {
+                    uploadProgress.value = (event.loaded / event.total) * 100;
+                }
+            };
+            request.onload = () => {
+                uploadProgress.style.display = 'none';
+                uploadBtn.disabled = false;
+                try {
+                    const data = JSON.parse(request.responseText);
+                    showStatus(data.success ? data.message : `Error: ${data.message}`, data.success ? 'success' : 'error');
+                } catch (error) {
+                    showStatus(`Upload failed (${request.status})`, 'error');
+                }
+                uploadInput.value = '';
+            };
+            request.onerror = () => {
+                uploadProgress.style.display = 'none';
+                uploadBtn.disabled = false;
+                showStatus('Upload failed', 'error');
+            };
+
+            uploadProgress.value = 0;
+            uploadProgress.style.display = 'block';
+            uploadBtn.disabled = true;
+            request.send(form);
+        }
+
+        let currentSharePath = '';
+
+        async function fetchShare(path) {
+            try {
+                const response = await fetch(`/api/files/share?${new URLSearchParams({ path })}`);
+                const data = await response.json();
+                if (!Array.isArray(data)) {
+                    shareTableBody.innerHTML = '';
+                    const row = document.createElement('tr');
+                    const cell = document.createElement('td');
+                    cell.colSpan = 3;
+                    cell.textContent = data.message;
+                    row.appendChild(cell);
+                    shareTableBody.appendChild(row);
+                    return;
+                }
+
+                currentSharePath = path;
+                sharePath.textContent = `/${path}`;
+                renderShare(data);
+            } catch (error) {
+                console.error('Failed to list shared files:', error);
+            }
+        }
+
+        function renderShare(entries) {
+            shareTableBody.innerHTML = '';
+
+            entries.forEach((entry) => {
+                const row = document.createElement('tr');
+
+                const nameCell = document.createElement('td');
+                nameCell.textContent = entry.is_dir ? `📁 ${entry.name}` : entry.name;
+                row.appendChild(nameCell);
+
+                const sizeCell = document.createElement('td');
+                sizeCell.textContent = entry.is_dir ? '' : formatBytes(entry.size);
+                row.appendChild(sizeCell);
+
+                const actionCell = document.createElement('td');
+                if (entry.is_dir) {
+                    const openBtn = document.createElement('button');
+                    openBtn.className = 'small-btn';
+                    openBtn.textContent = 'Open';
+                    openBtn.addEventListener('click', () => fetchShare(entry.path));
+                    actionCell.appendChild(openBtn);
+                } else {
+                    const link = document.createElement('a');
+                    link.className = 'small-btn';
+                    link.textContent = 'Download';
+                    link.href = `/api/files/download?${new URLSearchParams({ path: entry.path })}`;
+                    link.download = entry.name;
+                    actionCell.appendChild(link);
+                }
+                row.appendChild(actionCell);
+
+                shareTableBody.appendChild(row);
+            });
+        }
+
+        filesPanel.addEventListener('toggle', () => {
+            if (filesPanel.open) {
+                fetchShare(currentSharePath);
+            }
+        });
+
+        uploadBtn.addEventListener('click', uploadFiles);
+        shareUpBtn.addEventListener('click', () => {
+            fetchShare(currentSharePath.split('/').slice(0, -1).join('/'));
+        });
+
+        let liveController = null;
+
+        function indexOfSequence(buffer, sequence) {
+            for (let i = 0; i <= buffer.length - sequence.length; i++) {
+                if (sequence.every((byte, j) => buffer[i + j] === byte)) {
+                    return i;
+                }
+            }
+            return -1;
+        }
+
+        async function drawRegion(headers, jpeg) {
+            const region = /X-Region: (\d+),(\d+),(\d+),(\d+)/i.exec(headers);
+            const frameSize = /X-Frame-Size: (\d+),(\d+)/i.exec(headers);
+            if (!region || !frameSize) {
+                return;
+            }
+
+            const [frameWidth, frameHeight] = [parseInt(frameSize[1]), parseInt(frameSize[2])];
+            if (screenCanvas.width !== frameWidth || screenCanvas.height !== frameHeight) {
+                screenCanvas.width = frameWidth;
+                screenCanvas.height = frameHeight;
+            }
+
+            const bitmap = await createImageBitmap(new Blob([jpeg], { type: 'image/jpeg' }));
+            screenCanvas.getContext('2d').drawImage(bitmap, parseInt(region[1]), parseInt(region[2]));
+            bitmap.close();
+        }
+
+        // Live view: the server only sends the changed region of each frame,
+        // which is drawn onto a canvas holding the full picture
+        async function startLiveView() {
+            liveController = new AbortController();
+            screenLiveBtn.textContent = 'Stop';
+            screenPreview.style.display = 'none';
+            screenCanvas.style.display = 'block';
+
+            const width = Math.min(Math.round(screenCanvas.clientWidth * window.devicePixelRatio) || 800, 1280);
+            const params = new URLSearchParams({
+                monitor: monitorSelect.value || 0,
+                width,
+                regions: true,
+            });
+
+            try {
+                const response = await fetch(`/api/screen/stream?${params}`, { signal: liveController.signal });
+                const reader = response.body.getReader();
+                const decoder = new TextDecoder();
+                let buffer = new Uint8Array(0);
+
+                while (true) {
+                    const { value, done } = await reader.read();
+                    if (done) {
+                        break;
+                    }
+
+                    const merged = new Uint8Array(buffer.length + value.length);
+                    merged.set(buffer);
+                    merged.set(value, buffer.length);
+                    buffer = merged;
+
+                    while (true) {
+                        const headerEnd = indexOfSequence(buffer, [13, 10, 13, 10]);
+                        if (headerEnd < 0) {
+                            break;
+                        }
+
+                        const headers = decoder.decode(buffer.subarray(0, headerEnd));
+                        const length = parseInt((/Content-Length: (\d+)/i.exec(headers) || [])[1]);
+                        const bodyStart = headerEnd + 4;
+                        if (isNaN(length) || buffer.length < bodyStart + length + 2) {
+                            break;
+                        }
+
+                        const jpeg = buffer.slice(bodyStart, bodyStart + length);
+                        buffer = buffer.slice(bodyStart + length + 2);
+                        await drawRegion(headers, jpeg);
+                    }
+                }
+            } catch (error) {
+                if (error.name !== 'AbortError') {
+                    console.error('Live view failed:', error);
+                }
+            }
+
+            stopLiveView();
+        }
+
+        function stopLiveView() {
+            if (liveController) {
+                liveController.abort();
+                liveController = null;
+            }
+            screenLiveBtn.textContent = 'Live';
+            screenCanvas.style.display = 'none';
+            screenPreview.style.display = 'block';
+        }
+
+        screenPanel.addEventListener('toggle', async () => {
+            if (screenPanel.open) {
+                await fetchMonitors();
+                refreshScreenshot();
+            } else {
+                stopLiveView();
+            }
+        });
+
+        monitorSelect.addEventListener('change', () => {
+            if (liveController) {
+                stopLiveView();
+                startLiveView();
+            } else {
+                refreshScreenshot();
+            }
+        });
+        screenRefreshBtn.addEventListener('click', refreshScreenshot);
+        screenLiveBtn.addEventListener('click', () => {
+            if (liveController) {
+                stopLiveView();
+            } else {
+                startLiveView();
+            }
+        });
+    </script>
+</body>
+</html>"#;
+
+#[derive(Serialize, Deserialize)]
+struct ApiResponse {
+    success: bool,
+    message: String,
+}
+
+#[get("/")]
+async fn index() -> impl Responder {
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(HTML_CONTENT)
+}
+
+#[post("/api/shutdown")]
+async fn shutdown() -> impl Responder {
+    info!("Shutdown request received via web API");
+
+    let result = if cfg!(target_os = "windows") {
+        runs::spawn("shutdown", Command::new("shutdown").args(["/s", "/t", "60"]))
+    } else if cfg!(target_os = "linux") {
+        runs::spawn("shutdown", Command::new("shutdown").args(["-h", "+1"]))
+    } else if cfg!(target_os = "macos") {
+        runs::spawn(
+            "shutdown",
+            Command::new("sh")
+                .args(["-c", "sleep 60 && osascript -e 'tell app \"System Events\" to shut down' &"]),
+        )
+    } else {
+        return HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: "Unsupported operating system".to_string(),
+        });
+    };
+
+    match result {
+        Ok(_) => {
+            power::mark_shutdown_pending();
+            HttpResponse::Ok().json(ApiResponse {
+                success: true,
+                message: "Shutdown command executed".to_string(),
+            })
+        }
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: format!("Failed to execute shutdown: {}", e),
+        }),
+    }
+}
+
+#[post("/api/restart")]
+async fn restart() -> impl Responder {
+    info!("Restart request received via web API");
+
+    let result = if cfg!(target_os = "windows") {
+        runs::spawn("restart", Command::new("shutdown").args(["/r", "/t", "0"]))
+    } else if cfg!(target_os = "linux") {
+        runs::spawn("restart", Command::new("shutdown").args(["-r", "now"]))
+    } else if cfg!(target_os = "macos") {
+        runs::spawn("restart", Command::new("shutdown").args(["-r", "now"]))
+    } else {
+        return HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: "Unsupported operating system".to_string(),
+        });
+    };
+
+    match result {
+        Ok(_) => HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Restart command executed".to_string(),
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: format!("Failed to execute restart: {}", e),
+        }),
+    }
+}
+
+#[post("/api/cancel")]
+async fn cancel_shutdown() -> impl Responder {
+    info!("Cancel shutdown request received via web API");
+
+    let result = if cfg!(target_os = "windows") {
+        runs::spawn("cancel_shutdown", Command::new("shutdown").args(["/a"]))
+    } else if cfg!(target_os = "linux") {
+        runs::spawn("cancel_shutdown", Command::new("shutdown").args(["-c"]))
+    } else if cfg!(target_os = "macos") {
+        runs::spawn("cancel_shutdown", Command::new("pkill").args(["-f", "sleep 60 && osascript"]))
+    } else {
+        return HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: "Unsupported operating system".to_string(),
+        });
+    };
+
+    match result {
+        Ok(_) => {
+            power::clear_shutdown_pending();
+            HttpResponse::Ok().json(ApiResponse {
+                success: true,
+                message: "Shutdown cancelled".to_string(),
+            })
+        }
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: format!("Failed to cancel shutdown: {}", e),
+        }),
+    }
+}
+
+#[post("/api/sleep")]
+async fn sleep() -> impl Responder {
+    info!("Sleep request received via web API");
+
+    let result = if cfg!(target_os = "windows") {
+        runs::spawn(
+            "sleep",
+            Command::new("rundll32.exe").args(["powrprof.dll,SetSuspendState", "0,1,0"]),
+        )
+    } else if cfg!(target_os = "linux") {
+        runs::spawn("sleep", Command::new("systemctl").args(["suspend"]))
+    } else if cfg!(target_os = "macos") {
+        runs::spawn("sleep", Command::new("pmset").args(["sleepnow"]))
+    } else {
+        return HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: "Unsupported operating system".to_string(),
+        });
+    };
+
+    match result {
+        Ok(_) => HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Sleep command executed".to_string(),
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: format!("Failed to execute sleep: {}", e),
+        }),
+    }
+}
+
+#[get("/api/power/rules")]
+async fn list_power_rules() -> impl Responder {
+    HttpResponse::Ok().json(power_rules::list())
+}
+
+#[derive(Deserialize)]
+struct PowerRuleRequest {
+    condition: power_rules::Condition,
+    action: power_rules::PowerAction,
+}
+
+#[post("/api/power/rules")]
+async fn add_power_rule(req: web::Json<PowerRuleRequest>) -> impl Responder {
+    info!("Power rule request received via web API");
+    let req = req.into_inner();
+
+    // Checking the condition may refresh the process list or query the display server
+    match web::block(move || power_rules::add(req.condition, req.action)).await {
+        Ok(Ok(rule)) => HttpResponse::Ok().json(rule),
+        Ok(Err(message)) => HttpResponse::BadRequest().json(ApiResponse {
+            success: false,
+            message,
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: format!("Failed to add power rule: {}", e),
+        }),
+    }
+}
+
+#[delete("/api/power/rules/{id}")]
+async fn remove_power_rule(path: web::Path<u64>) -> impl Responder {
+    let id = path.into_inner();
+
+    if power_rules::remove(id) {
+        HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: format!("Power rule {} removed", id),
+        })
+    } else {
+        HttpResponse::NotFound().json(ApiResponse {
+            success: false,
+            message: format!("Power rule {} not found", id),
+        })
+    }
+}
+
+#[get("/api/schedules")]
+async fn list_schedules() -> impl Responder {
+    HttpResponse::Ok().json(schedules::list())
+}
+
+#[post("/api/schedules")]
+async fn create_schedule(req: web::Json<schedules::ScheduleSpec>) -> impl Responder {
+    info!(name = %req.name, "Create schedule request received via web API");
+
+    let spec = req.into_inner();
+    match web::block(move || schedules::create(spec)).await {
+        Ok(Ok(schedule)) => HttpResponse::Ok().json(schedule),
+        Ok(Err(message)) => HttpResponse::BadRequest().json(ApiResponse {
+            success: false,
+            message,
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: format!("Failed to create schedule: {}", e),
+        }),
+    }
+}
+
+#[put("/api/schedules/{id}")]
+async fn update_schedule(
+    path: web::Path<u64>,
+    req: web::Json<schedules::ScheduleSpec>,
+) -> impl Responder {
+    let id = path.into_inner();
+    info!(id, "Update schedule request received via web API");
+
+    let spec = req.into_inner();
+    match web::block(move || schedules::update(id, spec)).await {
+        Ok(Ok(Some(schedule))) => HttpResponse::Ok().json(schedule),
+        Ok(Ok(None)) => HttpResponse::NotFound().json(ApiResponse {
+            success: false,
+            message: format!("Schedule {} not found", id),
+        }),
+        Ok(Err(message)) => HttpResponse::BadRequest().json(ApiResponse {
+            success: false,
+            message,
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: format!("Failed to update schedule: {}", e),
+        }),
+    }
+}
+
+#[delete("/api/schedules/{id}")]
+async fn delete_schedule(path: web::Path<u64>) -> impl Responder {
+    let id = path.into_inner();
+    info!(id, "Delete schedule request received via web API");
+
+    match web::block(move || schedules::delete(id)).await {
+        Ok(Ok(true)) => HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: format!("Schedule {} deleted", id),
+        }),
+        Ok(Ok(false)) => HttpResponse::NotFound().json(ApiResponse {
+            success: false,
+            message: format!("Schedule {} not found", id),
+        }),
+        Ok(Err(message)) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message,
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: format!("Failed to delete schedule: {}", e),
+        }),
+    }
+}
+
+#[derive(Deserialize)]
+struct SchedulePreviewQuery {
+    cron: String,
+}
+
+#[get("/api/schedules/preview")]
+async fn preview_schedule(query: web::Query<SchedulePreviewQuery>) -> impl Responder {
+    match schedules::preview(&query.cron, 5) {
+        Ok(runs) => HttpResponse::Ok().json(runs),
+        Err(message) => HttpResponse::BadRequest().json(ApiResponse {
+            success: false,
+            message,
+        }),
+    }
+}
+
+#[get("/api/wol")]
+async fn list_wol_machines() -> impl Responder {
+    HttpResponse::Ok().json(wol::machines())
+}
+
+#[post("/api/wol/{name}")]
+async fn wake_machine(path: web::Path<String>) -> impl Responder {
+    let name = path.into_inner();
+    info!(%name, "Wake-on-LAN request received via web API");
+
+    match wol::wake(&name) {
+        Ok(message) => HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message,
+        }),
+        Err(e @ wol::WakeError::UnknownMachine(_)) => HttpResponse::NotFound().json(ApiResponse {
+            success: false,
+            message: e.to_string(),
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: e.to_string(),
+        }),
+    }
+}
+
+#[get("/api/peers")]
+async fn list_peers() -> impl Responder {
+    match web::block(peers::statuses).await {
+        Ok(statuses) => HttpResponse::Ok().json(statuses),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: format!("Failed to query peers: {}", e),
+        }),
+    }
+}
+
+#[get("/api/peers/{name}/status")]
+async fn get_peer_status(path: web::Path<String>) -> impl Responder {
+    let name = path.into_inner();
+
+    match web::block(move || peers::system_status(&name)).await {
+        Ok(Ok(status)) => HttpResponse::Ok().json(status),
+        Ok(Err(e @ peers::PeerError::UnknownPeer(_))) => {
+            HttpResponse::NotFound().json(ApiResponse {
+                success: false,
+                message: e.to_string(),
+            })
+        }
+        Ok(Err(e)) => HttpResponse::BadGateway().json(ApiResponse {
+            success: false,
+            message: e.to_string(),
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: format!("Failed to query peer: {}", e),
+        }),
+    }
+}
+
+#[post("/api/peers/{name}/action")]
+async fn run_peer_action(
+    path: web::Path<String>,
+    req: web::Json<peers::PeerAction>,
+) -> impl Responder {
+    let name = path.into_inner();
+    info!(peer = %name, "Peer action request received via web API");
+    let action = req.into_inner();
+
+    match web::block(move || peers::run(&name, &action)).await {
+        Ok(Ok(message)) => HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message,
+        }),
+        Ok(Err(e @ peers::PeerError::UnknownPeer(_))) => {
+            HttpResponse::NotFound().json(ApiResponse {
+                success: false,
+                message: e.to_string(),
+            })
+        }
+        Ok(Err(e)) => HttpResponse::BadGateway().json(ApiResponse {
+            success: false,
+            message: e.to_string(),
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: format!("Failed to reach peer: {}", e),
+        }),
+    }
+}
+
+#[derive(Deserialize)]
+struct BulkPeerActionRequest {
+    group: Option<String>,
+    #[serde(default)]
+    peers: Vec<String>,
+    action: peers::PeerAction,
+}
+
+#[post("/api/peers/bulk")]
+async fn run_bulk_peer_action(req: web::Json<BulkPeerActionRequest>) -> impl Responder {
+    info!("Bulk peer action request received via web API");
+    let req = req.into_inner();
+
+    match web::block(move || peers::run_bulk(req.group.as_deref(), &req.peers, &req.action)).await {
+        Ok(Ok(results)) => HttpResponse::Ok().json(results),
+        Ok(Err(message)) => HttpResponse::BadRequest().json(ApiResponse {
+            success: false,
+            message,
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: format!("Failed to reach peers: {}", e),
+        }),
+    }
+}
+
+#[derive(Deserialize)]
+struct DiscoveryQuery {
+    /// How long to listen for answers, in seconds
+    timeout: Option<u64>,
+}
+
+#[get("/api/discovery")]
+async fn discover_instances(query: web::Query<DiscoveryQuery>) -> impl Responder {
+    let duration = Duration::from_secs(query.timeout.unwrap_or(3));
+
+    match web::block(move || discovery::browse(duration)).await {
+        Ok(Ok(instances)) => HttpResponse::Ok().json(instances),
+        Ok(Err(message)) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message,
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: format!("Failed to browse: {}", e),
+        }),
+    }
+}
+
+#[get("/api/audit")]
+async fn query_audit(query: web::Query<audit::AuditQuery>) -> impl Responder {
+    match web::block(move || audit::query(&query)).await {
+        Ok(entries) => HttpResponse::Ok().json(entries),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: format!("Failed to read the audit log: {}", e),
+        }),
+    }
+}
+
+#[derive(Serialize)]
+struct RoleResponse {
+    role: roles::Role,
+    /// Name the device is paired under, if any
+    device: Option<String>,
+}
+
+/// The calling device's role, so the page can hide controls it can't use
+#[get("/api/role")]
+async fn get_role(req: HttpRequest) -> impl Responder {
+    let token = device_token(&req);
+    let role = req
+        .peer_addr()
+        .and_then(|addr| roles::role_for(addr.ip(), token.as_deref()).ok());
+    HttpResponse::Ok().json(RoleResponse {
+        role: role.unwrap_or(roles::Role::Viewer),
+        device: token
+            .as_deref()
+            .and_then(roles::device_for)
+            .map(|device| device.name),
+    })
+}
+
+#[derive(Deserialize)]
+struct PairQuery {
+    token: String,
+}
+
+/// Pair this browser with a device from the device roles by keeping its token
+/// in a cookie. The desktop app shows this link as a QR code.
+#[get("/api/pair")]
+async fn pair_device(req: HttpRequest, query: web::Query<PairQuery>) -> impl Responder {
+    let token = query.into_inner().token;
+    let Some(device) = roles::device_for(&token) else {
+        if let Some(addr) = req.peer_addr() {
+            rate_limit::record_auth_failure(addr.ip());
+        }
+        return HttpResponse::Unauthorized().json(ApiResponse {
+            success: false,
+            message: "This pairing link is not valid; scan the one shown on the PC".to_string(),
+        });
+    };
+
+    info!(device = %device.name, "Paired a browser");
+    let cookie = Cookie::build(roles::TOKEN_COOKIE, token)
+        .path("/")
+        .http_only(true)
+        .same_site(SameSite::Strict)
+        .permanent()
+        .finish();
+    HttpResponse::SeeOther()
+        .insert_header((header::LOCATION, "/"))
+        .cookie(cookie)
+        .finish()
+}
+
+#[post("/api/volume/increase")]
+async fn increase_volume() -> impl Responder {
+    info!("Increase volume request received via web API");
+
+    let volume_change = 2;
+
+    if cfg!(target_os = "windows") {
+        #[cfg(target_os = "windows")]
+        {
+            match volume_control::VolumeControl::increase_volume(volume_change) {
+                Ok(new_volume) => HttpResponse::Ok().json(ApiResponse {
+                    success: true,
+                    message: format!("Volume increased to {}%", new_volume),
+                }),
+                Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+                    success: false,
+                    message: format!("Failed to increase volume: {}", e),
+                }),
+            }
+        }
+        #[cfg(not(target_os = "windows"))]
+        {
+            HttpResponse::InternalServerError().json(ApiResponse {
+                success: false,
+                message: "Windows-only code path".to_string(),
+            })
+        }
+    } else if cfg!(target_os = "linux") {
+        let pactl_result = Command::new("pactl")
+            .args(["set-sink-volume", "@DEFAULT_SINK@", &format!("+{}%", volume_change)])
+            .spawn();
+
+        let result = if pactl_result.is_ok() {
+            pactl_result
+        } else {
+            Command::new("amixer")
+                .args(["set", "Master", &format!("{}%+", volume_change)])
+                .spawn()
+        };
+
+        match result {
+            Ok(_) => HttpResponse::Ok().json(ApiResponse {
+                success: true,
+                message: format!("Volume increased by {}", volume_change),
+            }),
+            Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+                success: false,
+                message: format!("Failed to increase volume: {}", e),
+            }),
+        }
+    } else if cfg!(target_os = "macos") {
+        let result = Command::new("osascript")
+            .args([
+                "-e",
+                &format!(
+                    "set volume output volume (output volume of (get volume settings) + {})",
+                    volume_change
+                )
+            ])
+            .spawn();
+
+        match result {
+            Ok(_) => HttpResponse::Ok().json(ApiResponse {
+                success: true,
+                message: format!("Volume increased by {}", volume_change),
+            }),
+            Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+                success: false,
+                message: format!("Failed to increase volume: {}", e),
+            }),
+        }
+    } else {
+        HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: "Unsupported operating system".to_string(),
+        })
+    }
+}
+
+#[post("/api/volume/decrease")]
+async fn decrease_volume() -> impl Responder {
+    info!("Decrease volume request received via web API");
+
+    let volume_change = 2;
+
+    if cfg!(target_os = "windows") {
+        #[cfg(target_os = "windows")]
+        {
+            match volume_control::VolumeControl::decrease_volume(volume_change) {
+                Ok(new_volume) => HttpResponse::Ok().json(ApiResponse {
+                    success: true,
+                    message: format!("Volume decreased to {}%", new_volume),
+                }),
+                Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+                    success: false,
+                    message: format!("Failed to decrease volume: {}", e),
+                }),
+            }
+        }
+        #[cfg(not(target_os = "windows"))]
+        {
+            HttpResponse::InternalServerError().json(ApiResponse {
+                success: false,
+                message: "Windows-only code path".to_string(),
+            })
+        }
+    } else if cfg!(target_os = "linux") {
+        let pactl_result = Command::new("pactl")
+            .args(["set-sink-volume", "@DEFAULT_SINK@", &format!("-{}%", volume_change)])
+            .spawn();
+
+        let result = if pactl_result.is_ok() {
+            pactl_result
+        } else {
+            Command::new("amixer")
+                .args(["set", "Master", &format!("{}%-", volume_change)])
+                .spawn()
+        };
+
+        match result {
+            Ok(_) => HttpResponse::Ok().json(ApiResponse {
+                success: true,
+                message: format!("Volume decreased by {}", volume_change),
+            }),
+            Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+                success: false,
+                message: format!("Failed to decrease volume: {}", e),
+            }),
+        }
+    } else if cfg!(target_os = "macos") {
+        let result = Command::new("osascript")
+            .args([
+                "-e",
+                &format!(
+                    "set volume output volume (output volume of (get volume settings) - {})",
+                    volume_change
+                )
+            ])
+            .spawn();
+
+        match result {
+            Ok(_) => HttpResponse::Ok().json(ApiResponse {
+                success: true,
+                message: format!("Volume decreased by {}", volume_change),
+            }),
+            Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+                success: false,
+                message: format!("Failed to decrease volume: {}", e),
+            }),
+        }
+    } else {
+        HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: "Unsupported operating system".to_string(),
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct VolumeResponse {
+    volume: i32,
+}
+
+#[get("/api/volume/get")]
+async fn get_volume() -> impl Responder {
+    info!("Get volume request received via web API");
+
+    if cfg!(target_os = "windows") {
+        #[cfg(target_os = "windows")]
+        {
+            match volume_control::VolumeControl::get_volume() {
+                Ok(volume) => {
+                    tracing::debug!(volume, "Retrieved volume");
+                    HttpResponse::Ok().json(VolumeResponse {
+                        volume,
+                    })
+                },
+                Err(e) => {
+                    HttpResponse::InternalServerError().json(ApiResponse {
+                        success: false,
+                        message: format!("Failed to get volume: {}", e),
+                    })
+                }
+            }
+        }
+        #[cfg(not(target_os = "windows"))]
+        {
+            HttpResponse::InternalServerError().json(ApiResponse {
+                success: false,
+                message: "Windows-only code path".to_string(),
+            })
+        }
     } else if cfg!(target_os = "macos") {
-        Command::new("pmset").args(["sleepnow"]).spawn()
+        let output = std::process::Command::new("osascript")
+            .args(["-e", "output volume of (get volume settings)"])
+            .output();
+
+        match output {
+            Ok(result) => {
+                let stdout = String::from_utf8_lossy(&result.stdout);
+                let volume_str = stdout.trim();
+                match volume_str.parse::<i32>() {
+                    Ok(vol) => {
+                        let volume = vol.min(100).max(0);
+                        tracing::debug!(volume, "Retrieved volume");
+                        HttpResponse::Ok().json(VolumeResponse {
+                            volume,
+                        })
+                    },
+                    Err(_) => {
+                        HttpResponse::InternalServerError().json(ApiResponse {
+                            success: false,
+                            message: "Failed to parse volume".to_string(),
+                        })
+                    }
+                }
+            }
+            Err(e) => {
+                HttpResponse::InternalServerError().json(ApiResponse {
+                    success: false,
+                    message: format!("Failed to get volume: {}", e),
+                })
+            }
+        }
     } else {
-        return HttpResponse::InternalServerError().json(ApiResponse {
+        HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: "Unsupported operating system for volume detection".to_string(),
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct SetVolumeRequest {
+    volume: i32,
+}
+
+#[post("/api/volume/set")]
+async fn set_volume(req: actix_web::web::Json<SetVolumeRequest>) -> impl Responder {
+    info!(level = req.volume, "Set volume request received via web API");
+
+    let volume_level = req.volume.clamp(0, 100);
+
+    if cfg!(target_os = "windows") {
+        #[cfg(target_os = "windows")]
+        {
+            match volume_control::VolumeControl::set_volume(volume_level) {
+                Ok(_) => {
+                    tracing::debug!(level = volume_level, "Set volume");
+                    HttpResponse::Ok().json(ApiResponse {
+                        success: true,
+                        message: format!("Volume set to {}%", volume_level),
+                    })
+                },
+                Err(e) => {
+                    HttpResponse::InternalServerError().json(ApiResponse {
+                        success: false,
+                        message: format!("Failed to set volume: {}", e),
+                    })
+                }
+            }
+        }
+        #[cfg(not(target_os = "windows"))]
+        {
+            HttpResponse::InternalServerError().json(ApiResponse {
+                success: false,
+                message: "Windows-only code path".to_string(),
+            })
+        }
+    } else if cfg!(target_os = "linux") {
+        // Try pactl (PulseAudio) first, fallback to amixer (ALSA)
+        let pactl_result = Command::new("pactl")
+            .args(["set-sink-volume", "@DEFAULT_SINK@", &format!("{}%", volume_level)])
+            .spawn();
+
+        let result = if pactl_result.is_ok() {
+            pactl_result
+        } else {
+            Command::new("amixer")
+                .args(["set", "Master", &format!("{}%", volume_level)])
+                .spawn()
+        };
+
+        match result {
+            Ok(_) => HttpResponse::Ok().json(ApiResponse {
+                success: true,
+                message: format!("Volume set to {}%", volume_level),
+            }),
+            Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+                success: false,
+                message: format!("Failed to set volume: {}", e),
+            }),
+        }
+    } else if cfg!(target_os = "macos") {
+        let result = Command::new("osascript")
+            .args([
+                "-e",
+                &format!("set volume output volume {}", volume_level)
+            ])
+            .spawn();
+
+        match result {
+            Ok(_) => HttpResponse::Ok().json(ApiResponse {
+                success: true,
+                message: format!("Volume set to {}%", volume_level),
+            }),
+            Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+                success: false,
+                message: format!("Failed to set volume: {}", e),
+            }),
+        }
+    } else {
+        HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: "Unsupported operating system".to_string(),
+        })
+    }
+}
+
+#[get("/api/display/brightness")]
+async fn get_brightness() -> impl Responder {
+    info!("Get brightness request received via web API");
+
+    // Querying external monitors runs ddcutil, which can take seconds
+    match web::block(brightness::list).await {
+        Ok(Ok(displays)) => HttpResponse::Ok().json(displays),
+        Ok(Err(e)) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: format!("Failed to get brightness: {}", e),
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: format!("Failed to get brightness: {}", e),
+        }),
+    }
+}
+
+#[derive(Deserialize)]
+struct SetBrightnessRequest {
+    brightness: i32,
+    display: Option<String>,
+}
+
+#[post("/api/display/brightness")]
+async fn set_brightness(req: web::Json<SetBrightnessRequest>) -> impl Responder {
+    info!(level = req.brightness, display = ?req.display, "Set brightness request received via web API");
+
+    let SetBrightnessRequest {
+        brightness: level,
+        display,
+    } = req.into_inner();
+    match web::block(move || brightness::set(display.as_deref(), level)).await {
+        Ok(Ok(displays)) => HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: format!(
+                "Brightness set to {}% on {} display(s)",
+                level.clamp(0, 100),
+                displays.len()
+            ),
+        }),
+        Ok(Err(e)) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: e,
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: format!("Failed to set brightness: {}", e),
+        }),
+    }
+}
+
+#[get("/api/screen/monitors")]
+async fn list_monitors() -> impl Responder {
+    match web::block(screen::monitors).await {
+        Ok(Ok(monitors)) => HttpResponse::Ok().json(monitors),
+        Ok(Err(e)) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: format!("Failed to list monitors: {}", e),
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: format!("Failed to list monitors: {}", e),
+        }),
+    }
+}
+
+#[derive(Deserialize)]
+struct ScreenshotQuery {
+    monitor: Option<usize>,
+    width: Option<u32>,
+    format: Option<screen::ImageFormat>,
+    quality: Option<u8>,
+}
+
+#[get("/api/screen/screenshot")]
+async fn screenshot(query: web::Query<ScreenshotQuery>) -> impl Responder {
+    info!("Screenshot request received via web API");
+
+    let monitor = query.monitor.unwrap_or(0);
+    let width = query.width;
+    let format = query.format.unwrap_or_default();
+    let quality = query.quality.unwrap_or(80);
+
+    match web::block(move || screen::screenshot(monitor, width, format, quality)).await {
+        Ok(Ok(image)) => HttpResponse::Ok()
+            .content_type(format.content_type())
+            .insert_header(("Cache-Control", "no-store"))
+            .body(image),
+        Ok(Err(e)) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: e,
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: format!("Failed to capture screen: {}", e),
+        }),
+    }
+}
+
+#[derive(Deserialize)]
+struct StreamQuery {
+    monitor: Option<usize>,
+    width: Option<u32>,
+    fps: Option<u32>,
+    quality: Option<u8>,
+    regions: Option<bool>,
+}
+
+#[get("/api/screen/stream")]
+async fn stream_screen(query: web::Query<StreamQuery>) -> impl Responder {
+    info!("Screen stream request received via web API");
+
+    let config = config::get();
+    let options = screen_stream::StreamOptions {
+        monitor: query.monitor.unwrap_or(0),
+        width: query.width,
+        fps: query.fps.unwrap_or(config.stream_fps),
+        quality: query.quality.unwrap_or(config.stream_quality),
+        regions: query.regions.unwrap_or(false),
+    };
+
+    let body = screen_stream::frames(options)
+        .map(|part| Ok::<_, actix_web::Error>(web::Bytes::from(part)));
+
+    HttpResponse::Ok()
+        .content_type(format!(
+            "multipart/x-mixed-replace; boundary={}",
+            screen_stream::BOUNDARY
+        ))
+        .insert_header(("Cache-Control", "no-store"))
+        .streaming(body)
+}
+
+#[get("/share")]
+async fn share_target() -> impl Responder {
+    // The page reads the shared link from the query string itself
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(HTML_CONTENT)
+}
+
+#[get("/manifest.webmanifest")]
+async fn web_manifest() -> impl Responder {
+    HttpResponse::Ok()
+        .content_type("application/manifest+json")
+        .json(serde_json::json!({
+            "name": "Ferrous Control",
+            "short_name": "Ferrous",
+            "start_url": "/",
+            "display": "standalone",
+            "background_color": "#667eea",
+            "theme_color": "#667eea",
+            "icons": [{ "src": "/icon.png", "sizes": "512x512", "type": "image/png" }],
+            "share_target": {
+                "action": "/share",
+                "method": "GET",
+                "params": { "title": "title", "text": "text", "url": "url" }
+            }
+        }))
+}
+
+#[get("/icon.png")]
+async fn app_icon() -> impl Responder {
+    HttpResponse::Ok()
+        .content_type("image/png")
+        .body(&include_bytes!("../icons/icon.png")[..])
+}
+
+#[derive(Deserialize)]
+struct OpenUrlRequest {
+    url: String,
+}
+
+#[post("/api/open-url")]
+async fn open_link(req: web::Json<OpenUrlRequest>) -> impl Responder {
+    info!(url = %req.url, "Open URL request received via web API");
+
+    let url = req.into_inner().url;
+    // Blocks while the desktop confirmation prompt (if enabled) is open
+    match web::block(move || open_url::open(&url)).await {
+        Ok(Ok(url)) => HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: format!("Opened {}", url),
+        }),
+        Ok(Err(e)) => HttpResponse::BadRequest().json(ApiResponse {
+            success: false,
+            message: e,
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: format!("Failed to open URL: {}", e),
+        }),
+    }
+}
+
+#[derive(Deserialize)]
+struct NotifyRequest {
+    title: String,
+    #[serde(default)]
+    body: String,
+    #[serde(default)]
+    urgency: notify::Urgency,
+}
+
+#[post("/api/notify")]
+async fn send_notification(req: web::Json<NotifyRequest>) -> impl Responder {
+    info!(title = %req.title, "Notification request received via web API");
+
+    let NotifyRequest {
+        title,
+        body,
+        urgency,
+    } = req.into_inner();
+    match web::block(move || notify::send(&title, &body, urgency)).await {
+        Ok(Ok(())) => HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Notification shown".to_string(),
+        }),
+        Ok(Err(notify::NotifyError::RateLimited(retry_after))) => HttpResponse::TooManyRequests()
+            .insert_header(("Retry-After", retry_after.as_secs().max(1).to_string()))
+            .json(ApiResponse {
+                success: false,
+                message: notify::NotifyError::RateLimited(retry_after).to_string(),
+            }),
+        Ok(Err(e @ notify::NotifyError::Invalid(_))) => {
+            HttpResponse::BadRequest().json(ApiResponse {
+                success: false,
+                message: e.to_string(),
+            })
+        }
+        Ok(Err(e)) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: e.to_string(),
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: format!("Failed to show notification: {}", e),
+        }),
+    }
+}
+
+#[derive(Deserialize)]
+struct SpeakRequest {
+    text: String,
+    #[serde(flatten)]
+    options: speech::SpeakOptions,
+}
+
+#[post("/api/speak")]
+async fn speak(req: web::Json<SpeakRequest>) -> impl Responder {
+    info!("Speak request received via web API");
+
+    let SpeakRequest { text, options } = req.into_inner();
+    match web::block(move || speech::speak(&text, &options)).await {
+        Ok(Ok(run)) => HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: match run {
+                Some(id) => format!("Speaking (run {})", id),
+                None => "Speaking".to_string(),
+            },
+        }),
+        Ok(Err(e)) => HttpResponse::BadRequest().json(ApiResponse {
+            success: false,
+            message: e,
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: format!("Failed to speak: {}", e),
+        }),
+    }
+}
+
+#[get("/api/clipboard")]
+async fn get_clipboard() -> impl Responder {
+    info!("Get clipboard request received via web API");
+
+    match web::block(clipboard::get).await {
+        Ok(Ok(content)) => HttpResponse::Ok().json(content),
+        Ok(Err(e)) => HttpResponse::InternalServerError().json(ApiResponse {
             success: false,
-            message: "Unsupported operating system".to_string(),
-        });
-    };
+            message: e,
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: format!("Failed to read clipboard: {}", e),
+        }),
+    }
+}
 
-    match result {
-        Ok(_) => HttpResponse::Ok().json(ApiResponse {
+#[derive(Deserialize)]
+struct ClipboardTextRequest {
+    text: String,
+}
+
+#[post("/api/clipboard")]
+async fn set_clipboard(req: web::Json<ClipboardTextRequest>) -> impl Responder {
+    info!("Set clipboard request received via web API");
+
+    let text = req.into_inner().text;
+    let length = text.chars().count();
+    match web::block(move || clipboard::set_text(&text)).await {
+        Ok(Ok(())) => HttpResponse::Ok().json(ApiResponse {
             success: true,
-            message: "Sleep command executed".to_string(),
+            message: format!("Copied {} characters to the clipboard", length),
+        }),
+        Ok(Err(e)) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: e,
         }),
         Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
             success: false,
-            message: format!("Failed to execute sleep: {}", e),
+            message: format!("Failed to set clipboard: {}", e),
         }),
     }
 }
 
-#[post("/api/volume/increase")]
-async fn increase_volume() -> impl Responder {
-    println!("Increase volume request received via web API");
+#[get("/api/clipboard/image")]
+async fn get_clipboard_image() -> impl Responder {
+    info!("Get clipboard image request received via web API");
 
-    let volume_change = 2;
+    match web::block(clipboard::get_image_png).await {
+        Ok(Ok(png)) => HttpResponse::Ok()
+            .content_type("image/png")
+            .insert_header(("Cache-Control", "no-store"))
+            .body(png),
+        Ok(Err(e)) => HttpResponse::NotFound().json(ApiResponse {
+            success: false,
+            message: e,
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: format!("Failed to read clipboard: {}", e),
+        }),
+    }
+}
 
-    if cfg!(target_os = "windows") {
-        #[cfg(target_os = "windows")]
-        {
-            match volume_control::VolumeControl::increase_volume(volume_change) {
-                Ok(new_volume) => HttpResponse::Ok().json(ApiResponse {
-                    success: true,
-                    message: format!("Volume increased to {}%", new_volume),
-                }),
-                Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+#[post("/api/clipboard/image")]
+async fn set_clipboard_image(body: web::Bytes) -> impl Responder {
+    info!("Set clipboard image request received via web API");
+
+    match web::block(move || clipboard::set_image(&body)).await {
+        Ok(Ok(())) => HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Image copied to the clipboard".to_string(),
+        }),
+        Ok(Err(e)) => HttpResponse::BadRequest().json(ApiResponse {
+            success: false,
+            message: e,
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: format!("Failed to set clipboard: {}", e),
+        }),
+    }
+}
+
+#[get("/api/clipboard/events")]
+async fn clipboard_events() -> impl Responder {
+    // Server-Sent Events: one JSON payload per clipboard change
+    let body = clipboard::changes().map(|content| {
+        let payload = serde_json::to_string(&content).unwrap_or_default();
+        Ok::<_, actix_web::Error>(web::Bytes::from(format!("data: {}\n\n", payload)))
+    });
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(body)
+}
+
+/// Stream one multipart field into a new file in the inbox
+async fn save_upload(
+    field: &mut Field,
+    inbox: &Path,
+    name: &str,
+    limit: u64,
+) -> Result<files::ReceivedFile, HttpResponse> {
+    let (path, mut file) = {
+        let (inbox, name) = (inbox.to_path_buf(), name.to_string());
+        match web::block(move || files::create_unique(&inbox, &name)).await {
+            Ok(Ok(created)) => created,
+            Ok(Err(e)) => {
+                return Err(HttpResponse::InternalServerError().json(ApiResponse {
                     success: false,
-                    message: format!("Failed to increase volume: {}", e),
-                }),
+                    message: format!("Failed to create file: {}", e),
+                }))
+            }
+            Err(e) => {
+                return Err(HttpResponse::InternalServerError().json(ApiResponse {
+                    success: false,
+                    message: format!("Failed to create file: {}", e),
+                }))
             }
         }
-        #[cfg(not(target_os = "windows"))]
-        {
-            HttpResponse::InternalServerError().json(ApiResponse {
-                success: false,
-                message: "Windows-only code path".to_string(),
-            })
-        }
-    } else if cfg!(target_os = "linux") {
-        let pactl_result = Command::new("pactl")
-            .args(["set-sink-volume", "@DEFAULT_SINK@", &format!("+{}%", volume_change)])
-            .spawn();
+    };
 
-        let result = if pactl_result.is_ok() {
-            pactl_result
-        } else {
-            Command::new("amixer")
-                .args(["set", "Master", &format!("{}%+", volume_change)])
-                .spawn()
-        };
+    let mut size: u64 = 0;
+    while let Some(chunk) = field.next().await {
+        let error = match chunk {
+            Ok(chunk) => {
+                size += chunk.len() as u64;
+                if size > limit {
+                    let _ = std::fs::remove_file(&path);
+                    return Err(HttpResponse::PayloadTooLarge().json(ApiResponse {
+                        success: false,
+                        message: format!(
+                            "{} is larger than the {} MB limit",
+                            name,
+                            limit / (1024 * 1024)
+                        ),
+                    }));
+                }
 
-        match result {
-            Ok(_) => HttpResponse::Ok().json(ApiResponse {
-                success: true,
-                message: format!("Volume increased by {}", volume_change),
-            }),
-            Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
-                success: false,
-                message: format!("Failed to increase volume: {}", e),
-            }),
-        }
-    } else if cfg!(target_os = "macos") {
-        let result = Command::new("osascript")
-            .args([
-                "-e",
-                &format!(
-                    "set volume output volume (output volume of (get volume settings) + {})",
-                    volume_change
-                )
-            ])
-            .spawn();
+                match web::block(move || file.write_all(&chunk).map(|_| file)).await {
+                    Ok(Ok(written)) => {
+                        file = written;
+                        continue;
+                    }
+                    Ok(Err(e)) => format!("Failed to write {}: {}", name, e),
+                    Err(e) => format!("Failed to write {}: {}", name, e),
+                }
+            }
+            Err(e) => format!("Upload of {} failed: {}", name, e),
+        };
 
-        match result {
-            Ok(_) => HttpResponse::Ok().json(ApiResponse {
-                success: true,
-                message: format!("Volume increased by {}", volume_change),
-            }),
-            Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
-                success: false,
-                message: format!("Failed to increase volume: {}", e),
-            }),
-        }
-    } else {
-        HttpResponse::InternalServerError().json(ApiResponse {
+        let _ = std::fs::remove_file(&path);
+        return Err(HttpResponse::BadRequest().json(ApiResponse {
             success: false,
-            message: "Unsupported operating system".to_string(),
-        })
+            message: error,
+        }));
     }
+
+    Ok(files::ReceivedFile {
+        name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        path: path.display().to_string(),
+        size,
+    })
 }
 
-#[post("/api/volume/decrease")]
-async fn decrease_volume() -> impl Responder {
-    println!("Decrease volume request received via web API");
+#[post("/api/files/upload")]
+async fn upload_files(mut payload: Multipart) -> impl Responder {
+    info!("File upload request received via web API");
 
-    let volume_change = 2;
+    let inbox = files::inbox_dir();
+    if let Err(e) = std::fs::create_dir_all(&inbox) {
+        return HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: format!("Failed to create inbox {}: {}", inbox.display(), e),
+        });
+    }
+    let limit = files::max_upload_bytes();
 
-    if cfg!(target_os = "windows") {
-        #[cfg(target_os = "windows")]
-        {
-            match volume_control::VolumeControl::decrease_volume(volume_change) {
-                Ok(new_volume) => HttpResponse::Ok().json(ApiResponse {
-                    success: true,
-                    message: format!("Volume decreased to {}%", new_volume),
-                }),
-                Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+    let mut received = Vec::new();
+    while let Some(field) = payload.next().await {
+        let mut field = match field {
+            Ok(field) => field,
+            Err(e) => {
+                return HttpResponse::BadRequest().json(ApiResponse {
                     success: false,
-                    message: format!("Failed to decrease volume: {}", e),
-                }),
+                    message: format!("Invalid upload: {}", e),
+                })
+            }
+        };
+
+        // Fields without a file name are plain form values, not files
+        let Some(name) = field
+            .content_disposition()
+            .and_then(|disposition| disposition.get_filename())
+            .and_then(files::sanitize_filename)
+        else {
+            continue;
+        };
+
+        match save_upload(&mut field, &inbox, &name, limit).await {
+            Ok(file) => {
+                files::received(file.clone());
+                received.push(file);
             }
+            Err(response) => return response,
         }
-        #[cfg(not(target_os = "windows"))]
-        {
-            HttpResponse::InternalServerError().json(ApiResponse {
+    }
+
+    if received.is_empty() {
+        return HttpResponse::BadRequest().json(ApiResponse {
+            success: false,
+            message: "No file received".to_string(),
+        });
+    }
+
+    let names: Vec<&str> = received.iter().map(|file| file.name.as_str()).collect();
+    HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        message: format!("Saved {} to {}", names.join(", "), inbox.display()),
+    })
+}
+
+#[derive(Deserialize)]
+struct SharePathQuery {
+    path: Option<String>,
+}
+
+#[get("/api/files/share")]
+async fn list_shared_files(query: web::Query<SharePathQuery>) -> impl Responder {
+    let path = query.into_inner().path.unwrap_or_default();
+
+    match web::block(move || files::list_share(&path)).await {
+        Ok(Ok(entries)) => HttpResponse::Ok().json(entries),
+        Ok(Err(e)) => HttpResponse::NotFound().json(ApiResponse {
+            success: false,
+            message: e,
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: format!("Failed to list files: {}", e),
+        }),
+    }
+}
+
+#[get("/api/files/download")]
+async fn download_file(req: HttpRequest, query: web::Query<SharePathQuery>) -> HttpResponse {
+    let path = query.into_inner().path.unwrap_or_default();
+    info!(%path, "Download request received via web API");
+
+    let resolved = match files::resolve_share(&path) {
+        Ok(resolved) if resolved.is_file() => resolved,
+        Ok(_) => {
+            return HttpResponse::BadRequest().json(ApiResponse {
                 success: false,
-                message: "Windows-only code path".to_string(),
+                message: "Not a file".to_string(),
             })
         }
-    } else if cfg!(target_os = "linux") {
-        let pactl_result = Command::new("pactl")
-            .args(["set-sink-volume", "@DEFAULT_SINK@", &format!("-{}%", volume_change)])
-            .spawn();
-
-        let result = if pactl_result.is_ok() {
-            pactl_result
-        } else {
-            Command::new("amixer")
-                .args(["set", "Master", &format!("{}%-", volume_change)])
-                .spawn()
-        };
-
-        match result {
-            Ok(_) => HttpResponse::Ok().json(ApiResponse {
-                success: true,
-                message: format!("Volume decreased by {}", volume_change),
-            }),
-            Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+        Err(e) => {
+            return HttpResponse::NotFound().json(ApiResponse {
                 success: false,
-                message: format!("Failed to decrease volume: {}", e),
-            }),
+                message: e,
+            })
         }
-    } else if cfg!(target_os = "macos") {
-        let result = Command::new("osascript")
-            .args([
-                "-e",
-                &format!(
-                    "set volume output volume (output volume of (get volume settings) - {})",
-                    volume_change
-                )
-            ])
-            .spawn();
+    };
 
-        match result {
-            Ok(_) => HttpResponse::Ok().json(ApiResponse {
-                success: true,
-                message: format!("Volume decreased by {}", volume_change),
-            }),
-            Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
-                success: false,
-                message: format!("Failed to decrease volume: {}", e),
-            }),
+    match NamedFile::open_async(&resolved).await {
+        Ok(file) => {
+            // Always download instead of letting the browser display images or text inline
+            let mut disposition = file.content_disposition().clone();
+            disposition.disposition = DispositionType::Attachment;
+            file.set_content_disposition(disposition)
+                .into_response(&req)
         }
-    } else {
-        HttpResponse::InternalServerError().json(ApiResponse {
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: format!("Failed to open file: {}", e),
+        }),
+    }
+}
+
+#[get("/api/runs")]
+async fn list_runs() -> impl Responder {
+    HttpResponse::Ok().json(runs::list())
+}
+
+#[get("/api/runs/{id}")]
+async fn get_run(path: web::Path<u64>) -> impl Responder {
+    let id = path.into_inner();
+
+    match runs::get(id) {
+        Some(run) => HttpResponse::Ok().json(run),
+        None => HttpResponse::NotFound().json(ApiResponse {
+            success: false,
+            message: format!("Run {} not found", id),
+        }),
+    }
+}
+
+#[get("/api/runs/{id}/stream")]
+async fn stream_run(path: web::Path<u64>) -> impl Responder {
+    let id = path.into_inner();
+
+    match runs::events(id) {
+        Some(events) => {
+            // Server-Sent Events: one JSON payload per output chunk
+            let body = events.map(|event| {
+                let payload = serde_json::to_string(&event).unwrap_or_default();
+                Ok::<_, actix_web::Error>(web::Bytes::from(format!("data: {}\n\n", payload)))
+            });
+
+            HttpResponse::Ok()
+                .content_type("text/event-stream")
+                .insert_header(("Cache-Control", "no-cache"))
+                .streaming(body)
+        }
+        None => HttpResponse::NotFound().json(ApiResponse {
+            success: false,
+            message: format!("Run {} not found", id),
+        }),
+    }
+}
+
+#[post("/api/runs/{id}/kill")]
+async fn kill_run(path: web::Path<u64>) -> impl Responder {
+    let id = path.into_inner();
+    info!(run = id, "Kill run request received via web API");
+
+    if runs::get(id).is_none() {
+        return HttpResponse::NotFound().json(ApiResponse {
+            success: false,
+            message: format!("Run {} not found", id),
+        });
+    }
+
+    match runs::kill(id) {
+        Ok(true) => HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: format!("Run {} killed", id),
+        }),
+        Ok(false) => HttpResponse::Conflict().json(ApiResponse {
+            success: false,
+            message: format!("Run {} has already finished", id),
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: e,
+        }),
+    }
+}
+
+#[get("/api/processes")]
+async fn list_processes() -> impl Responder {
+    info!("List processes request received via web API");
+
+    // Refreshing the process list blocks, and waits to prime CPU usage on first use
+    match web::block(processes::list).await {
+        Ok(processes) => HttpResponse::Ok().json(processes),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
             success: false,
-            message: "Unsupported operating system".to_string(),
-        })
+            message: format!("Failed to list processes: {}", e),
+        }),
     }
 }
 
-#[derive(Serialize)]
-struct VolumeResponse {
-    volume: i32,
+#[derive(Deserialize)]
+struct KillProcessRequest {
+    pid: Option<u32>,
+    name: Option<String>,
 }
 
-#[get("/api/volume/get")]
-async fn get_volume() -> impl Responder {
-    println!("Get volume request received via web API");
+#[post("/api/processes/kill")]
+async fn kill_process(req: web::Json<KillProcessRequest>) -> impl Responder {
+    info!(pid = ?req.pid, name = ?req.name, "Kill process request received via web API");
 
-    if cfg!(target_os = "windows") {
-        #[cfg(target_os = "windows")]
-        {
-            match volume_control::VolumeControl::get_volume() {
-                Ok(volume) => {
-                    println!("Successfully retrieved volume: {}%", volume);
-                    HttpResponse::Ok().json(VolumeResponse {
-                        volume,
-                    })
-                },
-                Err(e) => {
-                    HttpResponse::InternalServerError().json(ApiResponse {
-                        success: false,
-                        message: format!("Failed to get volume: {}", e),
-                    })
-                }
-            }
-        }
-        #[cfg(not(target_os = "windows"))]
-        {
-            HttpResponse::InternalServerError().json(ApiResponse {
+    let KillProcessRequest { pid, name } = req.into_inner();
+    let result = match (pid, name) {
+        (Some(pid), _) => web::block(move || processes::kill_pid(pid)).await,
+        (None, Some(name)) => web::block(move || processes::kill_name(&name)).await,
+        (None, None) => {
+            return HttpResponse::BadRequest().json(ApiResponse {
                 success: false,
-                message: "Windows-only code path".to_string(),
+                message: "Either pid or name is required".to_string(),
             })
         }
-    } else if cfg!(target_os = "macos") {
-        let output = std::process::Command::new("osascript")
-            .args(["-e", "output volume of (get volume settings)"])
-            .output();
+    };
 
-        match output {
-            Ok(result) => {
-                let stdout = String::from_utf8_lossy(&result.stdout);
-                let volume_str = stdout.trim();
-                match volume_str.parse::<i32>() {
-                    Ok(vol) => {
-                        let volume = vol.min(100).max(0);
-                        println!("Successfully retrieved volume: {}%", volume);
-                        HttpResponse::Ok().json(VolumeResponse {
-                            volume,
-                        })
-                    },
-                    Err(_) => {
-                        HttpResponse::InternalServerError().json(ApiResponse {
-                            success: false,
-                            message: "Failed to parse volume".to_string(),
-                        })
-                    }
-                }
-            }
-            Err(e) => {
-                HttpResponse::InternalServerError().json(ApiResponse {
-                    success: false,
-                    message: format!("Failed to get volume: {}", e),
-                })
-            }
+    match result {
+        Ok(Ok(message)) => HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message,
+        }),
+        Ok(Err(e @ processes::ProcessError::NotFound(_))) => {
+            HttpResponse::NotFound().json(ApiResponse {
+                success: false,
+                message: e.to_string(),
+            })
         }
-    } else {
-        HttpResponse::InternalServerError().json(ApiResponse {
+        Ok(Err(e @ processes::ProcessError::NotAllowed(_))) => {
+            HttpResponse::Forbidden().json(ApiResponse {
+                success: false,
+                message: e.to_string(),
+            })
+        }
+        Ok(Err(e)) => HttpResponse::InternalServerError().json(ApiResponse {
             success: false,
-            message: "Unsupported operating system for volume detection".to_string(),
-        })
+            message: e.to_string(),
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: format!("Failed to kill process: {}", e),
+        }),
     }
 }
 
-#[derive(Deserialize)]
-struct SetVolumeRequest {
-    volume: i32,
+#[get("/api/processes/launchable")]
+async fn list_launchable() -> impl Responder {
+    HttpResponse::Ok().json(processes::launchable())
 }
 
-#[post("/api/volume/set")]
-async fn set_volume(req: actix_web::web::Json<SetVolumeRequest>) -> impl Responder {
-    println!("Set volume request received via web API: {}%", req.volume);
+#[derive(Deserialize)]
+struct LaunchRequest {
+    name: String,
+}
 
-    let volume_level = req.volume.clamp(0, 100);
+#[post("/api/processes/launch")]
+async fn launch_application(req: web::Json<LaunchRequest>) -> impl Responder {
+    info!(name = %req.name, "Launch request received via web API");
 
-    if cfg!(target_os = "windows") {
-        #[cfg(target_os = "windows")]
-        {
-            match volume_control::VolumeControl::set_volume(volume_level) {
-                Ok(_) => {
-                    println!("Successfully set volume to {}%", volume_level);
-                    HttpResponse::Ok().json(ApiResponse {
-                        success: true,
-                        message: format!("Volume set to {}%", volume_level),
-                    })
-                },
-                Err(e) => {
-                    HttpResponse::InternalServerError().json(ApiResponse {
-                        success: false,
-                        message: format!("Failed to set volume: {}", e),
-                    })
-                }
-            }
+    let name = req.into_inner().name;
+    let launched = name.clone();
+    match web::block(move || processes::launch(&launched)).await {
+        Ok(Ok(pid)) => HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: format!("Launched {} (PID {})", name, pid),
+        }),
+        Ok(Err(e @ processes::ProcessError::NotFound(_))) => {
+            HttpResponse::NotFound().json(ApiResponse {
+                success: false,
+                message: e.to_string(),
+            })
         }
-        #[cfg(not(target_os = "windows"))]
-        {
-            HttpResponse::InternalServerError().json(ApiResponse {
+        Ok(Err(e @ processes::ProcessError::NotAllowed(_))) => {
+            HttpResponse::Forbidden().json(ApiResponse {
                 success: false,
-                message: "Windows-only code path".to_string(),
+                message: e.to_string(),
             })
         }
-    } else if cfg!(target_os = "linux") {
-        // Try pactl (PulseAudio) first, fallback to amixer (ALSA)
-        let pactl_result = Command::new("pactl")
-            .args(["set-sink-volume", "@DEFAULT_SINK@", &format!("{}%", volume_level)])
-            .spawn();
+        Ok(Err(e)) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: e.to_string(),
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: format!("Failed to launch {}: {}", name, e),
+        }),
+    }
+}
 
-        let result = if pactl_result.is_ok() {
-            pactl_result
+#[get("/api/system/status")]
+async fn get_system_status() -> impl Responder {
+    match web::block(system_status::status).await {
+        Ok(status) => HttpResponse::Ok().json(status),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: format!("Failed to read system status: {}", e),
+        }),
+    }
+}
+
+#[get("/metrics")]
+async fn get_metrics() -> impl Responder {
+    if !config::get().metrics_enabled {
+        return HttpResponse::NotFound().finish();
+    }
+
+    match web::block(metrics::render).await {
+        Ok(body) => HttpResponse::Ok()
+            .content_type("text/plain; version=0.0.4; charset=utf-8")
+            .body(body),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: format!("Failed to render metrics: {}", e),
+        }),
+    }
+}
+
+/// Count requests and their latency per route for `/metrics`.
+///
+/// This is the outermost middleware, so requests the other middleware
+/// reject (403, 429) are counted too.
+async fn track_metrics(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    if !config::get().metrics_enabled {
+        return next.call(req).await;
+    }
+
+    let method = req.method().to_string();
+    // Use the route pattern (e.g. /api/runs/{id}) to keep label cardinality bounded
+    let route = match route_pattern(&req) {
+        route if route.is_empty() => "unmatched".to_string(),
+        route => route,
+    };
+    let started = Instant::now();
+    let res = next.call(req).await;
+
+    let status = match &res {
+        Ok(res) => res.status(),
+        Err(e) => e.as_response_error().status_code(),
+    };
+    metrics::record_request(&method, &route, status.as_u16(), started.elapsed());
+
+    res
+}
+
+/// Sequence number identifying a request in the logs
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Run each request in its own span, so everything logged while handling it
+/// carries the request id, route and client
+async fn trace_request(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let client = req
+        .peer_addr()
+        .map(|addr| network::client_ip(addr.ip()).to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    let span = info_span!(
+        "request",
+        id = NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed),
+        method = %req.method(),
+        path = req.path(),
+        %client,
+    );
+    let is_get = req.method() == actix_web::http::Method::GET;
+    let started = Instant::now();
+
+    async move {
+        let res = next.call(req).await?;
+        let status = res.status().as_u16();
+        let elapsed_ms = started.elapsed().as_millis() as u64;
+        // Polling keeps GETs frequent, so only log them when debugging
+        if is_get {
+            debug!(status, elapsed_ms, "Request finished");
         } else {
-            Command::new("amixer")
-                .args(["set", "Master", &format!("{}%", volume_level)])
-                .spawn()
-        };
+            info!(status, elapsed_ms, "Request finished");
+        }
+        Ok(res)
+    }
+    .instrument(span)
+    .await
+}
 
-        match result {
-            Ok(_) => HttpResponse::Ok().json(ApiResponse {
-                success: true,
-                message: format!("Volume set to {}%", volume_level),
-            }),
-            Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
-                success: false,
-                message: format!("Failed to set volume: {}", e),
-            }),
+/// Path as the router matches it, with percent-encoding decoded.
+///
+/// `req.path()` is the raw path, so checking it would let `/%61pi/shutdown`
+/// reach `/api/shutdown` unchecked.
+fn routed_path(req: &ServiceRequest) -> &str {
+    req.match_info().as_str()
+}
+
+/// Whether the request is for the web API
+fn is_api(req: &ServiceRequest) -> bool {
+    routed_path(req).starts_with("/api/")
+}
+
+/// Route pattern the request will be dispatched to, e.g. `/api/runs/{id}`.
+///
+/// Unlike `ServiceRequest::match_pattern`, this works before routing. It
+/// ignores the method, so a static route such as `/api/peers/bulk` must be
+/// registered before a dynamic one it overlaps.
+fn route_pattern(req: &ServiceRequest) -> String {
+    req.resource_map()
+        .match_pattern(routed_path(req))
+        .unwrap_or_default()
+}
+
+/// Device token of a peer, sent as a bearer token, or of a paired browser,
+/// kept in a cookie
+fn device_token(req: &HttpRequest) -> Option<String> {
+    let bearer = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::to_string);
+    bearer.or_else(|| {
+        req.cookie(roles::TOKEN_COOKIE)
+            .map(|cookie| cookie.value().to_string())
+    })
+}
+
+/// Reject `/api/*` requests from clients outside the configured CIDR ranges
+async fn restrict_api(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, actix_web::Error> {
+    let ip = req.peer_addr().map(|addr| addr.ip());
+    if !is_api(&req) || ip.is_some_and(access::is_allowed) {
+        return Ok(next.call(req).await?.map_into_left_body());
+    }
+
+    access::record_denial(ip, req.method().as_str(), routed_path(&req));
+    if let Some(ip) = ip {
+        rate_limit::record_auth_failure(ip);
+    }
+    let res = HttpResponse::Forbidden().json(ApiResponse {
+        success: false,
+        message: "Your address is not allowed to use this API".to_string(),
+    });
+    Ok(req.into_response(res).map_into_right_body())
+}
+
+/// Refuse `/api/*` requests the client's role doesn't cover. An unknown
+/// device token counts as a failed login, and its cookie is cleared so the
+/// browser carries on as an unpaired device.
+async fn enforce_role(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, actix_web::Error> {
+    let ip = match req.peer_addr() {
+        Some(addr) if is_api(&req) => addr.ip(),
+        _ => return Ok(next.call(req).await?.map_into_left_body()),
+    };
+
+    let route = route_pattern(&req);
+    let required = roles::Role::required_for(req.method().as_str(), &route);
+    let role = match roles::role_for(ip, device_token(req.request()).as_deref()) {
+        Ok(role) => role,
+        Err(e) => {
+            warn!(
+                "Denied {} {}: unknown device token",
+                req.method(),
+                routed_path(&req)
+            );
+            rate_limit::record_auth_failure(ip);
+            let mut cookie = Cookie::new(roles::TOKEN_COOKIE, "");
+            cookie.set_path("/");
+            cookie.make_removal();
+            let res = HttpResponse::Unauthorized()
+                .cookie(cookie)
+                .json(ApiResponse {
+                    success: false,
+                    message: e.to_string(),
+                });
+            return Ok(req.into_response(res).map_into_right_body());
         }
-    } else if cfg!(target_os = "macos") {
-        let result = Command::new("osascript")
-            .args([
-                "-e",
-                &format!("set volume output volume {}", volume_level)
-            ])
-            .spawn();
+    };
+    if role >= required {
+        return Ok(next.call(req).await?.map_into_left_body());
+    }
 
-        match result {
-            Ok(_) => HttpResponse::Ok().json(ApiResponse {
-                success: true,
-                message: format!("Volume set to {}%", volume_level),
-            }),
-            Err(e) => HttpResponse::InternalServerError().json(ApiResponse {
+    warn!(%role, %required, "Denied {} {}: role too low", req.method(), routed_path(&req));
+    rate_limit::record_auth_failure(ip);
+    let res = HttpResponse::Forbidden().json(ApiResponse {
+        success: false,
+        message: format!("This device has the {} role; this needs {}", role, required),
+    });
+    Ok(req.into_response(res).map_into_right_body())
+}
+
+/// Apply the per-client request budgets and the authentication lockout to
+/// `/api/*`. The lockout answers with 429 too, with `Retry-After` set to when
+/// it ends.
+async fn limit_rate(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, actix_web::Error> {
+    let ip = match req.peer_addr() {
+        Some(addr) if is_api(&req) => addr.ip(),
+        _ => return Ok(next.call(req).await?.map_into_left_body()),
+    };
+
+    if let Err(limited) = rate_limit::check(ip, req.method().as_str(), &route_pattern(&req)) {
+        let res = HttpResponse::TooManyRequests()
+            .insert_header((
+                "Retry-After",
+                limited.retry_after().as_secs().max(1).to_string(),
+            ))
+            .json(ApiResponse {
                 success: false,
-                message: format!("Failed to set volume: {}", e),
-            }),
+                message: limited.to_string(),
+            });
+        return Ok(req.into_response(res).map_into_right_body());
+    }
+
+    Ok(next.call(req).await?.map_into_left_body())
+}
+
+/// Longest request body parsed for the audit log; larger ones are recorded without it
+const AUDIT_BODY_LIMIT: usize = 64 * 1024;
+
+/// Whether a request is recorded in the audit log: every change, plus reads of
+/// the screen, clipboard and shared files
+fn is_audited(method: &str, path: &str) -> bool {
+    path.starts_with("/api/")
+        && (method != "GET"
+            || path.starts_with("/api/clipboard")
+            || path.starts_with("/api/screen")
+            || path.starts_with("/api/files/"))
+}
+
+/// Audit parameters of a JSON request body to `route`. Clipboard text is
+/// recorded by its length only, as the desktop does.
+fn body_params(
+    method: &str,
+    route: &str,
+    body: serde_json::Value,
+) -> serde_json::Map<String, serde_json::Value> {
+    let mut params = serde_json::Map::new();
+    match (method, route, body) {
+        ("POST", "/api/clipboard", body) => {
+            let chars = body
+                .get("text")
+                .and_then(|text| text.as_str())
+                .map(|text| text.chars().count());
+            params.insert("chars".to_string(), chars.into());
         }
-    } else {
-        HttpResponse::InternalServerError().json(ApiResponse {
-            success: false,
-            message: "Unsupported operating system".to_string(),
-        })
+        (_, _, serde_json::Value::Object(fields)) => params = fields,
+        (_, _, value) => {
+            params.insert("body".to_string(), value);
+        }
+    }
+    params
+}
+
+/// Record API requests in the audit log with their parameters, client and result
+async fn audit_api(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody, web::Bytes>>, actix_web::Error> {
+    let method = req.method().to_string();
+    if !is_audited(&method, routed_path(&req)) {
+        return Ok(next.call(req).await?.map_into_left_body());
+    }
+
+    let client = req
+        .peer_addr()
+        .map(|addr| network::client_ip(addr.ip()).to_string());
+    let device = device_token(req.request())
+        .as_deref()
+        .and_then(roles::device_for)
+        .map(|device| device.name)
+        .or_else(|| client.clone());
+
+    let route = match route_pattern(&req) {
+        route if route.is_empty() => routed_path(&req).to_string(),
+        route => route,
+    };
+
+    let mut params = serde_json::Map::new();
+    for (key, value) in web::Query::<Vec<(String, String)>>::from_query(req.query_string())
+        .map(|query| query.into_inner())
+        .unwrap_or_default()
+    {
+        params.insert(key, value.into());
+    }
+
+    // JSON bodies are small; uploads and images are left alone
+    let is_json = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("application/json"));
+    let length = req
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|length| length.to_str().ok()?.parse::<usize>().ok());
+    if is_json && length.is_some_and(|length| length <= AUDIT_BODY_LIMIT) {
+        let body = req.extract::<web::Bytes>().await?;
+        if let Ok(value) = serde_json::from_slice(&body) {
+            params.extend(body_params(&method, &route, value));
+        }
+        req.set_payload(Payload::from(body));
     }
+
+    let res = next.call(req).await?;
+    for (key, value) in res.request().match_info().iter() {
+        params.insert(key.to_string(), value.into());
+    }
+    let action = format!("{} {}", method, route);
+    let status = res.status();
+
+    // Streams and downloads are never buffered; their status is the result
+    if method == "GET" {
+        audit::record_web(
+            &action,
+            params.into(),
+            client,
+            device,
+            status.is_success(),
+            &status.to_string(),
+        );
+        return Ok(res.map_into_left_body());
+    }
+
+    let (http_req, http_res) = res.into_parts();
+    let (head, body) = http_res.into_parts();
+    let body = to_bytes(body)
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(e.into()))?;
+    let result = serde_json::from_slice::<ApiResponse>(&body)
+        .map(|response| response.message)
+        .unwrap_or_else(|_| status.to_string());
+    audit::record_web(
+        &action,
+        params.into(),
+        client,
+        device,
+        status.is_success(),
+        &result,
+    );
+
+    let res = ServiceResponse::new(http_req, head.set_body(body));
+    Ok(res.map_into_right_body())
 }
 
 #[actix_web::main]
 async fn start_web_server() -> std::io::Result<()> {
-    let host = "0.0.0.0";
     let port = 7777;
+    let config = config::get();
+    if !config.listen_ipv4 && !config.listen_ipv6 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "Both listen_ipv4 and listen_ipv6 are disabled",
+        ));
+    }
 
-    println!("Starting Ferrous Control web server");
-    println!("Local access: http://127.0.0.1:{}", port);
-    println!("Network access: http://<your-ip>:{}", port);
-    println!("Server listening on {}:{}", host, port);
+    info!("Starting Ferrous Control web server");
+    let loopback = if config.listen_ipv4 {
+        "127.0.0.1"
+    } else {
+        "[::1]"
+    };
+    info!("Local access: http://{}:{}", loopback, port);
+    match network::preferred_url_host() {
+        Some(host) => info!("Network access: http://{}:{}", host, port),
+        None => info!("Network access: http://<your-ip>:{}", port),
+    }
 
-    HttpServer::new(|| {
+    let mut server = HttpServer::new(|| {
         App::new()
+            .wrap(from_fn(enforce_role))
+            .wrap(from_fn(audit_api))
+            .wrap(from_fn(limit_rate))
+            .wrap(from_fn(restrict_api))
+            .wrap(from_fn(trace_request))
+            .wrap(from_fn(track_metrics))
+            // Raise the default 256 KiB body limit so clipboard images fit
+            .app_data(web::PayloadConfig::new(16 * 1024 * 1024))
             .service(index)
+            .service(share_target)
+            .service(web_manifest)
+            .service(app_icon)
             .service(shutdown)
             .service(restart)
             .service(cancel_shutdown)
             .service(sleep)
+            .service(list_power_rules)
+            .service(add_power_rule)
+            .service(remove_power_rule)
+            .service(preview_schedule)
+            .service(list_schedules)
+            .service(create_schedule)
+            .service(update_schedule)
+            .service(delete_schedule)
+            .service(list_wol_machines)
+            .service(wake_machine)
+            .service(list_peers)
+            .service(get_peer_status)
+            .service(run_bulk_peer_action)
+            .service(run_peer_action)
+            .service(discover_instances)
+            .service(query_audit)
+            .service(get_role)
+            .service(pair_device)
             .service(increase_volume)
             .service(decrease_volume)
             .service(get_volume)
             .service(set_volume)
-    })
-    .bind((host, port))?
-    .run()
-    .await
+            .service(get_brightness)
+            .service(set_brightness)
+            .service(list_monitors)
+            .service(screenshot)
+            .service(stream_screen)
+            .service(open_link)
+            .service(send_notification)
+            .service(speak)
+            .service(get_clipboard)
+            .service(set_clipboard)
+            .service(get_clipboard_image)
+            .service(set_clipboard_image)
+            .service(clipboard_events)
+            .service(upload_files)
+            .service(list_shared_files)
+            .service(download_file)
+            .service(list_runs)
+            .service(stream_run)
+            .service(get_run)
+            .service(kill_run)
+            .service(list_processes)
+            .service(kill_process)
+            .service(list_launchable)
+            .service(launch_application)
+            .service(get_system_status)
+            .service(get_metrics)
+    });
+
+    // Separate sockets per family, since a dual-stack `::` socket can't be
+    // turned off for IPv4 everywhere
+    if config.listen_ipv4 {
+        server = server.bind((Ipv4Addr::UNSPECIFIED, port))?;
+        info!("Server listening on 0.0.0.0:{}", port);
+    }
+    if config.listen_ipv6 {
+        match network::ipv6_listener(port) {
+            Ok(listener) => {
+                server = server.listen(listener)?;
+                info!("Server listening on [::]:{}", port);
+            }
+            // Hosts without IPv6 still serve IPv4 clients
+            Err(e) if config.listen_ipv4 => warn!("Not listening on IPv6: {}", e),
+            Err(e) => return Err(e),
+        }
+    }
+
+    // Only advertise once the port is ours
+    if let Err(e) = discovery::advertise(port, false) {
+        warn!("{}", e);
+    }
+
+    server.run().await
 }
 
 fn main() {
+    logging::init();
+
+    // Run persisted schedules whether or not a client is connected
+    schedules::start();
+
     // Start the Actix-web server in a background thread
     thread::spawn(|| {
         if let Err(e) = start_web_server() {
-            eprintln!("Failed to start web server: {}", e);
+            error!("Failed to start web server: {}", e);
         }
     });
 
     // Start the Tauri app (this will block until the app is closed)
     ferrous_control_lib::run();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::test::{self, TestRequest};
+
+    #[actix_web::test]
+    async fn enforce_role_refuses_routes_above_the_device_role() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::env::set_var("FERROUS_CONTROL_CONFIG_DIR", dir.path());
+        roles::set_settings(config::RolesConfig {
+            devices: vec![config::DeviceRole {
+                name: "Kids' tablet".to_string(),
+                token: "tablet-token".to_string(),
+                role: roles::Role::Media,
+            }],
+            ..Default::default()
+        })
+        .unwrap();
+
+        let app = test::init_service(
+            App::new()
+                .wrap(from_fn(enforce_role))
+                .route("/api/shutdown", web::post().to(HttpResponse::Ok))
+                .route("/api/volume/set", web::post().to(HttpResponse::Ok)),
+        )
+        .await;
+        let call = |uri: &str, peer: &str, token: Option<&str>| {
+            let mut req = TestRequest::post()
+                .uri(uri)
+                .peer_addr(peer.parse().unwrap());
+            if let Some(token) = token {
+                req = req.insert_header((header::AUTHORIZATION, format!("Bearer {}", token)));
+            }
+            test::call_service(&app, req.to_request())
+        };
+
+        let remote = "192.0.2.80:50000";
+        // Unpaired devices are viewers
+        assert_eq!(
+            call("/api/volume/set", remote, None).await.status(),
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            call("/api/volume/set", remote, Some("tablet-token"))
+                .await
+                .status(),
+            StatusCode::OK
+        );
+        assert_eq!(
+            call("/api/shutdown", remote, Some("tablet-token"))
+                .await
+                .status(),
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            call("/api/volume/set", remote, Some("guessed"))
+                .await
+                .status(),
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            call("/api/shutdown", "127.0.0.1:50000", None)
+                .await
+                .status(),
+            StatusCode::OK
+        );
+    }
+}
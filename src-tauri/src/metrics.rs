@@ -0,0 +1,249 @@
+use crate::power;
+use crate::system_status::{self, CpuSampler};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Upper bounds (in seconds) of the request latency histogram buckets
+const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+#[derive(Default)]
+struct Histogram {
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+#[derive(Default)]
+struct Registry {
+    /// (method, route, status) -> count
+    requests: BTreeMap<(String, String, u16), u64>,
+    /// (method, route) -> latency histogram
+    latencies: BTreeMap<(String, String), Histogram>,
+    /// (action, succeeded) -> count
+    actions: BTreeMap<(String, bool), u64>,
+}
+
+static REGISTRY: Mutex<Option<Registry>> = Mutex::new(None);
+
+/// Scrapes keep their own CPU baseline, so they don't skew the status page
+static CPU: Mutex<Option<CpuSampler>> = Mutex::new(None);
+
+fn with_registry<T>(f: impl FnOnce(&mut Registry) -> T) -> T {
+    let mut registry = REGISTRY.lock().unwrap();
+    f(registry.get_or_insert_with(Registry::default))
+}
+
+/// Record a handled HTTP request
+pub fn record_request(method: &str, route: &str, status: u16, duration: Duration) {
+    let seconds = duration.as_secs_f64();
+
+    with_registry(|registry| {
+        *registry
+            .requests
+            .entry((method.to_string(), route.to_string(), status))
+            .or_default() += 1;
+
+        let histogram = registry
+            .latencies
+            .entry((method.to_string(), route.to_string()))
+            .or_default();
+        for (bucket, bound) in histogram.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+        histogram.count += 1;
+        histogram.sum += seconds;
+    });
+}
+
+/// Record the outcome of an action such as `shutdown` or `sleep`
+pub fn record_action(action: &str, succeeded: bool) {
+    with_registry(|registry| {
+        *registry
+            .actions
+            .entry((action.to_string(), succeeded))
+            .or_default() += 1;
+    });
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+/// Render all metrics in the Prometheus text exposition format
+pub fn render() -> String {
+    let mut out = String::new();
+
+    with_registry(|registry| {
+        header(
+            &mut out,
+            "ferrous_http_requests_total",
+            "counter",
+            "HTTP requests handled, by route and status",
+        );
+        for ((method, route, status), count) in &registry.requests {
+            let _ = writeln!(
+                out,
+                "ferrous_http_requests_total{{method=\"{}\",route=\"{}\",status=\"{}\"}} {}",
+                method,
+                escape(route),
+                status,
+                count
+            );
+        }
+
+        header(
+            &mut out,
+            "ferrous_http_request_duration_seconds",
+            "histogram",
+            "HTTP request latency, by route",
+        );
+        for ((method, route), histogram) in &registry.latencies {
+            let labels = format!("method=\"{}\",route=\"{}\"", method, escape(route));
+            for (count, bound) in histogram.buckets.iter().zip(LATENCY_BUCKETS) {
+                let _ = writeln!(
+                    out,
+                    "ferrous_http_request_duration_seconds_bucket{{{},le=\"{}\"}} {}",
+                    labels, bound, count
+                );
+            }
+            let _ = writeln!(
+                out,
+                "ferrous_http_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}",
+                labels, histogram.count
+            );
+            let _ = writeln!(
+                out,
+                "ferrous_http_request_duration_seconds_sum{{{}}} {}",
+                labels, histogram.sum
+            );
+            let _ = writeln!(
+                out,
+                "ferrous_http_request_duration_seconds_count{{{}}} {}",
+                labels, histogram.count
+            );
+        }
+
+        header(
+            &mut out,
+            "ferrous_actions_total",
+            "counter",
+            "Actions executed, by action name and result",
+        );
+        for ((action, succeeded), count) in &registry.actions {
+            let _ = writeln!(
+                out,
+                "ferrous_actions_total{{action=\"{}\",result=\"{}\"}} {}",
+                escape(action),
+                if *succeeded { "success" } else { "failure" },
+                count
+            );
+        }
+    });
+
+    if let Ok(volume) = crate::read_volume() {
+        header(&mut out, "ferrous_volume_percent", "gauge", "Current master volume");
+        let _ = writeln!(out, "ferrous_volume_percent {}", volume);
+    }
+
+    header(
+        &mut out,
+        "ferrous_shutdown_pending",
+        "gauge",
+        "Whether a shutdown is currently scheduled",
+    );
+    let _ = writeln!(
+        out,
+        "ferrous_shutdown_pending {}",
+        u8::from(power::pending_shutdown().is_some())
+    );
+
+    let status = {
+        let mut cpu = CPU.lock().unwrap();
+        system_status::sampled_status(cpu.get_or_insert_with(CpuSampler::default))
+    };
+    render_system(&mut out, &status);
+
+    out
+}
+
+fn render_system(out: &mut String, status: &system_status::SystemStatus) {
+    header(out, "ferrous_uptime_seconds", "gauge", "System uptime");
+    let _ = writeln!(out, "ferrous_uptime_seconds {}", status.uptime_seconds);
+
+    header(out, "ferrous_cpu_usage_percent", "gauge", "CPU usage across all cores");
+    let _ = writeln!(out, "ferrous_cpu_usage_percent {}", status.cpu.usage_percent);
+
+    header(out, "ferrous_load_average", "gauge", "System load average");
+    for (period, load) in ["1m", "5m", "15m"].iter().zip(status.cpu.load_average) {
+        let _ = writeln!(out, "ferrous_load_average{{period=\"{}\"}} {}", period, load);
+    }
+
+    header(out, "ferrous_memory_total_bytes", "gauge", "Total physical memory");
+    let _ = writeln!(out, "ferrous_memory_total_bytes {}", status.memory.total);
+    header(out, "ferrous_memory_used_bytes", "gauge", "Used physical memory");
+    let _ = writeln!(out, "ferrous_memory_used_bytes {}", status.memory.used);
+    header(out, "ferrous_swap_total_bytes", "gauge", "Total swap space");
+    let _ = writeln!(out, "ferrous_swap_total_bytes {}", status.memory.swap_total);
+    header(out, "ferrous_swap_used_bytes", "gauge", "Used swap space");
+    let _ = writeln!(out, "ferrous_swap_used_bytes {}", status.memory.swap_used);
+
+    header(out, "ferrous_disk_total_bytes", "gauge", "Disk size, by mount point");
+    for disk in &status.disks {
+        let _ = writeln!(
+            out,
+            "ferrous_disk_total_bytes{{mount=\"{}\"}} {}",
+            escape(&disk.mount_point),
+            disk.total
+        );
+    }
+    header(out, "ferrous_disk_used_bytes", "gauge", "Used disk space, by mount point");
+    for disk in &status.disks {
+        let _ = writeln!(
+            out,
+            "ferrous_disk_used_bytes{{mount=\"{}\"}} {}",
+            escape(&disk.mount_point),
+            disk.used
+        );
+    }
+
+    if let Some(battery) = &status.battery {
+        header(out, "ferrous_battery_level_percent", "gauge", "Battery charge level");
+        let _ = writeln!(out, "ferrous_battery_level_percent {}", battery.level);
+        header(out, "ferrous_battery_charging", "gauge", "Whether the battery is charging");
+        let _ = writeln!(out, "ferrous_battery_charging {}", u8::from(battery.charging));
+    }
+
+    if !status.temperatures.is_empty() {
+        header(out, "ferrous_temperature_celsius", "gauge", "Temperature, by sensor");
+        // Sensor names are not unique (e.g. one per core), so number repeats
+        let mut seen: BTreeMap<&str, usize> = BTreeMap::new();
+        for sensor in &status.temperatures {
+            let occurrence = seen.entry(&sensor.name).or_default();
+            *occurrence += 1;
+            let name = match occurrence {
+                1 => sensor.name.clone(),
+                n => format!("{} #{}", sensor.name, n),
+            };
+            let _ = writeln!(
+                out,
+                "ferrous_temperature_celsius{{sensor=\"{}\"}} {}",
+                escape(&name),
+                sensor.celsius
+            );
+        }
+    }
+}
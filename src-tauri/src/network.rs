@@ -0,0 +1,253 @@
+use crate::config;
+use local_ip_address::{list_afinet_netifas, local_ip};
+use serde::Serialize;
+use socket2::{Domain, Protocol, Socket, Type};
+use std::net::{IpAddr, Ipv6Addr, SocketAddr, TcpListener};
+
+/// Name prefixes of adapters created by VPNs, containers and hypervisors
+const VIRTUAL_PREFIXES: [&str; 17] = [
+    "docker",
+    "br-",
+    "veth",
+    "virbr",
+    "vmnet",
+    "vboxnet",
+    "tun",
+    "tap",
+    "wg",
+    "utun",
+    "zt",
+    "tailscale",
+    "vethernet",
+    "lxcbr",
+    "lxdbr",
+    "cni",
+    "flannel",
+];
+
+/// Words in Windows adapter names that mark them as virtual
+const VIRTUAL_WORDS: [&str; 5] = ["virtual", "vmware", "hyper-v", "vpn", "loopback"];
+
+#[derive(Clone, Serialize)]
+pub struct NetworkInterface {
+    pub name: String,
+    pub ipv4: Vec<String>,
+    pub ipv6: Vec<String>,
+    pub is_loopback: bool,
+    /// VPN, container bridge or virtual machine adapter
+    pub is_virtual: bool,
+    /// The interface whose address is shown to users, e.g. in the QR code
+    pub preferred: bool,
+    /// Host to put in URLs for this interface, with IPv6 in brackets, or `None`
+    /// when the server doesn't listen on any of its address families
+    pub url_host: Option<String>,
+}
+
+fn is_virtual(name: &str) -> bool {
+    let name = name.to_lowercase();
+    VIRTUAL_PREFIXES
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+        || VIRTUAL_WORDS.iter().any(|word| name.contains(word))
+}
+
+/// Network interfaces with their addresses, physical adapters first
+pub fn interfaces() -> Result<Vec<NetworkInterface>, String> {
+    let addresses =
+        list_afinet_netifas().map_err(|e| format!("Failed to list network interfaces: {}", e))?;
+
+    let mut interfaces: Vec<NetworkInterface> = Vec::new();
+    for (name, address) in addresses {
+        let index = match interfaces
+            .iter()
+            .position(|interface| interface.name == name)
+        {
+            Some(index) => index,
+            None => {
+                interfaces.push(NetworkInterface {
+                    is_virtual: is_virtual(&name),
+                    name,
+                    ipv4: Vec::new(),
+                    ipv6: Vec::new(),
+                    is_loopback: true,
+                    preferred: false,
+                    url_host: None,
+                });
+                interfaces.len() - 1
+            }
+        };
+
+        let interface = &mut interfaces[index];
+        interface.is_loopback &= address.is_loopback();
+        match address {
+            IpAddr::V4(address) => interface.ipv4.push(address.to_string()),
+            IpAddr::V6(address) => interface.ipv6.push(address.to_string()),
+        }
+    }
+
+    let config = config::get();
+    for interface in &mut interfaces {
+        // Link-local IPv6 needs a zone index, which browsers don't accept in URLs
+        interface
+            .ipv6
+            .sort_by_key(|address| address.starts_with("fe80:"));
+        let ipv4 = interface.ipv4.first().filter(|_| config.listen_ipv4);
+        let ipv6 = interface
+            .ipv6
+            .first()
+            .filter(|address| config.listen_ipv6 && !address.starts_with("fe80:"));
+        interface.url_host = ipv4.or(ipv6).map(|address| url_host(address));
+    }
+
+    interfaces.sort_by_key(|interface| (interface.is_loopback, interface.is_virtual));
+
+    // The configured interface if it is still present, otherwise the first physical one
+    let preferred = config
+        .preferred_interface
+        .and_then(|name| {
+            interfaces
+                .iter()
+                .position(|interface| interface.name == name)
+        })
+        .or_else(|| {
+            interfaces.iter().position(|interface| {
+                !interface.is_loopback && !interface.is_virtual && interface.url_host.is_some()
+            })
+        });
+    if let Some(index) = preferred {
+        interfaces[index].preferred = true;
+    }
+
+    Ok(interfaces)
+}
+
+/// Format an address for use as the host part of a URL, bracketing IPv6.
+///
+/// The `%` before the zone of a scoped address (`fe80::1%eth0`) is escaped as
+/// `%25`, as URLs require.
+pub fn url_host(address: &str) -> String {
+    if address.contains(':') && !address.starts_with('[') {
+        format!("[{}]", address.replacen('%', "%25", 1))
+    } else {
+        address.to_string()
+    }
+}
+
+/// The client's address with IPv4-mapped IPv6 addresses (`::ffff:a.b.c.d`)
+/// turned back into IPv4, so the same client is treated alike over either
+/// family
+pub fn client_ip(address: IpAddr) -> IpAddr {
+    match address {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(address),
+        IpAddr::V4(_) => address,
+    }
+}
+
+/// A listener on `[::]` that only accepts IPv6, so it can share the port with
+/// the IPv4 listener on `0.0.0.0`
+pub fn ipv6_listener(port: u16) -> std::io::Result<TcpListener> {
+    let socket = Socket::new(Domain::IPV6, Type::STREAM, Some(Protocol::TCP))?;
+    socket.set_only_v6(true)?;
+    #[cfg(unix)]
+    socket.set_reuse_address(true)?;
+    socket.bind(&SocketAddr::from((Ipv6Addr::UNSPECIFIED, port)).into())?;
+    socket.listen(1024)?;
+    Ok(socket.into())
+}
+
+/// The URL host to show users, from the preferred interface or the OS default route
+pub fn preferred_url_host() -> Option<String> {
+    interfaces()
+        .ok()
+        .and_then(|interfaces| {
+            interfaces
+                .into_iter()
+                .find(|interface| interface.preferred)?
+                .url_host
+        })
+        .or_else(|| local_ip().ok().map(|ip| url_host(&ip.to_string())))
+}
+
+/// Save the interface to prefer, or `None` to pick automatically
+pub fn set_preferred_interface(name: Option<String>) -> Result<(), String> {
+    if let Some(name) = &name {
+        if !interfaces()?
+            .iter()
+            .any(|interface| &interface.name == name)
+        {
+            return Err(format!("Unknown network interface {}", name));
+        }
+    }
+    config::update(|config| config.preferred_interface = name).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognizes_virtual_adapters() {
+        for name in [
+            "docker0",
+            "br-1a2b3c",
+            "veth12ab",
+            "virbr0",
+            "tun0",
+            "wg0",
+            "utun3",
+            "tailscale0",
+            "vEthernet (WSL)",
+            "VMware Network Adapter VMnet8",
+            "VirtualBox Host-Only Network",
+            "Hyper-V Virtual Ethernet Adapter",
+            "Loopback Pseudo-Interface 1",
+        ] {
+            assert!(is_virtual(name), "{}", name);
+        }
+    }
+
+    #[test]
+    fn keeps_physical_adapters() {
+        for name in [
+            "eth0",
+            "enp3s0",
+            "wlan0",
+            "wlp2s0",
+            "en0",
+            "Wi-Fi",
+            "Ethernet 2",
+        ] {
+            assert!(!is_virtual(name), "{}", name);
+        }
+    }
+
+    #[test]
+    fn brackets_ipv6_url_hosts() {
+        assert_eq!(url_host("192.168.1.20"), "192.168.1.20");
+        assert_eq!(url_host("desk.local"), "desk.local");
+        assert_eq!(url_host("2001:db8::1"), "[2001:db8::1]");
+        assert_eq!(url_host("::1"), "[::1]");
+        assert_eq!(url_host("::ffff:192.168.1.20"), "[::ffff:192.168.1.20]");
+        // Already bracketed
+        assert_eq!(url_host("[2001:db8::1]"), "[2001:db8::1]");
+    }
+
+    #[test]
+    fn escapes_the_zone_of_scoped_addresses() {
+        assert_eq!(url_host("fe80::1%eth0"), "[fe80::1%25eth0]");
+        assert_eq!(url_host("fe80::1%12"), "[fe80::1%2512]");
+    }
+
+    #[test]
+    fn unmaps_ipv4_mapped_clients() {
+        let ip = |address: &str| address.parse::<IpAddr>().unwrap();
+
+        assert_eq!(client_ip(ip("::ffff:192.168.1.20")), ip("192.168.1.20"));
+        assert_eq!(client_ip(ip("::ffff:127.0.0.1")), ip("127.0.0.1"));
+        assert_eq!(client_ip(ip("192.168.1.20")), ip("192.168.1.20"));
+        // Plain IPv6, including the deprecated IPv4-compatible form
+        for address in ["::1", "2001:db8::1", "fe80::1", "::192.168.1.20"] {
+            assert_eq!(client_ip(ip(address)), ip(address));
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use crate::config;
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::fmt;
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Window the notification rate limit applies to
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+const MAX_TITLE_LEN: usize = 256;
+const MAX_BODY_LEN: usize = 4096;
+
+/// AppUserModelID of PowerShell, which Windows accepts for toasts without registering our own
+const WINDOWS_TOAST_APP_ID: &str =
+    "{1AC14E77-02E7-4E5D-B744-2EB1AE5198B7}\\WindowsPowerShell\\v1.0\\powershell.exe";
+
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Urgency {
+    Low,
+    #[default]
+    Normal,
+    Critical,
+}
+
+impl Urgency {
+    fn as_str(self) -> &'static str {
+        match self {
+            Urgency::Low => "low",
+            Urgency::Normal => "normal",
+            Urgency::Critical => "critical",
+        }
+    }
+}
+
+pub enum NotifyError {
+    /// Too many notifications recently; retry after the given delay
+    RateLimited(Duration),
+    Invalid(String),
+    Failed(String),
+}
+
+impl fmt::Display for NotifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NotifyError::RateLimited(retry_after) => write!(
+                f,
+                "Too many notifications, try again in {} seconds",
+                retry_after.as_secs().max(1)
+            ),
+            NotifyError::Invalid(message) | NotifyError::Failed(message) => f.write_str(message),
+        }
+    }
+}
+
+/// Times of the notifications shown within the current window
+static RECENT: Mutex<VecDeque<Instant>> = Mutex::new(VecDeque::new());
+
+fn check_rate_limit() -> Result<(), NotifyError> {
+    let limit = config::get().notify_limit_per_minute as usize;
+    admit(&mut RECENT.lock().unwrap(), limit, Instant::now())
+}
+
+/// Record a notification at `now` unless `limit` were already shown within the window
+fn admit(recent: &mut VecDeque<Instant>, limit: usize, now: Instant) -> Result<(), NotifyError> {
+    while recent
+        .front()
+        .is_some_and(|shown| now.duration_since(*shown) >= RATE_WINDOW)
+    {
+        recent.pop_front();
+    }
+
+    if recent.len() >= limit {
+        let retry_after = recent
+            .front()
+            .map(|oldest| RATE_WINDOW.saturating_sub(now.duration_since(*oldest)))
+            .unwrap_or(RATE_WINDOW);
+        return Err(NotifyError::RateLimited(retry_after));
+    }
+
+    recent.push_back(now);
+    Ok(())
+}
+
+/// Show a native desktop notification
+pub fn send(title: &str, body: &str, urgency: Urgency) -> Result<(), NotifyError> {
+    let title = title.trim();
+    if title.is_empty() {
+        return Err(NotifyError::Invalid("Title is required".to_string()));
+    }
+    if title.chars().count() > MAX_TITLE_LEN || body.chars().count() > MAX_BODY_LEN {
+        return Err(NotifyError::Invalid(format!(
+            "Title and body are limited to {} and {} characters",
+            MAX_TITLE_LEN, MAX_BODY_LEN
+        )));
+    }
+
+    check_rate_limit()?;
+    show(title, body, urgency).map_err(NotifyError::Failed)
+}
+
+/// Show a notification without validation or rate limiting, for the app's own alerts
+pub fn show(title: &str, body: &str, urgency: Urgency) -> Result<(), String> {
+    let output = if cfg!(target_os = "windows") {
+        // Pass the text through the environment so it never needs escaping
+        let script = format!(
+            "[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] | Out-Null; \
+             $template = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02); \
+             $text = $template.GetElementsByTagName('text'); \
+             $text.Item(0).AppendChild($template.CreateTextNode($env:FERROUS_NOTIFY_TITLE)) | Out-Null; \
+             $text.Item(1).AppendChild($template.CreateTextNode($env:FERROUS_NOTIFY_BODY)) | Out-Null; \
+             $toast = [Windows.UI.Notifications.ToastNotification]::new($template); \
+             {} \
+             [Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier('{}').Show($toast)",
+            match urgency {
+                Urgency::Critical => "$toast.Priority = [Windows.UI.Notifications.ToastNotificationPriority]::High;",
+                _ => "",
+            },
+            WINDOWS_TOAST_APP_ID
+        );
+        Command::new("powershell")
+            .args(["-NoProfile", "-Command", &script])
+            .env("FERROUS_NOTIFY_TITLE", title)
+            .env("FERROUS_NOTIFY_BODY", body)
+            .output()
+    } else if cfg!(target_os = "linux") {
+        Command::new("notify-send")
+            .args([
+                "--app-name",
+                "Ferrous Control",
+                "--urgency",
+                urgency.as_str(),
+            ])
+            .arg("--")
+            .args([title, body])
+            .output()
+    } else if cfg!(target_os = "macos") {
+        // Passing the text as arguments avoids AppleScript string escaping
+        Command::new("osascript")
+            .args([
+                "-e",
+                "on run argv",
+                "-e",
+                "display notification (item 2 of argv) with title (item 1 of argv)",
+                "-e",
+                "end run",
+                title,
+                body,
+            ])
+            .output()
+    } else {
+        return Err("Unsupported operating system".to_string());
+    };
+
+    match output {
+        Ok(output) if output.status.success() => Ok(()),
+        Ok(output) => Err(format!(
+            "Failed to show notification: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )),
+        Err(e) => Err(format!("Failed to show notification: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn retry_after(result: Result<(), NotifyError>) -> Option<Duration> {
+        match result {
+            Err(NotifyError::RateLimited(retry_after)) => Some(retry_after),
+            Err(e) => panic!("unexpected error: {}", e),
+            Ok(()) => None,
+        }
+    }
+
+    #[test]
+    fn enforces_the_limit_per_window() {
+        let start = Instant::now();
+        let mut recent = VecDeque::new();
+
+        for second in 0..3 {
+            let now = start + Duration::from_secs(second * 10);
+            assert_eq!(retry_after(admit(&mut recent, 3, now)), None);
+        }
+        // The oldest notification leaves the window 60 s after it was shown
+        let now = start + Duration::from_secs(45);
+        assert_eq!(
+            retry_after(admit(&mut recent, 3, now)),
+            Some(Duration::from_secs(15))
+        );
+        // Refused notifications don't count
+        assert_eq!(recent.len(), 3);
+    }
+
+    #[test]
+    fn the_window_slides() {
+        let start = Instant::now();
+        let mut recent = VecDeque::new();
+        assert!(admit(&mut recent, 2, start).is_ok());
+        assert!(admit(&mut recent, 2, start + Duration::from_secs(30)).is_ok());
+        assert!(admit(&mut recent, 2, start + Duration::from_secs(59)).is_err());
+
+        // The first one expired, the second is still in the window
+        assert!(admit(&mut recent, 2, start + RATE_WINDOW).is_ok());
+        assert!(admit(&mut recent, 2, start + Duration::from_secs(61)).is_err());
+
+        // Everything expired
+        let later = start + Duration::from_secs(200);
+        assert!(admit(&mut recent, 2, later).is_ok());
+        assert!(admit(&mut recent, 2, later).is_ok());
+        assert_eq!(recent.len(), 2);
+    }
+
+    #[test]
+    fn a_zero_limit_refuses_everything() {
+        let mut recent = VecDeque::new();
+        assert_eq!(
+            retry_after(admit(&mut recent, 0, Instant::now())),
+            Some(RATE_WINDOW)
+        );
+    }
+}
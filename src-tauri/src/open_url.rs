@@ -0,0 +1,231 @@
+use crate::config;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+/// How long to wait for the user to answer the desktop confirmation prompt
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Serialize)]
+pub struct OpenUrlRequest {
+    pub id: u64,
+    pub url: String,
+}
+
+type Opener = Box<dyn Fn(&str) -> Result<(), String> + Send + Sync>;
+type Prompt = Box<dyn Fn(&OpenUrlRequest) + Send + Sync>;
+
+static OPENER: OnceLock<Opener> = OnceLock::new();
+static PROMPT: OnceLock<Prompt> = OnceLock::new();
+
+/// Confirmation requests waiting for an answer
+#[derive(Default)]
+struct Pending {
+    next_id: u64,
+    waiting: HashMap<u64, Sender<bool>>,
+}
+
+static PENDING: Mutex<Option<Pending>> = Mutex::new(None);
+
+/// Register the function that opens a URL (the desktop app wires in tauri-plugin-opener)
+pub fn set_opener<F: Fn(&str) -> Result<(), String> + Send + Sync + 'static>(opener: F) {
+    let _ = OPENER.set(Box::new(opener));
+}
+
+/// Register the function that asks the desktop user to confirm a URL
+pub fn set_prompt<F: Fn(&OpenUrlRequest) + Send + Sync + 'static>(prompt: F) {
+    let _ = PROMPT.set(Box::new(prompt));
+}
+
+/// Check that `url` is well formed and uses an allowed scheme, returning it trimmed
+pub fn validate(url: &str) -> Result<String, String> {
+    let url = url.trim();
+    if url.is_empty() {
+        return Err("URL is empty".to_string());
+    }
+    if url.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err("URL must not contain spaces or control characters".to_string());
+    }
+
+    let scheme = match url.split_once(':') {
+        Some((scheme, _))
+            if !scheme.is_empty()
+                && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.')) =>
+        {
+            scheme.to_ascii_lowercase()
+        }
+        _ => return Err("URL has no scheme, e.g. https://".to_string()),
+    };
+
+    let allowed = config::get().open_url.allowed_schemes;
+    if !allowed
+        .iter()
+        .any(|allowed| allowed.eq_ignore_ascii_case(&scheme))
+    {
+        return Err(format!(
+            "Scheme {} is not allowed (allowed: {})",
+            scheme,
+            allowed.join(", ")
+        ));
+    }
+
+    Ok(url.to_string())
+}
+
+/// Validate a URL, ask for confirmation if enabled, and open it. Blocks while
+/// waiting for the desktop user.
+pub fn open(url: &str) -> Result<String, String> {
+    let url = validate(url)?;
+    let opener = OPENER.get().ok_or("The desktop app is not running")?;
+
+    if config::get().open_url.confirm && !confirm(&url)? {
+        return Err("Declined on the PC".to_string());
+    }
+
+    opener(&url)?;
+    Ok(url)
+}
+
+fn confirm(url: &str) -> Result<bool, String> {
+    let prompt = PROMPT
+        .get()
+        .ok_or("Confirmation is enabled but the desktop app is not running")?;
+    confirm_with(prompt, url, CONFIRM_TIMEOUT)
+}
+
+/// Show `prompt` for `url` and wait up to `timeout` for [`respond`]
+fn confirm_with(
+    prompt: &dyn Fn(&OpenUrlRequest),
+    url: &str,
+    timeout: Duration,
+) -> Result<bool, String> {
+    let (sender, receiver) = mpsc::channel();
+    let id = {
+        let mut pending = PENDING.lock().unwrap();
+        let pending = pending.get_or_insert_with(Pending::default);
+        pending.next_id += 1;
+        pending.waiting.insert(pending.next_id, sender);
+        pending.next_id
+    };
+
+    prompt(&OpenUrlRequest {
+        id,
+        url: url.to_string(),
+    });
+    let answer = receiver.recv_timeout(timeout);
+
+    if let Some(pending) = PENDING.lock().unwrap().as_mut() {
+        pending.waiting.remove(&id);
+    }
+
+    answer.map_err(|_| "Nobody confirmed the URL on the PC in time".to_string())
+}
+
+/// Answer a confirmation prompt; returns false if the request is no longer waiting
+pub fn respond(id: u64, approved: bool) -> bool {
+    let sender = PENDING
+        .lock()
+        .unwrap()
+        .as_mut()
+        .and_then(|pending| pending.waiting.remove(&id));
+
+    match sender {
+        Some(sender) => sender.send(approved).is_ok(),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_allowed_schemes_in_any_case() {
+        config::use_test_dir();
+
+        assert_eq!(
+            validate("  https://example.com/a?b=c  "),
+            Ok("https://example.com/a?b=c".to_string())
+        );
+        assert_eq!(
+            validate("HtTp://example.com"),
+            Ok("HtTp://example.com".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_other_schemes() {
+        config::use_test_dir();
+
+        for url in [
+            "javascript:alert(1)",
+            "JavaScript:alert(1)",
+            "file:///etc/passwd",
+            "FILE:///C:/Windows/win.ini",
+            "ftp://example.com",
+        ] {
+            let error = validate(url).unwrap_err();
+            assert!(error.contains("is not allowed"), "{}: {}", url, error);
+        }
+    }
+
+    #[test]
+    fn rejects_urls_without_a_scheme() {
+        config::use_test_dir();
+
+        for url in [
+            "example.com",
+            "//example.com",
+            ":foo",
+            "1http://example.com",
+        ] {
+            assert_eq!(
+                validate(url),
+                Err("URL has no scheme, e.g. https://".to_string()),
+                "{}",
+                url
+            );
+        }
+        assert_eq!(validate("   "), Err("URL is empty".to_string()));
+        assert!(validate("https://example.com/a b").is_err());
+        assert!(validate("https://example.com/\u{7}").is_err());
+    }
+
+    #[test]
+    fn waits_for_the_desktop_answer() {
+        let answer = |approved: bool| {
+            move |request: &OpenUrlRequest| {
+                let id = request.id;
+                std::thread::spawn(move || assert!(respond(id, approved)));
+            }
+        };
+
+        let timeout = Duration::from_secs(5);
+        assert_eq!(confirm_with(&answer(true), "https://a", timeout), Ok(true));
+        assert_eq!(
+            confirm_with(&answer(false), "https://b", timeout),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn times_out_without_an_answer() {
+        let shown = Mutex::new(None);
+        let prompt = |request: &OpenUrlRequest| {
+            *shown.lock().unwrap() = Some(request.id);
+        };
+
+        assert_eq!(
+            confirm_with(&prompt, "https://example.com", Duration::from_millis(20)),
+            Err("Nobody confirmed the URL on the PC in time".to_string())
+        );
+        // A late answer is not taken
+        let id = shown.lock().unwrap().unwrap();
+        assert!(!respond(id, true));
+    }
+}
@@ -0,0 +1,290 @@
+use crate::config::{self, Peer};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::time::Duration;
+use tracing::info;
+
+/// Peers are usually on the LAN, so anything slower than this counts as offline
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub enum PeerError {
+    /// No registered peer has this name
+    UnknownPeer(String),
+    /// The peer could not be reached or reported a failure
+    Failed(String),
+}
+
+impl fmt::Display for PeerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PeerError::UnknownPeer(name) => write!(f, "Unknown peer {}", name),
+            PeerError::Failed(message) => f.write_str(message),
+        }
+    }
+}
+
+/// An action forwarded to a peer's own web API
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PeerAction {
+    Shutdown,
+    Restart,
+    Sleep,
+    CancelShutdown,
+    SetVolume { level: i32 },
+}
+
+#[derive(Serialize)]
+pub struct PeerStatus {
+    pub name: String,
+    pub url: String,
+    pub group: Option<String>,
+    pub online: bool,
+    /// The peer's `/api/system/status` response
+    pub system: Option<Value>,
+    pub volume: Option<i32>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ActionResult {
+    pub name: String,
+    pub success: bool,
+    pub message: String,
+}
+
+/// Shape of the `{success, message}` responses of the web API
+#[derive(Deserialize)]
+struct PeerResponse {
+    success: bool,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct VolumeResponse {
+    volume: i32,
+}
+
+fn request(peer: &Peer, method: &str, path: &str) -> ureq::Request {
+    let url = format!("{}{}", peer.url.trim_end_matches('/'), path);
+    let request = ureq::AgentBuilder::new()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .request(method, &url);
+
+    match peer.token.as_deref().filter(|token| !token.is_empty()) {
+        Some(token) => request.set("Authorization", &format!("Bearer {}", token)),
+        None => request,
+    }
+}
+
+fn error_message(peer: &Peer, error: ureq::Error) -> String {
+    match error {
+        // Error responses from the web API carry a message worth showing
+        ureq::Error::Status(code, response) => response
+            .into_json::<PeerResponse>()
+            .map(|body| body.message)
+            .unwrap_or_else(|_| format!("{} answered with HTTP {}", peer.name, code)),
+        ureq::Error::Transport(e) => format!("{} is unreachable: {}", peer.name, e),
+    }
+}
+
+fn find(name: &str) -> Result<Peer, PeerError> {
+    peers()
+        .into_iter()
+        .find(|peer| peer.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| PeerError::UnknownPeer(name.to_string()))
+}
+
+/// Registered peers, including their tokens
+pub fn peers() -> Vec<Peer> {
+    config::get().peers
+}
+
+/// Validate and save the peer list
+pub fn set_peers(peers: Vec<Peer>) -> Result<(), String> {
+    for (i, peer) in peers.iter().enumerate() {
+        if peer.name.trim().is_empty() {
+            return Err("Peer name is required".to_string());
+        }
+        if !(peer.url.starts_with("http://") || peer.url.starts_with("https://")) {
+            return Err(format!(
+                "URL of {} must start with http:// or https://",
+                peer.name
+            ));
+        }
+        if peers[..i]
+            .iter()
+            .any(|other| other.name.eq_ignore_ascii_case(&peer.name))
+        {
+            return Err(format!("Duplicate peer name {}", peer.name));
+        }
+    }
+
+    config::update(|config| config.peers = peers).map(|_| ())
+}
+
+fn status_of(peer: &Peer) -> PeerStatus {
+    let system = request(peer, "GET", "/api/system/status")
+        .call()
+        .map_err(|e| error_message(peer, e))
+        .and_then(|response| {
+            response
+                .into_json::<Value>()
+                .map_err(|e| format!("Invalid status from {}: {}", peer.name, e))
+        });
+    // Volume is optional, e.g. when the peer has no audio device
+    let volume = system.is_ok().then(|| {
+        request(peer, "GET", "/api/volume/get")
+            .call()
+            .ok()?
+            .into_json::<VolumeResponse>()
+            .ok()
+            .map(|body| body.volume)
+    });
+
+    PeerStatus {
+        name: peer.name.clone(),
+        url: peer.url.clone(),
+        group: peer.group.clone(),
+        online: system.is_ok(),
+        error: system.as_ref().err().cloned(),
+        system: system.ok(),
+        volume: volume.flatten(),
+    }
+}
+
+/// Status of every peer, queried in parallel
+pub fn statuses() -> Vec<PeerStatus> {
+    let peers = peers();
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = peers
+            .iter()
+            .map(|peer| scope.spawn(move || status_of(peer)))
+            .collect();
+        handles
+            .into_iter()
+            .zip(&peers)
+            .map(|(handle, peer)| {
+                handle.join().unwrap_or_else(|_| PeerStatus {
+                    name: peer.name.clone(),
+                    url: peer.url.clone(),
+                    group: peer.group.clone(),
+                    online: false,
+                    system: None,
+                    volume: None,
+                    error: Some("Status check failed".to_string()),
+                })
+            })
+            .collect()
+    })
+}
+
+/// Proxy a peer's `/api/system/status`
+pub fn system_status(name: &str) -> Result<Value, PeerError> {
+    let peer = find(name)?;
+    request(&peer, "GET", "/api/system/status")
+        .call()
+        .map_err(|e| PeerError::Failed(error_message(&peer, e)))?
+        .into_json()
+        .map_err(|e| PeerError::Failed(format!("Invalid status from {}: {}", peer.name, e)))
+}
+
+fn run_on(peer: &Peer, action: &PeerAction) -> Result<String, String> {
+    let (path, body) = match action {
+        PeerAction::Shutdown => ("/api/shutdown", None),
+        PeerAction::Restart => ("/api/restart", None),
+        PeerAction::Sleep => ("/api/sleep", None),
+        PeerAction::CancelShutdown => ("/api/cancel", None),
+        PeerAction::SetVolume { level } => (
+            "/api/volume/set",
+            Some(serde_json::json!({ "volume": level })),
+        ),
+    };
+
+    let request = request(peer, "POST", path);
+    let response = match body {
+        Some(body) => request.send_json(body),
+        None => request.call(),
+    }
+    .map_err(|e| error_message(peer, e))?
+    .into_json::<PeerResponse>()
+    .map_err(|e| format!("Invalid response from {}: {}", peer.name, e))?;
+
+    if response.success {
+        Ok(response.message)
+    } else {
+        Err(response.message)
+    }
+}
+
+/// Run an action on one peer
+pub fn run(name: &str, action: &PeerAction) -> Result<String, PeerError> {
+    let peer = find(name)?;
+    info!(peer = %peer.name, "Forwarding action to peer");
+    run_on(&peer, action).map_err(PeerError::Failed)
+}
+
+/// Run an action on the named peers, or on a whole group. One of them is
+/// required, so a request naming neither never reaches every peer.
+pub fn run_bulk(
+    group: Option<&str>,
+    names: &[String],
+    action: &PeerAction,
+) -> Result<Vec<ActionResult>, String> {
+    let group = group.filter(|group| !group.trim().is_empty());
+    if group.is_none() && names.is_empty() {
+        return Err("Name the peers or the group to run the action on".to_string());
+    }
+
+    let targets: Vec<Peer> = peers()
+        .into_iter()
+        .filter(|peer| {
+            if !names.is_empty() {
+                names
+                    .iter()
+                    .any(|name| name.eq_ignore_ascii_case(&peer.name))
+            } else {
+                peer.group
+                    .as_deref()
+                    .zip(group)
+                    .is_some_and(|(peer_group, group)| peer_group.eq_ignore_ascii_case(group))
+            }
+        })
+        .collect();
+    info!(peers = targets.len(), "Forwarding action to peers");
+
+    Ok(std::thread::scope(|scope| {
+        let handles: Vec<_> = targets
+            .iter()
+            .map(|peer| scope.spawn(move || run_on(peer, action)))
+            .collect();
+        handles
+            .into_iter()
+            .zip(&targets)
+            .map(|(handle, peer)| {
+                let result = handle
+                    .join()
+                    .unwrap_or_else(|_| Err("Request failed".to_string()));
+                ActionResult {
+                    name: peer.name.clone(),
+                    success: result.is_ok(),
+                    message: result.unwrap_or_else(|message| message),
+                }
+            })
+            .collect()
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bulk_actions_need_a_target() {
+        for group in [None, Some(""), Some("  ")] {
+            assert!(run_bulk(group, &[], &PeerAction::Shutdown).is_err());
+        }
+    }
+}
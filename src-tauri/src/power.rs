@@ -0,0 +1,58 @@
+use crate::config;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime};
+
+/// Delay the shutdown commands wait before the machine actually powers off
+pub const SHUTDOWN_DELAY: Duration = Duration::from_secs(60);
+
+/// Shown to whoever is at the PC when no `shutdown_message` is configured
+const DEFAULT_SHUTDOWN_MESSAGE: &str =
+    "This PC is about to shut down. Save your work or cancel the shutdown.";
+
+static PENDING_SHUTDOWN: Mutex<Option<SystemTime>> = Mutex::new(None);
+
+type ShutdownListener = Box<dyn Fn(Option<SystemTime>) + Send + Sync>;
+
+static LISTENER: OnceLock<ShutdownListener> = OnceLock::new();
+
+/// Register the callback run when a shutdown is scheduled (with its deadline) or
+/// cancelled (with `None`); used to warn the user at the PC
+pub fn on_shutdown_change<F: Fn(Option<SystemTime>) + Send + Sync + 'static>(listener: F) {
+    let _ = LISTENER.set(Box::new(listener));
+}
+
+fn notify_listener(deadline: Option<SystemTime>) {
+    if let Some(listener) = LISTENER.get() {
+        listener(deadline);
+    }
+}
+
+/// Remember that a shutdown has been scheduled
+pub fn mark_shutdown_pending() {
+    let deadline = SystemTime::now() + SHUTDOWN_DELAY;
+    *PENDING_SHUTDOWN.lock().unwrap() = Some(deadline);
+    notify_listener(Some(deadline));
+}
+
+/// Forget a scheduled shutdown, e.g. after it was cancelled
+pub fn clear_shutdown_pending() {
+    *PENDING_SHUTDOWN.lock().unwrap() = None;
+    notify_listener(None);
+}
+
+/// The grace message shown with the shutdown countdown
+pub fn shutdown_message() -> String {
+    config::get()
+        .shutdown_message
+        .filter(|message| !message.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_SHUTDOWN_MESSAGE.to_string())
+}
+
+/// When the pending shutdown will happen, if one is scheduled
+pub fn pending_shutdown() -> Option<SystemTime> {
+    let mut pending = PENDING_SHUTDOWN.lock().unwrap();
+    if pending.is_some_and(|deadline| deadline < SystemTime::now()) {
+        *pending = None;
+    }
+    *pending
+}
@@ -0,0 +1,388 @@
+use crate::audit::{self, Origin};
+use crate::processes;
+use crate::system_status::CpuSampler;
+use serde::{Deserialize, Serialize};
+use std::sync::{Mutex, Once};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::info;
+
+/// How often the watcher re-evaluates the rules
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Condition {
+    /// No keyboard or mouse input for `minutes`
+    Idle { minutes: u32 },
+    /// The process with `pid`, or every process named `name`, has exited
+    ProcessExit {
+        pid: Option<u32>,
+        name: Option<String>,
+    },
+    /// Total CPU usage stayed below `percent` for `minutes`
+    LowCpu { percent: f32, minutes: u32 },
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerAction {
+    Shutdown,
+    Sleep,
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleState {
+    Waiting,
+    Fired,
+    Failed,
+}
+
+#[derive(Clone, Serialize)]
+pub struct RuleStatus {
+    pub id: u64,
+    pub condition: Condition,
+    pub action: PowerAction,
+    pub state: RuleState,
+    /// Human-readable progress, e.g. "Idle for 3 of 10 minutes"
+    pub detail: String,
+    /// Unix milliseconds
+    pub created_at: u64,
+    pub fired_at: Option<u64>,
+}
+
+struct Rule {
+    status: RuleStatus,
+    /// When the low CPU condition started holding
+    satisfied_since: Option<Instant>,
+}
+
+struct Rules {
+    next_id: u64,
+    rules: Vec<Rule>,
+}
+
+static RULES: Mutex<Rules> = Mutex::new(Rules {
+    next_id: 0,
+    rules: Vec::new(),
+});
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+fn describe_process(pid: Option<u32>, name: Option<&str>) -> String {
+    match (pid, name) {
+        (Some(pid), _) => format!("PID {}", pid),
+        (None, Some(name)) => name.to_string(),
+        (None, None) => "process".to_string(),
+    }
+}
+
+/// Add a rule and start the watcher if needed
+pub fn add(condition: Condition, action: PowerAction) -> Result<RuleStatus, String> {
+    match &condition {
+        Condition::Idle { minutes } | Condition::LowCpu { minutes, .. } if *minutes == 0 => {
+            return Err("Minutes must be at least 1".to_string());
+        }
+        Condition::LowCpu { percent, .. } if !(*percent > 0.0 && *percent <= 100.0) => {
+            return Err("CPU percentage must be between 0 and 100".to_string());
+        }
+        Condition::Idle { .. } => {
+            // Fail now rather than waiting forever on a platform we cannot read
+            idle_time()?;
+        }
+        Condition::ProcessExit { pid, name } => {
+            let running = match (pid, name.as_deref()) {
+                (Some(pid), _) => processes::pid_running(*pid),
+                (None, Some(name)) if !name.is_empty() => processes::name_running(name),
+                _ => return Err("A PID or process name is required".to_string()),
+            };
+            if !running {
+                return Err(format!(
+                    "{} is not running",
+                    describe_process(*pid, name.as_deref())
+                ));
+            }
+        }
+        Condition::LowCpu { .. } => {}
+    }
+
+    let status = {
+        let mut rules = RULES.lock().unwrap();
+        rules.next_id += 1;
+        let status = RuleStatus {
+            id: rules.next_id,
+            condition,
+            action,
+            state: RuleState::Waiting,
+            detail: "Waiting for the first check".to_string(),
+            created_at: now_millis(),
+            fired_at: None,
+        };
+        rules.rules.push(Rule {
+            status: status.clone(),
+            satisfied_since: None,
+        });
+        status
+    };
+
+    start_watcher();
+    Ok(status)
+}
+
+/// All rules, oldest first
+pub fn list() -> Vec<RuleStatus> {
+    RULES
+        .lock()
+        .unwrap()
+        .rules
+        .iter()
+        .map(|rule| rule.status.clone())
+        .collect()
+}
+
+/// Remove a rule; returns false if it does not exist
+pub fn remove(id: u64) -> bool {
+    let mut rules = RULES.lock().unwrap();
+    let before = rules.rules.len();
+    rules.rules.retain(|rule| rule.status.id != id);
+    rules.rules.len() != before
+}
+
+fn start_watcher() {
+    static WATCHER: Once = Once::new();
+    WATCHER.call_once(|| {
+        std::thread::spawn(|| {
+            let mut cpu = CpuSampler::default();
+            loop {
+                std::thread::sleep(POLL_INTERVAL);
+                check_rules(&mut cpu);
+            }
+        });
+    });
+}
+
+/// What was measured for one rule's condition
+enum Reading {
+    Idle(Result<Duration, String>),
+    ProcessRunning(bool),
+    Cpu(f32),
+}
+
+/// Evaluate every waiting rule and fire the first one that is satisfied
+fn check_rules(cpu: &mut CpuSampler) {
+    let waiting: Vec<(u64, Condition)> = RULES
+        .lock()
+        .unwrap()
+        .rules
+        .iter()
+        .filter(|rule| rule.status.state == RuleState::Waiting)
+        .map(|rule| (rule.status.id, rule.status.condition.clone()))
+        .collect();
+    if waiting.is_empty() {
+        return;
+    }
+
+    // Measure without holding the lock, and only what the rules need
+    let needs = |kind: fn(&Condition) -> bool| waiting.iter().any(|(_, c)| kind(c));
+    let usage = needs(|c| matches!(c, Condition::LowCpu { .. })).then(|| cpu.usage());
+    let idle = needs(|c| matches!(c, Condition::Idle { .. })).then(idle_time);
+    let readings: Vec<(u64, Reading)> = waiting
+        .into_iter()
+        .map(|(id, condition)| {
+            let reading = match condition {
+                Condition::Idle { .. } => Reading::Idle(idle.clone().unwrap_or_else(idle_time)),
+                Condition::ProcessExit { pid, name } => {
+                    Reading::ProcessRunning(match (pid, name.as_deref()) {
+                        (Some(pid), _) => processes::pid_running(pid),
+                        (None, Some(name)) => processes::name_running(name),
+                        (None, None) => false,
+                    })
+                }
+                Condition::LowCpu { .. } => Reading::Cpu(usage.unwrap_or(100.0)),
+            };
+            (id, reading)
+        })
+        .collect();
+
+    let due = {
+        let mut rules = RULES.lock().unwrap();
+        let mut due = None;
+        for (id, reading) in readings {
+            // The rule may have been removed while measuring
+            let Some(rule) = rules
+                .rules
+                .iter_mut()
+                .find(|rule| rule.status.id == id && rule.status.state == RuleState::Waiting)
+            else {
+                continue;
+            };
+            let satisfied = evaluate(rule, reading);
+            if satisfied && due.is_none() {
+                due = Some((rule.status.id, rule.status.action));
+            }
+        }
+        due
+    };
+
+    let Some((id, action)) = due else {
+        return;
+    };
+
+    info!(rule = id, action = action_name(action), "Power rule satisfied");
+    let response = match action {
+        PowerAction::Shutdown => crate::perform_shutdown(),
+        PowerAction::Sleep => crate::perform_sleep(),
+    };
+    audit::record_action(
+        Origin::PowerRule,
+        action_name(action),
+        serde_json::json!({ "rule": id }),
+        response.success,
+        &response.message,
+    );
+
+    let mut rules = RULES.lock().unwrap();
+    if let Some(rule) = rules.rules.iter_mut().find(|rule| rule.status.id == id) {
+        rule.status.state = if response.success {
+            RuleState::Fired
+        } else {
+            RuleState::Failed
+        };
+        rule.status.detail = response.message;
+        rule.status.fired_at = Some(now_millis());
+    }
+}
+
+fn action_name(action: PowerAction) -> &'static str {
+    match action {
+        PowerAction::Shutdown => "shutdown",
+        PowerAction::Sleep => "sleep",
+    }
+}
+
+/// Update the rule's detail text and report whether its condition holds
+fn evaluate(rule: &mut Rule, reading: Reading) -> bool {
+    let (satisfied, detail) = match (&rule.status.condition, reading) {
+        (Condition::Idle { minutes }, Reading::Idle(idle)) => match idle {
+            Ok(idle) => (
+                idle.as_secs() >= *minutes as u64 * 60,
+                format!("Idle for {} of {} minutes", idle.as_secs() / 60, minutes),
+            ),
+            Err(e) => (false, e),
+        },
+        (Condition::ProcessExit { pid, name }, Reading::ProcessRunning(running)) => {
+            let process = describe_process(*pid, name.as_deref());
+            if running {
+                (false, format!("Waiting for {} to exit", process))
+            } else {
+                (true, format!("{} has exited", process))
+            }
+        }
+        (Condition::LowCpu { percent, minutes }, Reading::Cpu(usage)) => {
+            if usage < *percent {
+                let since = *rule.satisfied_since.get_or_insert_with(Instant::now);
+                let elapsed = since.elapsed().as_secs();
+                (
+                    elapsed >= *minutes as u64 * 60,
+                    format!(
+                        "CPU at {:.1}%, below {}% for {} of {} minutes",
+                        usage,
+                        percent,
+                        elapsed / 60,
+                        minutes
+                    ),
+                )
+            } else {
+                rule.satisfied_since = None;
+                (false, format!("CPU at {:.1}%, above {}%", usage, percent))
+            }
+        }
+        // Readings are taken per condition, so they always match
+        _ => return false,
+    };
+
+    rule.status.detail = detail;
+    satisfied
+}
+
+/// Time since the last keyboard or mouse input
+pub fn idle_time() -> Result<Duration, String> {
+    if cfg!(target_os = "linux") {
+        #[cfg(target_os = "linux")]
+        {
+            x11_idle_time()
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            Err("Linux-only code path".to_string())
+        }
+    } else if cfg!(target_os = "windows") {
+        #[cfg(target_os = "windows")]
+        {
+            windows_idle_time()
+        }
+        #[cfg(not(target_os = "windows"))]
+        {
+            Err("Windows-only code path".to_string())
+        }
+    } else if cfg!(target_os = "macos") {
+        // HIDIdleTime is reported in nanoseconds
+        let output = std::process::Command::new("ioreg")
+            .args(["-c", "IOHIDSystem", "-d", "4"])
+            .output()
+            .map_err(|e| format!("Failed to read idle time: {}", e))?;
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .find(|line| line.contains("\"HIDIdleTime\""))
+            .and_then(|line| line.rsplit('=').next())
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .map(Duration::from_nanos)
+            .ok_or_else(|| "Failed to read idle time".to_string())
+    } else {
+        Err("Unsupported operating system".to_string())
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn x11_idle_time() -> Result<Duration, String> {
+    use x11rb::connection::Connection;
+    use x11rb::protocol::screensaver::ConnectionExt as _;
+
+    let (conn, screen_num) =
+        x11rb::connect(None).map_err(|e| format!("Idle time needs an X session: {}", e))?;
+    let root = conn.setup().roots[screen_num].root;
+
+    let reply = conn
+        .screensaver_query_info(root)
+        .map_err(|e| format!("Failed to read idle time: {}", e))?
+        .reply()
+        .map_err(|e| format!("Failed to read idle time: {}", e))?;
+
+    Ok(Duration::from_millis(reply.ms_since_user_input as u64))
+}
+
+#[cfg(target_os = "windows")]
+fn windows_idle_time() -> Result<Duration, String> {
+    use windows::Win32::System::SystemInformation::GetTickCount;
+    use windows::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+
+    let mut info = LASTINPUTINFO {
+        cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+        dwTime: 0,
+    };
+
+    unsafe {
+        if !GetLastInputInfo(&mut info).as_bool() {
+            return Err("Failed to read idle time".to_string());
+        }
+        // Both tick counts wrap after ~49 days, so subtract with wrapping
+        Ok(Duration::from_millis(
+            GetTickCount().wrapping_sub(info.dwTime) as u64,
+        ))
+    }
+}
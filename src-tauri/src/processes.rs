@@ -0,0 +1,184 @@
+use crate::config;
+use serde::Serialize;
+use std::ffi::OsStr;
+use std::fmt;
+use std::process::{Command, Stdio};
+use std::sync::{Mutex, OnceLock};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+
+#[derive(Clone, Serialize)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub name: String,
+    pub cpu_usage: f32,
+    pub memory: u64,
+}
+
+fn system() -> &'static Mutex<System> {
+    static SYSTEM: OnceLock<Mutex<System>> = OnceLock::new();
+    SYSTEM.get_or_init(|| {
+        let mut system = System::new();
+        // CPU usage is computed between two refreshes, so prime the first sample
+        refresh(&mut system);
+        std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+        Mutex::new(system)
+    })
+}
+
+fn refresh(system: &mut System) {
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::nothing().with_cpu().with_memory(),
+    );
+}
+
+/// List running processes, sorted by CPU usage (highest first)
+pub fn list() -> Vec<ProcessInfo> {
+    let mut system = system().lock().unwrap();
+    refresh(&mut system);
+
+    let mut processes: Vec<ProcessInfo> = system
+        .processes()
+        .values()
+        // Skip threads, which Linux reports as separate tasks
+        .filter(|process| process.thread_kind().is_none())
+        .map(|process| ProcessInfo {
+            pid: process.pid().as_u32(),
+            name: process.name().to_string_lossy().into_owned(),
+            cpu_usage: process.cpu_usage(),
+            memory: process.memory(),
+        })
+        .collect();
+
+    processes.sort_by(|a, b| b.cpu_usage.total_cmp(&a.cpu_usage));
+    processes
+}
+
+pub enum ProcessError {
+    /// No running process matches the PID or name
+    NotFound(String),
+    /// The process may not be killed or launched remotely
+    NotAllowed(String),
+    Failed(String),
+}
+
+impl fmt::Display for ProcessError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProcessError::NotFound(message)
+            | ProcessError::NotAllowed(message)
+            | ProcessError::Failed(message) => f.write_str(message),
+        }
+    }
+}
+
+/// Kill a single process by PID
+pub fn kill_pid(pid: u32) -> Result<String, ProcessError> {
+    if pid == std::process::id() {
+        return Err(ProcessError::NotAllowed(
+            "Refusing to kill Ferrous Control itself".to_string(),
+        ));
+    }
+
+    let mut system = system().lock().unwrap();
+    refresh(&mut system);
+
+    let process = system
+        .process(Pid::from_u32(pid))
+        .ok_or_else(|| ProcessError::NotFound(format!("No process with PID {}", pid)))?;
+
+    if process.kill() {
+        Ok(format!(
+            "Killed {} (PID {})",
+            process.name().to_string_lossy(),
+            pid
+        ))
+    } else {
+        Err(ProcessError::Failed(format!(
+            "Failed to kill process {}",
+            pid
+        )))
+    }
+}
+
+/// Kill every process with the given executable name
+pub fn kill_name(name: &str) -> Result<String, ProcessError> {
+    let mut system = system().lock().unwrap();
+    refresh(&mut system);
+
+    let own_pid = std::process::id();
+    let matches: Vec<_> = system
+        .processes_by_exact_name(OsStr::new(name))
+        .filter(|process| process.pid().as_u32() != own_pid)
+        .collect();
+
+    if matches.is_empty() {
+        return Err(ProcessError::NotFound(format!("No process named {}", name)));
+    }
+
+    let killed = matches.iter().filter(|process| process.kill()).count();
+    if killed == 0 {
+        return Err(ProcessError::Failed(format!("Failed to kill {}", name)));
+    }
+
+    Ok(format!(
+        "Killed {} of {} process(es) named {}",
+        killed,
+        matches.len(),
+        name
+    ))
+}
+
+/// Whether a process with the given PID is still running
+pub fn pid_running(pid: u32) -> bool {
+    let mut system = system().lock().unwrap();
+    refresh(&mut system);
+    system.process(Pid::from_u32(pid)).is_some()
+}
+
+/// Whether any process with the given executable name is running
+pub fn name_running(name: &str) -> bool {
+    let mut system = system().lock().unwrap();
+    refresh(&mut system);
+
+    let own_pid = std::process::id();
+    let running = system
+        .processes_by_exact_name(OsStr::new(name))
+        .any(|process| process.pid().as_u32() != own_pid);
+    running
+}
+
+/// Names of the applications that may be launched remotely
+pub fn launchable() -> Vec<String> {
+    config::get()
+        .launch_allowlist
+        .into_iter()
+        .map(|entry| entry.name)
+        .collect()
+}
+
+/// Launch an application from the configured allowlist
+pub fn launch(name: &str) -> Result<u32, ProcessError> {
+    let entry = config::get()
+        .launch_allowlist
+        .into_iter()
+        .find(|entry| entry.name == name)
+        .ok_or_else(|| {
+            ProcessError::NotAllowed(format!("{} is not in the launch allowlist", name))
+        })?;
+
+    let mut child = Command::new(&entry.path)
+        .args(&entry.args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| ProcessError::Failed(format!("Failed to launch {}: {}", name, e)))?;
+
+    let pid = child.id();
+    // Reap the child when it exits so it does not linger as a zombie
+    std::thread::spawn(move || child.wait());
+
+    Ok(pid)
+}
@@ -0,0 +1,376 @@
+use crate::config::{self, RateLimitConfig};
+use crate::network;
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Window the per-minute budgets apply to
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Clients tracked at once; the longest idle ones are forgotten first
+const MAX_CLIENTS: usize = 1024;
+
+/// Which budget a request is counted against
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Budget {
+    Power,
+    Media,
+    Default,
+}
+
+impl Budget {
+    /// Budget of a request to the route pattern `route`, e.g. `/api/wol/{name}`
+    pub fn for_route(method: &str, route: &str) -> Budget {
+        match (method, route) {
+            ("POST", "/api/shutdown" | "/api/restart" | "/api/sleep" | "/api/cancel") => {
+                Budget::Power
+            }
+            ("POST", "/api/power/rules") | ("DELETE", "/api/power/rules/{id}") => Budget::Power,
+            ("POST", "/api/wol/{name}" | "/api/peers/{name}/action" | "/api/peers/bulk") => {
+                Budget::Power
+            }
+            ("POST", "/api/volume/increase" | "/api/volume/decrease") => Budget::Media,
+            ("POST", "/api/volume/set" | "/api/display/brightness") => Budget::Media,
+            _ => Budget::Default,
+        }
+    }
+
+    fn per_minute(self, config: &RateLimitConfig) -> u32 {
+        match self {
+            Budget::Power => config.power_per_minute,
+            Budget::Media => config.media_per_minute,
+            Budget::Default => config.default_per_minute,
+        }
+    }
+}
+
+pub enum Limited {
+    /// The client used up a budget; retry after the given delay
+    TooManyRequests(Budget, Duration),
+    /// Too many failed authentications; locked out for the given time
+    LockedOut(Duration),
+}
+
+impl Limited {
+    pub fn retry_after(&self) -> Duration {
+        match self {
+            Limited::TooManyRequests(_, retry_after) | Limited::LockedOut(retry_after) => {
+                *retry_after
+            }
+        }
+    }
+}
+
+impl fmt::Display for Limited {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Limited::TooManyRequests(Budget::Power, retry_after) => write!(
+                f,
+                "Too many power actions, try again in {} seconds",
+                retry_after.as_secs().max(1)
+            ),
+            Limited::TooManyRequests(_, retry_after) => write!(
+                f,
+                "Too many requests, try again in {} seconds",
+                retry_after.as_secs().max(1)
+            ),
+            Limited::LockedOut(retry_after) => write!(
+                f,
+                "Too many failed sign-ins, try again in {} minutes",
+                retry_after.as_secs().div_ceil(60).max(1)
+            ),
+        }
+    }
+}
+
+struct Client {
+    /// Times of the requests within the current window, per budget
+    recent: BTreeMap<Budget, VecDeque<Instant>>,
+    /// Times of the failed authentications within the lockout time
+    auth_failures: VecDeque<Instant>,
+    locked_until: Option<Instant>,
+    requests: u64,
+    limited: u64,
+    last_seen: Instant,
+}
+
+impl Client {
+    fn new(now: Instant) -> Client {
+        Client {
+            recent: BTreeMap::new(),
+            auth_failures: VecDeque::new(),
+            locked_until: None,
+            requests: 0,
+            limited: 0,
+            last_seen: now,
+        }
+    }
+}
+
+/// Counters of one client, for the desktop UI
+#[derive(Serialize)]
+pub struct ClientStats {
+    pub ip: String,
+    pub requests: u64,
+    /// Requests answered with 429
+    pub limited: u64,
+    /// Requests per budget within the last minute
+    pub recent: BTreeMap<Budget, usize>,
+    pub auth_failures: usize,
+    /// Seconds until the lockout ends
+    pub locked_for: Option<u64>,
+    pub idle_seconds: u64,
+}
+
+static CLIENTS: Mutex<BTreeMap<IpAddr, Client>> = Mutex::new(BTreeMap::new());
+
+fn drop_older_than(times: &mut VecDeque<Instant>, now: Instant, age: Duration) {
+    while times
+        .front()
+        .is_some_and(|time| now.duration_since(*time) >= age)
+    {
+        times.pop_front();
+    }
+}
+
+fn with_client<T>(ip: IpAddr, now: Instant, f: impl FnOnce(&mut Client) -> T) -> T {
+    let mut clients = CLIENTS.lock().unwrap();
+    if !clients.contains_key(&ip) && clients.len() >= MAX_CLIENTS {
+        let idlest = clients
+            .iter()
+            .min_by_key(|(_, client)| client.last_seen)
+            .map(|(ip, _)| *ip);
+        if let Some(idlest) = idlest {
+            clients.remove(&idlest);
+        }
+    }
+
+    let client = clients.entry(ip).or_insert_with(|| Client::new(now));
+    client.last_seen = now;
+    f(client)
+}
+
+/// Count a request to the route pattern `route` against the client's budget,
+/// or refuse it
+pub fn check(ip: IpAddr, method: &str, route: &str) -> Result<(), Limited> {
+    check_at(ip, method, route, Instant::now())
+}
+
+fn check_at(ip: IpAddr, method: &str, route: &str, now: Instant) -> Result<(), Limited> {
+    let config = config::get().rate_limit;
+    if !config.enabled {
+        return Ok(());
+    }
+
+    let budget = Budget::for_route(method, route);
+    with_client(network::client_ip(ip), now, |client| {
+        client.requests += 1;
+
+        if let Some(locked_until) = client.locked_until {
+            if locked_until > now {
+                client.limited += 1;
+                return Err(Limited::LockedOut(locked_until - now));
+            }
+            client.locked_until = None;
+        }
+
+        let recent = client.recent.entry(budget).or_default();
+        drop_older_than(recent, now, WINDOW);
+        if recent.len() >= budget.per_minute(&config) as usize {
+            client.limited += 1;
+            let retry_after = recent
+                .front()
+                .map(|oldest| WINDOW.saturating_sub(now.duration_since(*oldest)))
+                .unwrap_or(WINDOW);
+            return Err(Limited::TooManyRequests(budget, retry_after));
+        }
+
+        recent.push_back(now);
+        Ok(())
+    })
+}
+
+/// Record a failed authentication, locking the client out after too many
+pub fn record_auth_failure(ip: IpAddr) {
+    record_auth_failure_at(ip, Instant::now());
+}
+
+fn record_auth_failure_at(ip: IpAddr, now: Instant) {
+    let config = config::get().rate_limit;
+    if !config.enabled {
+        return;
+    }
+    let lockout = Duration::from_secs(config.lockout_minutes * 60);
+    let ip = network::client_ip(ip);
+
+    with_client(ip, now, |client| {
+        drop_older_than(&mut client.auth_failures, now, lockout);
+        client.auth_failures.push_back(now);
+        if client.auth_failures.len() >= config.max_auth_failures as usize {
+            warn!(
+                %ip,
+                "Locking out for {} minutes after {} failed authentications",
+                config.lockout_minutes,
+                client.auth_failures.len()
+            );
+            client.auth_failures.clear();
+            client.locked_until = Some(now + lockout);
+        }
+    });
+}
+
+/// Lift a lockout and forget the client's failed authentications
+pub fn unlock(ip: IpAddr) -> bool {
+    let ip = network::client_ip(ip);
+    match CLIENTS.lock().unwrap().get_mut(&ip) {
+        Some(client) => {
+            client.auth_failures.clear();
+            client.locked_until = None;
+            true
+        }
+        None => false,
+    }
+}
+
+/// Counters of every client seen since startup, most recently seen first
+pub fn stats() -> Vec<ClientStats> {
+    let now = Instant::now();
+    let clients = CLIENTS.lock().unwrap();
+    let mut stats: Vec<(Instant, ClientStats)> = clients
+        .iter()
+        .map(|(ip, client)| {
+            let recent = client
+                .recent
+                .iter()
+                .map(|(budget, times)| {
+                    let count = times
+                        .iter()
+                        .filter(|time| now.duration_since(**time) < WINDOW)
+                        .count();
+                    (*budget, count)
+                })
+                .collect();
+            let stats = ClientStats {
+                ip: ip.to_string(),
+                requests: client.requests,
+                limited: client.limited,
+                recent,
+                auth_failures: client.auth_failures.len(),
+                locked_for: client
+                    .locked_until
+                    .filter(|until| *until > now)
+                    .map(|until| (until - now).as_secs().max(1)),
+                idle_seconds: now.duration_since(client.last_seen).as_secs(),
+            };
+            (client.last_seen, stats)
+        })
+        .collect();
+
+    stats.sort_by_key(|(last_seen, _)| std::cmp::Reverse(*last_seen));
+    stats.into_iter().map(|(_, stats)| stats).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budgets_follow_the_route_pattern() {
+        assert!(Budget::for_route("POST", "/api/shutdown") == Budget::Power);
+        assert!(Budget::for_route("POST", "/api/wol/{name}") == Budget::Power);
+        assert!(Budget::for_route("DELETE", "/api/power/rules/{id}") == Budget::Power);
+        assert!(Budget::for_route("POST", "/api/volume/set") == Budget::Media);
+        assert!(Budget::for_route("POST", "/api/display/brightness") == Budget::Media);
+        assert!(Budget::for_route("GET", "/api/display/brightness") == Budget::Default);
+        assert!(Budget::for_route("GET", "/api/power/rules") == Budget::Default);
+        assert!(Budget::for_route("POST", "/api/clipboard") == Budget::Default);
+    }
+
+    fn locked_for(result: Result<(), Limited>) -> Option<Duration> {
+        match result {
+            Err(Limited::LockedOut(retry_after)) => Some(retry_after),
+            Err(limited) => panic!("unexpected limit: {}", limited),
+            Ok(()) => None,
+        }
+    }
+
+    #[test]
+    fn failed_authentications_lock_the_client_out_until_it_expires() {
+        config::use_test_dir();
+        let defaults = RateLimitConfig::default();
+        let lockout = Duration::from_secs(defaults.lockout_minutes * 60);
+        let ip: IpAddr = "192.0.2.10".parse().unwrap();
+        let start = Instant::now();
+
+        for failure in 1..defaults.max_auth_failures {
+            record_auth_failure_at(ip, start + Duration::from_secs(failure.into()));
+        }
+        let before_lockout = start + Duration::from_secs(30);
+        assert_eq!(
+            locked_for(check_at(ip, "GET", "/api/volume", before_lockout)),
+            None
+        );
+
+        let locked_at = start + Duration::from_secs(60);
+        record_auth_failure_at(ip, locked_at);
+        assert_eq!(
+            locked_for(check_at(ip, "GET", "/api/volume", locked_at)),
+            Some(lockout)
+        );
+        let later = locked_at + lockout - Duration::from_secs(60);
+        assert_eq!(
+            locked_for(check_at(ip, "GET", "/api/volume", later)),
+            Some(Duration::from_secs(60))
+        );
+
+        // The lockout expires, and the failures that caused it are forgotten
+        let expired = locked_at + lockout;
+        assert_eq!(
+            locked_for(check_at(ip, "GET", "/api/volume", expired)),
+            None
+        );
+        record_auth_failure_at(ip, expired);
+        assert_eq!(
+            locked_for(check_at(ip, "GET", "/api/volume", expired)),
+            None
+        );
+    }
+
+    #[test]
+    fn old_failures_do_not_count() {
+        config::use_test_dir();
+        let defaults = RateLimitConfig::default();
+        let lockout = Duration::from_secs(defaults.lockout_minutes * 60);
+        let ip: IpAddr = "192.0.2.11".parse().unwrap();
+        let start = Instant::now();
+
+        // Spread out so that no lockout time ever holds enough of them
+        for failure in 0..defaults.max_auth_failures * 2 {
+            let now = start + lockout / 2 * failure;
+            record_auth_failure_at(ip, now);
+            assert_eq!(locked_for(check_at(ip, "GET", "/api/volume", now)), None);
+        }
+    }
+
+    #[test]
+    fn unlocking_lifts_the_lockout() {
+        config::use_test_dir();
+        let ip: IpAddr = "192.0.2.12".parse().unwrap();
+        let now = Instant::now();
+
+        for _ in 0..RateLimitConfig::default().max_auth_failures {
+            record_auth_failure_at(ip, now);
+        }
+        assert!(locked_for(check_at(ip, "GET", "/api/volume", now)).is_some());
+
+        // IPv4-mapped IPv6 is the same client
+        assert!(unlock("::ffff:192.0.2.12".parse().unwrap()));
+        assert_eq!(locked_for(check_at(ip, "GET", "/api/volume", now)), None);
+        assert!(!unlock("192.0.2.13".parse().unwrap()));
+    }
+}
@@ -0,0 +1,300 @@
+use crate::config::{self, DeviceRole, RolesConfig};
+use crate::network;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::IpAddr;
+
+/// What a device may do over the web API; each role includes the ones before it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Read status, volume, schedules, processes and the like
+    Viewer,
+    /// Volume, brightness, screen, clipboard, files, notifications and speech
+    Media,
+    /// Shutdown, restart, sleep, Wake-on-LAN and peer actions
+    Power,
+    /// Everything, including changing schedules and power rules, killing
+    /// processes and reading the audit log
+    Admin,
+}
+
+impl Role {
+    /// Least role that may call the route with pattern `route`, e.g.
+    /// `/api/runs/{id}`. Routes missing here need admin.
+    pub fn required_for(method: &str, route: &str) -> Role {
+        match (method, route) {
+            (
+                "GET",
+                "/api/power/rules"
+                | "/api/schedules"
+                | "/api/schedules/preview"
+                | "/api/wol"
+                | "/api/peers"
+                | "/api/peers/{name}/status"
+                | "/api/discovery"
+                | "/api/role"
+                | "/api/pair"
+                | "/api/volume/get"
+                | "/api/display/brightness"
+                | "/api/screen/monitors"
+                | "/api/runs"
+                | "/api/runs/{id}"
+                | "/api/runs/{id}/stream"
+                | "/api/processes"
+                | "/api/processes/launchable"
+                | "/api/system/status",
+            ) => Role::Viewer,
+            // Reading the screen, clipboard or files is as private as changing them
+            (
+                "GET",
+                "/api/screen/screenshot"
+                | "/api/screen/stream"
+                | "/api/clipboard"
+                | "/api/clipboard/image"
+                | "/api/clipboard/events"
+                | "/api/files/share"
+                | "/api/files/download",
+            ) => Role::Media,
+            (
+                "POST",
+                "/api/volume/increase"
+                | "/api/volume/decrease"
+                | "/api/volume/set"
+                | "/api/display/brightness"
+                | "/api/open-url"
+                | "/api/notify"
+                | "/api/speak"
+                | "/api/clipboard"
+                | "/api/clipboard/image"
+                | "/api/files/upload",
+            ) => Role::Media,
+            (
+                "POST",
+                "/api/shutdown"
+                | "/api/restart"
+                | "/api/sleep"
+                | "/api/cancel"
+                | "/api/wol/{name}"
+                | "/api/peers/{name}/action"
+                | "/api/peers/bulk",
+            ) => Role::Power,
+            _ => Role::Admin,
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Role::Viewer => "viewer",
+            Role::Media => "media",
+            Role::Power => "power",
+            Role::Admin => "admin",
+        };
+        f.write_str(name)
+    }
+}
+
+pub fn settings() -> RolesConfig {
+    config::get().roles
+}
+
+/// Name of the cookie holding a paired browser's device token
+pub const TOKEN_COOKIE: &str = "ferrous_device";
+
+/// A token nobody is paired with, e.g. of a removed device
+#[derive(Debug)]
+pub struct UnknownToken;
+
+impl fmt::Display for UnknownToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("This device is not paired with this PC")
+    }
+}
+
+/// 32 random bytes, hex-encoded
+fn new_token() -> Result<String, String> {
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes).map_err(|e| format!("Failed to generate a token: {}", e))?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/// Compare in constant time, so response times don't reveal how much of a
+/// guessed token was right
+fn same_token(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Replace the role settings, generating tokens for devices saved without one
+pub fn set_settings(settings: RolesConfig) -> Result<(), String> {
+    let mut devices: Vec<DeviceRole> = Vec::with_capacity(settings.devices.len());
+    for device in settings.devices {
+        let name = device.name.trim().to_string();
+        if name.is_empty() {
+            return Err("Every device needs a name".to_string());
+        }
+        let token = match device.token.trim() {
+            "" => new_token()?,
+            token => token.to_string(),
+        };
+        if let Some(other) = devices.iter().find(|other| other.token == token) {
+            return Err(format!("{} and {} have the same token", other.name, name));
+        }
+        devices.push(DeviceRole {
+            name,
+            token,
+            role: device.role,
+        });
+    }
+
+    config::update(|config| {
+        config.roles = RolesConfig {
+            default_role: settings.default_role,
+            devices,
+        }
+    })
+    .map(|_| ())
+}
+
+/// The paired device holding `token`, if any
+pub fn device_for(token: &str) -> Option<DeviceRole> {
+    settings()
+        .devices
+        .into_iter()
+        .find(|device| !device.token.is_empty() && same_token(&device.token, token))
+}
+
+/// Role of a client at `ip` presenting `token`. Requests from this PC itself
+/// are always admin, and clients without a token get the default role.
+pub fn role_for(ip: IpAddr, token: Option<&str>) -> Result<Role, UnknownToken> {
+    if network::client_ip(ip).is_loopback() {
+        return Ok(Role::Admin);
+    }
+    match token {
+        None => Ok(settings().default_role),
+        Some(token) => device_for(token)
+            .map(|device| device.role)
+            .ok_or(UnknownToken),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routes_need_their_listed_role() {
+        let cases = [
+            ("GET", "/api/system/status", Role::Viewer),
+            ("GET", "/api/runs/{id}/stream", Role::Viewer),
+            ("GET", "/api/screen/screenshot", Role::Media),
+            ("GET", "/api/files/download", Role::Media),
+            ("POST", "/api/display/brightness", Role::Media),
+            ("POST", "/api/wol/{name}", Role::Power),
+            ("POST", "/api/peers/bulk", Role::Power),
+            ("GET", "/api/audit", Role::Admin),
+            ("POST", "/api/processes/kill", Role::Admin),
+            ("DELETE", "/api/schedules/{id}", Role::Admin),
+        ];
+        for (method, route, role) in cases {
+            assert_eq!(
+                Role::required_for(method, route),
+                role,
+                "{} {}",
+                method,
+                route
+            );
+        }
+    }
+
+    #[test]
+    fn unlisted_routes_need_admin() {
+        // Raw paths are not route patterns, so they never match a lower role
+        for (method, route) in [
+            ("GET", "/%61pi/screen/screenshot"),
+            ("GET", ""),
+            ("PUT", "/api/volume/set"),
+        ] {
+            assert_eq!(Role::required_for(method, route), Role::Admin);
+        }
+    }
+
+    #[test]
+    fn tokens_decide_the_role() {
+        config::use_test_dir();
+        set_settings(RolesConfig {
+            default_role: Role::Viewer,
+            devices: vec![
+                DeviceRole {
+                    name: "Kids' tablet".to_string(),
+                    token: "tablet-token".to_string(),
+                    role: Role::Media,
+                },
+                DeviceRole {
+                    name: "Office PC".to_string(),
+                    token: "office-token".to_string(),
+                    role: Role::Power,
+                },
+                DeviceRole {
+                    name: "Laptop".to_string(),
+                    token: String::new(),
+                    role: Role::Admin,
+                },
+            ],
+        })
+        .unwrap();
+        let generated = settings().devices[2].token.clone();
+        assert_eq!(generated.len(), 64);
+        assert!(generated.chars().all(|c| c.is_ascii_hexdigit()));
+        let remote: IpAddr = "192.0.2.70".parse().unwrap();
+
+        assert_eq!(role_for(remote, None).ok(), Some(Role::Viewer));
+        assert_eq!(
+            role_for(remote, Some("tablet-token")).ok(),
+            Some(Role::Media)
+        );
+        assert_eq!(
+            role_for(remote, Some("office-token")).ok(),
+            Some(Role::Power)
+        );
+        assert_eq!(role_for(remote, Some(&generated)).ok(), Some(Role::Admin));
+        assert!(role_for(remote, Some("tablet-token2")).is_err());
+        assert!(role_for(remote, Some("")).is_err());
+        assert_eq!(
+            role_for("::1".parse().unwrap(), Some("wrong")).ok(),
+            Some(Role::Admin)
+        );
+        assert_eq!(
+            device_for("office-token").map(|device| device.name),
+            Some("Office PC".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_unnamed_devices_and_shared_tokens() {
+        config::use_test_dir();
+        let device = |name: &str, token: &str| DeviceRole {
+            name: name.to_string(),
+            token: token.to_string(),
+            role: Role::Media,
+        };
+        let shared = set_settings(RolesConfig {
+            default_role: Role::Viewer,
+            devices: vec![device("Phone", "same"), device("Tablet", " same ")],
+        });
+        assert_eq!(
+            shared.err().as_deref(),
+            Some("Phone and Tablet have the same token")
+        );
+        let unnamed = set_settings(RolesConfig {
+            default_role: Role::Viewer,
+            devices: vec![device(" ", "")],
+        });
+        assert!(unnamed.is_err());
+    }
+}
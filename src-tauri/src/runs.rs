@@ -0,0 +1,537 @@
+use crate::metrics;
+use futures_util::stream::{self, Stream};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Read};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+use tracing::info;
+
+/// Maximum number of bytes kept for each of stdout and stderr of a run
+pub const OUTPUT_LIMIT: usize = 64 * 1024;
+
+/// Maximum number of finished runs kept in the history
+const HISTORY_LIMIT: usize = 100;
+
+#[derive(Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    Running,
+    Exited,
+    Killed,
+    Failed,
+}
+
+#[derive(Clone, Serialize)]
+pub struct RunRecord {
+    pub id: u64,
+    pub action: String,
+    pub command: String,
+    pub status: RunStatus,
+    pub started_at: u64,
+    pub ended_at: Option<u64>,
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+    pub stdout_truncated: bool,
+    pub stderr_truncated: bool,
+}
+
+/// A single event of a run, as streamed to live listeners
+#[derive(Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RunEvent {
+    Output { stream: &'static str, data: String },
+    Finished { status: RunStatus, exit_code: Option<i32> },
+}
+
+struct Run {
+    record: RunRecord,
+    events: broadcast::Sender<RunEvent>,
+    child: Arc<Mutex<Child>>,
+    kill_requested: bool,
+}
+
+#[derive(Default)]
+struct RunHistory {
+    next_id: u64,
+    runs: HashMap<u64, Run>,
+    order: VecDeque<u64>,
+}
+
+fn history() -> &'static Mutex<RunHistory> {
+    static HISTORY: OnceLock<Mutex<RunHistory>> = OnceLock::new();
+    HISTORY.get_or_init(|| Mutex::new(RunHistory::default()))
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn describe(command: &Command) -> String {
+    let mut parts = vec![command.get_program().to_string_lossy().into_owned()];
+    parts.extend(command.get_args().map(|arg| arg.to_string_lossy().into_owned()));
+    parts.join(" ")
+}
+
+/// Spawn a command for the given action and record it in the run history.
+///
+/// Stdout and stderr are captured (up to `OUTPUT_LIMIT` bytes each) and
+/// forwarded to live listeners while the process runs.
+pub fn spawn(action: &str, command: &mut Command) -> io::Result<u64> {
+    let command_line = describe(command);
+    let spawned = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+
+    let mut child = match spawned {
+        Ok(child) => child,
+        Err(e) => {
+            metrics::record_action(action, false);
+            return Err(e);
+        }
+    };
+
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let child = Arc::new(Mutex::new(child));
+
+    let id = {
+        let mut history = history().lock().unwrap();
+        history.next_id += 1;
+        let id = history.next_id;
+        let (events, _) = broadcast::channel(256);
+
+        history.runs.insert(
+            id,
+            Run {
+                record: RunRecord {
+                    id,
+                    action: action.to_string(),
+                    command: command_line,
+                    status: RunStatus::Running,
+                    started_at: now_millis(),
+                    ended_at: None,
+                    exit_code: None,
+                    stdout: String::new(),
+                    stderr: String::new(),
+                    stdout_truncated: false,
+                    stderr_truncated: false,
+                },
+                events,
+                child: Arc::clone(&child),
+                kill_requested: false,
+            },
+        );
+        history.order.push_back(id);
+        prune(&mut history);
+        id
+    };
+
+    let readers: Vec<_> = [
+        stdout.map(|pipe| capture(id, "stdout", pipe)),
+        stderr.map(|pipe| capture(id, "stderr", pipe)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+
+    thread::spawn(move || {
+        let exit = loop {
+            match child.lock().unwrap().try_wait() {
+                Ok(Some(status)) => break Ok(status),
+                Ok(None) => {}
+                Err(e) => break Err(e),
+            }
+            thread::sleep(Duration::from_millis(100));
+        };
+
+        // Give the readers a moment to drain the pipes. Background children
+        // (e.g. the delayed macOS shutdown) may keep them open much longer.
+        for _ in 0..20 {
+            if readers.iter().all(|reader| reader.is_finished()) {
+                break;
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+
+        finish(id, exit);
+    });
+
+    Ok(id)
+}
+
+fn capture<R: Read + Send + 'static>(
+    id: u64,
+    stream: &'static str,
+    mut pipe: R,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut buf = [0u8; 4096];
+        let mut pending = Vec::new();
+        loop {
+            let n = match pipe.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => n,
+            };
+            let data = decode_chunk(&mut pending, &buf[..n]);
+            if !data.is_empty() && !record_output(id, stream, data) {
+                return;
+            }
+        }
+
+        // A character cut off by the end of the output can't be completed
+        if !pending.is_empty() {
+            record_output(id, stream, String::from_utf8_lossy(&pending).into_owned());
+        }
+    })
+}
+
+/// Decode a chunk of output. A character split across two reads is held
+/// back in `pending` until the rest of it arrives.
+fn decode_chunk(pending: &mut Vec<u8>, chunk: &[u8]) -> String {
+    pending.extend_from_slice(chunk);
+    let complete = pending.len() - incomplete_tail(pending);
+    let data = String::from_utf8_lossy(&pending[..complete]).into_owned();
+    pending.drain(..complete);
+    data
+}
+
+/// Number of bytes at the end of `bytes` that start a character but don't finish it
+fn incomplete_tail(bytes: &[u8]) -> usize {
+    // Walk back over continuation bytes to the last lead byte
+    for back in 1..=bytes.len().min(4) {
+        let byte = bytes[bytes.len() - back];
+        if byte & 0xC0 != 0x80 {
+            let length = match byte {
+                0xC0..=0xDF => 2,
+                0xE0..=0xEF => 3,
+                0xF0..=0xF7 => 4,
+                _ => 1,
+            };
+            return if length > back { back } else { 0 };
+        }
+    }
+    0
+}
+
+/// Add output to a run and forward it to live listeners. Returns false once
+/// the run is gone from the history.
+fn record_output(id: u64, stream: &'static str, data: String) -> bool {
+    let mut history = history().lock().unwrap();
+    let Some(run) = history.runs.get_mut(&id) else {
+        return false;
+    };
+
+    let (output, truncated) = match stream {
+        "stdout" => (&mut run.record.stdout, &mut run.record.stdout_truncated),
+        _ => (&mut run.record.stderr, &mut run.record.stderr_truncated),
+    };
+    append_limited(output, truncated, &data);
+
+    let _ = run.events.send(RunEvent::Output { stream, data });
+    true
+}
+
+fn append_limited(output: &mut String, truncated: &mut bool, data: &str) {
+    if *truncated {
+        return;
+    }
+
+    let remaining = OUTPUT_LIMIT.saturating_sub(output.len());
+    if data.len() <= remaining {
+        output.push_str(data);
+        return;
+    }
+
+    let mut end = remaining;
+    while !data.is_char_boundary(end) {
+        end -= 1;
+    }
+    output.push_str(&data[..end]);
+    *truncated = true;
+}
+
+fn finish(id: u64, exit: io::Result<std::process::ExitStatus>) {
+    let mut history = history().lock().unwrap();
+    let Some(run) = history.runs.get_mut(&id) else {
+        return;
+    };
+
+    let (status, exit_code) = match exit {
+        Ok(exit) if run.kill_requested => (RunStatus::Killed, exit.code()),
+        Ok(exit) => (RunStatus::Exited, exit.code()),
+        Err(e) => {
+            run.record.stderr.push_str(&format!("Failed to wait for process: {}", e));
+            (RunStatus::Failed, None)
+        }
+    };
+
+    run.record.status = status;
+    run.record.exit_code = exit_code;
+    run.record.ended_at = Some(now_millis());
+
+    info!(id, action = %run.record.action, ?exit_code, "Run finished");
+
+    metrics::record_action(
+        &run.record.action,
+        status == RunStatus::Exited && exit_code == Some(0),
+    );
+
+    let _ = run.events.send(RunEvent::Finished { status, exit_code });
+}
+
+/// Drop the oldest finished runs once the history grows past its limit
+fn prune(history: &mut RunHistory) {
+    while history.order.len() > HISTORY_LIMIT {
+        let finished = history.order.iter().position(|id| {
+            history
+                .runs
+                .get(id)
+                .is_none_or(|run| run.record.status != RunStatus::Running)
+        });
+
+        match finished {
+            Some(index) => {
+                if let Some(id) = history.order.remove(index) {
+                    history.runs.remove(&id);
+                }
+            }
+            None => break,
+        }
+    }
+}
+
+/// List all recorded runs, newest first
+pub fn list() -> Vec<RunRecord> {
+    let history = history().lock().unwrap();
+    history
+        .order
+        .iter()
+        .rev()
+        .filter_map(|id| history.runs.get(id))
+        .map(|run| run.record.clone())
+        .collect()
+}
+
+/// Get a single run by id
+pub fn get(id: u64) -> Option<RunRecord> {
+    let history = history().lock().unwrap();
+    history.runs.get(&id).map(|run| run.record.clone())
+}
+
+/// Kill a running process. Returns `Ok(false)` if the run has already finished.
+pub fn kill(id: u64) -> Result<bool, String> {
+    let child = {
+        let mut history = history().lock().unwrap();
+        let run = history
+            .runs
+            .get_mut(&id)
+            .ok_or_else(|| format!("Run {} not found", id))?;
+
+        if run.record.status != RunStatus::Running {
+            return Ok(false);
+        }
+
+        run.kill_requested = true;
+        Arc::clone(&run.child)
+    };
+
+    let result = child.lock().unwrap().kill();
+    match result {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::InvalidInput => Ok(false),
+        Err(e) => Err(format!("Failed to kill run {}: {}", id, e)),
+    }
+}
+
+/// Stream the events of a run: the output captured so far, followed by live
+/// output until the process exits.
+pub fn events(id: u64) -> Option<impl Stream<Item = RunEvent>> {
+    let history = history().lock().unwrap();
+    let run = history.runs.get(&id)?;
+
+    // Snapshot and subscribe under the same lock so no output is missed
+    let mut backlog = VecDeque::new();
+    if !run.record.stdout.is_empty() {
+        backlog.push_back(RunEvent::Output {
+            stream: "stdout",
+            data: run.record.stdout.clone(),
+        });
+    }
+    if !run.record.stderr.is_empty() {
+        backlog.push_back(RunEvent::Output {
+            stream: "stderr",
+            data: run.record.stderr.clone(),
+        });
+    }
+
+    let receiver = if run.record.status == RunStatus::Running {
+        Some(run.events.subscribe())
+    } else {
+        backlog.push_back(RunEvent::Finished {
+            status: run.record.status,
+            exit_code: run.record.exit_code,
+        });
+        None
+    };
+
+    Some(stream::unfold(
+        (backlog, receiver),
+        |(mut backlog, mut receiver)| async move {
+            if let Some(event) = backlog.pop_front() {
+                return Some((event, (backlog, receiver)));
+            }
+
+            let rx = receiver.as_mut()?;
+            loop {
+                match rx.recv().await {
+                    Ok(event @ RunEvent::Finished { .. }) => {
+                        return Some((event, (backlog, None)));
+                    }
+                    Ok(event) => return Some((event, (backlog, receiver))),
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wait_until_finished(id: u64) -> RunRecord {
+        for _ in 0..100 {
+            let record = get(id).unwrap();
+            if record.status != RunStatus::Running {
+                return record;
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+        panic!("Run {} did not finish", id);
+    }
+
+    #[test]
+    fn output_is_truncated_at_the_limit() {
+        let mut output = String::new();
+        let mut truncated = false;
+
+        append_limited(&mut output, &mut truncated, &"a".repeat(OUTPUT_LIMIT - 1));
+        assert!(!truncated);
+        // The two-byte character no longer fits, so it is dropped whole
+        append_limited(&mut output, &mut truncated, "éb");
+        assert!(truncated);
+        assert_eq!(output.len(), OUTPUT_LIMIT - 1);
+
+        append_limited(&mut output, &mut truncated, "c");
+        assert_eq!(output.len(), OUTPUT_LIMIT - 1);
+    }
+
+    #[test]
+    fn characters_split_across_reads_are_kept_whole() {
+        let text = "ok é € 😀";
+        let bytes = text.as_bytes();
+        let mut pending = Vec::new();
+        let mut decoded = String::new();
+        for chunk in bytes.chunks(1) {
+            decoded.push_str(&decode_chunk(&mut pending, chunk));
+        }
+        assert_eq!(decoded, text);
+        assert!(pending.is_empty());
+
+        // Invalid bytes are still replaced rather than held back
+        assert_eq!(decode_chunk(&mut pending, b"a\xffb"), "a\u{fffd}b");
+        assert!(pending.is_empty());
+        assert_eq!(decode_chunk(&mut pending, &"é".as_bytes()[..1]), "");
+        assert_eq!(pending.len(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn prune_drops_the_oldest_finished_runs() {
+        let mut history = RunHistory::default();
+        for id in 1..=HISTORY_LIMIT as u64 + 3 {
+            let child = Command::new("true").spawn().unwrap();
+            let status = if id == 1 {
+                RunStatus::Running
+            } else {
+                RunStatus::Exited
+            };
+            history.runs.insert(
+                id,
+                Run {
+                    record: RunRecord {
+                        id,
+                        action: "test".to_string(),
+                        command: "true".to_string(),
+                        status,
+                        started_at: 0,
+                        ended_at: None,
+                        exit_code: None,
+                        stdout: String::new(),
+                        stderr: String::new(),
+                        stdout_truncated: false,
+                        stderr_truncated: false,
+                    },
+                    events: broadcast::channel(1).0,
+                    child: Arc::new(Mutex::new(child)),
+                    kill_requested: false,
+                },
+            );
+            history.order.push_back(id);
+        }
+
+        prune(&mut history);
+
+        assert_eq!(history.order.len(), HISTORY_LIMIT);
+        assert_eq!(history.runs.len(), HISTORY_LIMIT);
+        // The running one is kept; the three oldest finished ones are gone
+        assert_eq!(history.order.iter().take(2).collect::<Vec<_>>(), [&1, &5]);
+        for (_, run) in history.runs.drain() {
+            let _ = run.child.lock().unwrap().wait();
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn finished_runs_record_exit_code_and_output() {
+        let id = spawn(
+            "test",
+            Command::new("sh").args(["-c", "echo hi; echo oops >&2; exit 3"]),
+        )
+        .unwrap();
+        let record = wait_until_finished(id);
+
+        assert!(record.status == RunStatus::Exited);
+        assert_eq!(record.exit_code, Some(3));
+        assert_eq!(record.stdout, "hi\n");
+        assert_eq!(record.stderr, "oops\n");
+        assert!(record.ended_at.is_some());
+        assert_eq!(kill(id), Ok(false));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn killed_runs_are_marked_killed() {
+        let id = spawn("test", Command::new("sleep").arg("30")).unwrap();
+        assert!(get(id).unwrap().status == RunStatus::Running);
+
+        assert_eq!(kill(id), Ok(true));
+        let record = wait_until_finished(id);
+        assert!(record.status == RunStatus::Killed);
+        assert_eq!(record.exit_code, None);
+
+        assert_eq!(kill(id), Ok(false));
+        assert_eq!(kill(u64::MAX), Err(format!("Run {} not found", u64::MAX)));
+    }
+}
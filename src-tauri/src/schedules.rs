@@ -0,0 +1,620 @@
+use crate::actions::{self, Action};
+use crate::audit::{self, Origin};
+use crate::config;
+use chrono::{
+    DateTime, Datelike, Duration as ChronoDuration, Local, LocalResult, NaiveDateTime, TimeZone,
+    Timelike,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::sync::{Mutex, Once};
+use std::time::Duration;
+use tracing::{error, info, info_span, warn};
+
+const SCHEDULES_FILE: &str = "schedules.json";
+
+/// How often the scheduler checks for due jobs
+const TICK: Duration = Duration::from_secs(15);
+
+/// A run this late is treated as missed, e.g. because the PC was asleep
+const MISSED_AFTER: Duration = Duration::from_secs(120);
+
+/// How far ahead to look for the next run before giving up
+const SEARCH_DAYS: i64 = 366 * 5;
+
+const MONTH_NAMES: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAY_NAMES: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// A five-field cron expression: minute, hour, day of month, month, day of week
+#[derive(Clone)]
+pub struct Cron {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    /// Bit 0 is Sunday
+    weekdays: u64,
+    /// Like cron, when both day fields are restricted a day matching either one runs
+    any_day: bool,
+    any_weekday: bool,
+}
+
+fn parse_value(value: &str, min: u32, names: &[&str]) -> Result<u32, String> {
+    if let Some(index) = names
+        .iter()
+        .position(|name| name.eq_ignore_ascii_case(value))
+    {
+        return Ok(index as u32 + min);
+    }
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value {}", value))
+}
+
+/// Parse one field into a bit set, supporting `*`, lists, ranges, steps and names
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64, String> {
+    let mut bits = 0u64;
+
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => match step.parse::<u32>() {
+                Ok(step) if step > 0 => (range, step),
+                _ => return Err(format!("Invalid step in {}", part)),
+            },
+            None => (part, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (
+                parse_value(start, min, names)?,
+                parse_value(end, min, names)?,
+            )
+        } else {
+            let value = parse_value(range, min, names)?;
+            // `5/15` means every 15 starting at 5
+            (value, if step > 1 { max } else { value })
+        };
+
+        if start < min || end > max || start > end {
+            return Err(format!("{} is out of range {}-{}", part, min, max));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+
+    Ok(bits)
+}
+
+impl Cron {
+    pub fn parse(expression: &str) -> Result<Cron, String> {
+        let expression = match expression.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other => other,
+        };
+
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(
+                "Expected 5 fields: minute hour day-of-month month day-of-week".to_string(),
+            );
+        };
+
+        let mut weekdays = parse_field(weekday, 0, 7, &WEEKDAY_NAMES)?;
+        // 7 is another name for Sunday
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+
+        Ok(Cron {
+            minutes: parse_field(minute, 0, 59, &[])?,
+            hours: parse_field(hour, 0, 23, &[])?,
+            days: parse_field(day, 1, 31, &[])?,
+            months: parse_field(month, 1, 12, &MONTH_NAMES)?,
+            weekdays,
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+        })
+    }
+
+    fn matches_date(&self, time: &NaiveDateTime) -> bool {
+        if self.months & (1 << time.month()) == 0 {
+            return false;
+        }
+        let day = self.days & (1 << time.day()) != 0;
+        let weekday = self.weekdays & (1 << time.weekday().num_days_from_sunday()) != 0;
+
+        match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (true, false) => weekday,
+            (false, true) => day,
+            (false, false) => day || weekday,
+        }
+    }
+
+    /// The first matching minute strictly after `after`, in local time
+    pub fn next_after(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        let start = after.naive_local().with_second(0)?.with_nanosecond(0)?;
+        let limit = start + ChronoDuration::days(SEARCH_DAYS);
+        let mut time = start + ChronoDuration::minutes(1);
+
+        while time < limit {
+            if !self.matches_date(&time) {
+                time = (time.date() + ChronoDuration::days(1)).and_hms_opt(0, 0, 0)?;
+                continue;
+            }
+            if self.hours & (1 << time.hour()) == 0 {
+                time = time.with_minute(0)? + ChronoDuration::hours(1);
+                continue;
+            }
+            if self.minutes & (1 << time.minute()) == 0 {
+                time += ChronoDuration::minutes(1);
+                continue;
+            }
+
+            match Local.from_local_datetime(&time) {
+                LocalResult::Single(time) => return Some(time),
+                // The earlier of the two when clocks go back
+                LocalResult::Ambiguous(time, _) => return Some(time),
+                // Skipped when clocks go forward
+                LocalResult::None => time += ChronoDuration::minutes(1),
+            }
+        }
+
+        None
+    }
+}
+
+/// What to do with runs that were due while the PC was asleep or the app was closed
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MissedPolicy {
+    /// Drop missed runs and wait for the next one
+    #[default]
+    Skip,
+    /// Run once as soon as possible, however many runs were missed
+    RunOnce,
+}
+
+impl MissedPolicy {
+    /// Whether a run that was due at `due` should still happen at `now`
+    fn runs(self, due: DateTime<Local>, now: DateTime<Local>) -> bool {
+        let missed_after = ChronoDuration::from_std(MISSED_AFTER).unwrap_or_default();
+        now - due <= missed_after || self == MissedPolicy::RunOnce
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// The user-editable part of a schedule
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ScheduleSpec {
+    pub name: String,
+    /// Cron expression, e.g. `0 1 * * 1-5` for 01:00 on weekdays
+    pub cron: String,
+    pub action: Action,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub missed: MissedPolicy,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Schedule {
+    pub id: u64,
+    #[serde(flatten)]
+    pub spec: ScheduleSpec,
+    /// Unix milliseconds of the last run
+    pub last_run: Option<u64>,
+    pub last_result: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct ScheduleStatus {
+    #[serde(flatten)]
+    pub schedule: Schedule,
+    /// Unix milliseconds of the next run, if enabled
+    pub next_run: Option<u64>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct Store {
+    next_id: u64,
+    schedules: Vec<Schedule>,
+}
+
+struct State {
+    store: Store,
+    /// When each enabled schedule is next due
+    due: HashMap<u64, DateTime<Local>>,
+}
+
+static STATE: Mutex<Option<State>> = Mutex::new(None);
+
+fn to_millis(time: DateTime<Local>) -> u64 {
+    time.timestamp_millis().max(0) as u64
+}
+
+fn from_millis(millis: u64) -> Option<DateTime<Local>> {
+    Local.timestamp_millis_opt(millis as i64).single()
+}
+
+fn load() -> Store {
+    let path = config::config_dir().join(SCHEDULES_FILE);
+
+    match fs::read_to_string(&path) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+            warn!("Invalid schedules file {}: {}", path.display(), e);
+            Store::default()
+        }),
+        Err(_) => Store::default(),
+    }
+}
+
+fn save(store: &Store) -> Result<(), String> {
+    let dir = config::config_dir();
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create config directory: {}", e))?;
+
+    let contents = serde_json::to_string_pretty(store)
+        .map_err(|e| format!("Failed to serialize schedules: {}", e))?;
+    fs::write(dir.join(SCHEDULES_FILE), contents)
+        .map_err(|e| format!("Failed to write schedules: {}", e))
+}
+
+/// The first run of an enabled schedule after `after`
+fn next_run(schedule: &Schedule, after: DateTime<Local>) -> Option<DateTime<Local>> {
+    if !schedule.spec.enabled {
+        return None;
+    }
+    Cron::parse(&schedule.spec.cron).ok()?.next_after(after)
+}
+
+/// When a schedule is next due, counting from its last run so runs missed while the
+/// app was closed are noticed
+fn first_due(schedule: &Schedule, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    let from = schedule.last_run.and_then(from_millis).unwrap_or(now);
+    next_run(schedule, from.min(now))
+}
+
+fn with_state<T>(f: impl FnOnce(&mut State) -> T) -> T {
+    let mut state = STATE.lock().unwrap();
+    let state = state.get_or_insert_with(|| {
+        let store = load();
+        let now = Local::now();
+        let due = store
+            .schedules
+            .iter()
+            .filter_map(|schedule| Some((schedule.id, first_due(schedule, now)?)))
+            .collect();
+        State { store, due }
+    });
+    f(state)
+}
+
+fn status(state: &State, schedule: &Schedule) -> ScheduleStatus {
+    ScheduleStatus {
+        schedule: schedule.clone(),
+        next_run: state.due.get(&schedule.id).copied().map(to_millis),
+    }
+}
+
+fn validate(spec: &ScheduleSpec) -> Result<(), String> {
+    if spec.name.trim().is_empty() {
+        return Err("Name is required".to_string());
+    }
+    Cron::parse(&spec.cron)?
+        .next_after(Local::now())
+        .ok_or("The schedule never runs")?;
+    spec.action.validate()
+}
+
+/// All schedules with their next run time
+pub fn list() -> Vec<ScheduleStatus> {
+    with_state(|state| {
+        state
+            .store
+            .schedules
+            .iter()
+            .map(|schedule| status(state, schedule))
+            .collect()
+    })
+}
+
+pub fn create(spec: ScheduleSpec) -> Result<ScheduleStatus, String> {
+    validate(&spec)?;
+
+    with_state(|state| {
+        state.store.next_id += 1;
+        let schedule = Schedule {
+            id: state.store.next_id,
+            spec,
+            last_run: None,
+            last_result: None,
+        };
+        state.store.schedules.push(schedule.clone());
+        save(&state.store)?;
+
+        if let Some(next) = next_run(&schedule, Local::now()) {
+            state.due.insert(schedule.id, next);
+        }
+        Ok(status(state, &schedule))
+    })
+}
+
+/// Replace a schedule's settings; `Ok(None)` if it does not exist
+pub fn update(id: u64, spec: ScheduleSpec) -> Result<Option<ScheduleStatus>, String> {
+    validate(&spec)?;
+
+    with_state(|state| {
+        let Some(schedule) = state.store.schedules.iter_mut().find(|s| s.id == id) else {
+            return Ok(None);
+        };
+        schedule.spec = spec;
+        let schedule = schedule.clone();
+        save(&state.store)?;
+
+        // Timing starts over from now, so editing never counts as a missed run
+        match next_run(&schedule, Local::now()) {
+            Some(next) => state.due.insert(id, next),
+            None => state.due.remove(&id),
+        };
+        Ok(Some(status(state, &schedule)))
+    })
+}
+
+/// Delete a schedule; `Ok(false)` if it does not exist
+pub fn delete(id: u64) -> Result<bool, String> {
+    with_state(|state| {
+        let before = state.store.schedules.len();
+        state.store.schedules.retain(|schedule| schedule.id != id);
+        if state.store.schedules.len() == before {
+            return Ok(false);
+        }
+        state.due.remove(&id);
+        save(&state.store)?;
+        Ok(true)
+    })
+}
+
+/// The next `count` run times of a cron expression, in unix milliseconds
+pub fn preview(cron: &str, count: usize) -> Result<Vec<u64>, String> {
+    let cron = Cron::parse(cron)?;
+    let mut runs = Vec::new();
+    let mut time = Local::now();
+
+    while runs.len() < count {
+        match cron.next_after(time) {
+            Some(next) => {
+                runs.push(to_millis(next));
+                time = next;
+            }
+            None => break,
+        }
+    }
+
+    Ok(runs)
+}
+
+/// Start the scheduler thread
+pub fn start() {
+    static SCHEDULER: Once = Once::new();
+    SCHEDULER.call_once(|| {
+        std::thread::spawn(|| loop {
+            run_due();
+            std::thread::sleep(TICK);
+        });
+    });
+}
+
+/// Run every schedule whose time has come, applying the missed-run policy to late ones
+fn run_due() {
+    let now = Local::now();
+
+    let to_run: Vec<Schedule> = with_state(|state| {
+        let mut to_run = Vec::new();
+
+        for schedule in &state.store.schedules {
+            let Some(due) = state.due.get(&schedule.id).copied() else {
+                continue;
+            };
+            if due > now {
+                continue;
+            }
+
+            if schedule.spec.missed.runs(due, now) {
+                to_run.push(schedule.clone());
+            } else {
+                info!(
+                    schedule = schedule.id,
+                    name = %schedule.spec.name,
+                    "Skipping missed run due at {}",
+                    due.format("%Y-%m-%d %H:%M")
+                );
+            }
+
+            // Move on to the first run after now, dropping any others that were missed
+            match next_run(schedule, now) {
+                Some(next) => state.due.insert(schedule.id, next),
+                None => state.due.remove(&schedule.id),
+            };
+        }
+
+        to_run
+    });
+
+    for schedule in to_run {
+        let span = info_span!("schedule", id = schedule.id, name = %schedule.spec.name);
+        let _entered = span.enter();
+        info!("Running {}", schedule.spec.action.describe());
+        let outcome = actions::run(&schedule.spec.action);
+        audit::record_action(
+            Origin::Schedule,
+            schedule.spec.action.name(),
+            serde_json::json!({ "schedule": schedule.spec.name, "action": schedule.spec.action }),
+            outcome.is_ok(),
+            outcome.as_ref().unwrap_or_else(|message| message),
+        );
+        let result = match outcome {
+            Ok(message) => message,
+            Err(message) => format!("Failed: {}", message),
+        };
+
+        with_state(|state| {
+            if let Some(stored) = state
+                .store
+                .schedules
+                .iter_mut()
+                .find(|stored| stored.id == schedule.id)
+            {
+                stored.last_run = Some(to_millis(now));
+                stored.last_result = Some(result);
+            }
+            if let Err(e) = save(&state.store) {
+                error!("{}", e);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(year, month, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn finds_the_next_run() {
+        // 2026-01-01 is a Thursday
+        let cases = [
+            (
+                "*/15 * * * *",
+                at(2026, 1, 1, 10, 7),
+                at(2026, 1, 1, 10, 15),
+            ),
+            (
+                "5/15 * * * *",
+                at(2026, 1, 1, 10, 50),
+                at(2026, 1, 1, 11, 5),
+            ),
+            ("0 * * * *", at(2026, 1, 1, 10, 0), at(2026, 1, 1, 11, 0)),
+            ("@daily", at(2026, 1, 1, 10, 0), at(2026, 1, 2, 0, 0)),
+            // Weekday ranges and names
+            ("0 1 * * 1-5", at(2026, 1, 2, 2, 0), at(2026, 1, 5, 1, 0)),
+            (
+                "30 9 * * mon-fri",
+                at(2026, 1, 3, 12, 0),
+                at(2026, 1, 5, 9, 30),
+            ),
+            (
+                "0 8 * * sat,sun",
+                at(2026, 1, 1, 12, 0),
+                at(2026, 1, 3, 8, 0),
+            ),
+            ("0 0 * * 7", at(2026, 1, 1, 12, 0), at(2026, 1, 4, 0, 0)),
+            // Either day field matches when both are restricted
+            ("0 0 13 * fri", at(2026, 1, 1, 12, 0), at(2026, 1, 2, 0, 0)),
+            // Day, month and year rollover
+            ("0 0 1 * *", at(2026, 1, 31, 12, 0), at(2026, 2, 1, 0, 0)),
+            (
+                "59 23 31 * *",
+                at(2026, 1, 31, 23, 59),
+                at(2026, 3, 31, 23, 59),
+            ),
+            ("0 0 1 jan *", at(2026, 6, 15, 12, 0), at(2027, 1, 1, 0, 0)),
+            ("0 12 29 2 *", at(2026, 3, 1, 0, 0), at(2028, 2, 29, 12, 0)),
+        ];
+
+        for (cron, after, expected) in cases {
+            let next = Cron::parse(cron).unwrap().next_after(after);
+            assert_eq!(next, Some(expected), "{} after {}", cron, after);
+        }
+    }
+
+    #[test]
+    fn impossible_dates_never_run() {
+        let cron = Cron::parse("0 0 30 2 *").unwrap();
+        assert_eq!(cron.next_after(at(2026, 1, 1, 0, 0)), None);
+    }
+
+    #[test]
+    fn rejects_invalid_expressions() {
+        for cron in [
+            "",
+            "* * * *",
+            "* * * * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "* * 0 * *",
+            "* * 32 * *",
+            "* * * 13 *",
+            "* * * * 8",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "foo * * * *",
+            "* * * * funday",
+        ] {
+            assert!(Cron::parse(cron).is_err(), "{:?}", cron);
+        }
+    }
+
+    #[test]
+    fn runs_on_time_regardless_of_policy() {
+        let due = at(2026, 1, 1, 1, 0);
+        let now = due + ChronoDuration::seconds(30);
+        assert!(MissedPolicy::Skip.runs(due, now));
+        assert!(MissedPolicy::RunOnce.runs(due, now));
+
+        let limit = due + ChronoDuration::from_std(MISSED_AFTER).unwrap();
+        assert!(MissedPolicy::Skip.runs(due, limit));
+    }
+
+    #[test]
+    fn runs_missed_after_sleep_only_when_asked() {
+        let due = at(2026, 1, 1, 1, 0);
+        // Woke up hours after the run was due
+        let now = at(2026, 1, 1, 7, 30);
+        assert!(!MissedPolicy::Skip.runs(due, now));
+        assert!(MissedPolicy::RunOnce.runs(due, now));
+    }
+
+    #[test]
+    fn counts_from_the_last_run_to_notice_missed_runs() {
+        let mut schedule = Schedule {
+            id: 1,
+            spec: ScheduleSpec {
+                name: "Nightly".to_string(),
+                cron: "0 1 * * *".to_string(),
+                action: Action::Shutdown,
+                enabled: true,
+                missed: MissedPolicy::RunOnce,
+            },
+            last_run: Some(to_millis(at(2026, 1, 1, 1, 0))),
+            last_result: None,
+        };
+        let now = at(2026, 1, 3, 12, 0);
+
+        // The app was closed for two nights; the first missed run is due
+        assert_eq!(first_due(&schedule, now), Some(at(2026, 1, 2, 1, 0)));
+
+        schedule.last_run = None;
+        assert_eq!(first_due(&schedule, now), Some(at(2026, 1, 4, 1, 0)));
+
+        schedule.spec.enabled = false;
+        assert_eq!(first_due(&schedule, now), None);
+    }
+}
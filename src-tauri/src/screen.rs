@@ -0,0 +1,471 @@
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat as EncodedFormat, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+
+#[derive(Clone, Serialize)]
+pub struct Monitor {
+    pub index: usize,
+    pub name: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub primary: bool,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFormat {
+    #[default]
+    Png,
+    Jpeg,
+}
+
+impl ImageFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "image/png",
+            ImageFormat::Jpeg => "image/jpeg",
+        }
+    }
+}
+
+/// List the monitors that can be captured
+pub fn monitors() -> Result<Vec<Monitor>, String> {
+    if cfg!(target_os = "linux") {
+        #[cfg(target_os = "linux")]
+        {
+            x11::monitors()
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            Err("Linux-only code path".to_string())
+        }
+    } else if cfg!(target_os = "windows") {
+        windows_monitors()
+    } else if cfg!(target_os = "macos") {
+        // screencapture has no listing mode, so report the main display by capturing it
+        let image = capture(0)?;
+        Ok(vec![Monitor {
+            index: 0,
+            name: "Main display".to_string(),
+            x: 0,
+            y: 0,
+            width: image.width(),
+            height: image.height(),
+            primary: true,
+        }])
+    } else {
+        Err("Unsupported operating system".to_string())
+    }
+}
+
+/// Capture a monitor as raw RGBA pixels
+pub fn capture(monitor: usize) -> Result<RgbaImage, String> {
+    if cfg!(target_os = "linux") {
+        #[cfg(target_os = "linux")]
+        {
+            x11::capture(monitor)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            Err("Linux-only code path".to_string())
+        }
+    } else if cfg!(target_os = "windows") {
+        let target = windows_monitors()?
+            .into_iter()
+            .find(|m| m.index == monitor)
+            .ok_or_else(|| format!("Monitor {} not found", monitor))?;
+
+        let script = format!(
+            "Add-Type -AssemblyName System.Drawing; \
+             $bitmap = New-Object System.Drawing.Bitmap {w}, {h}; \
+             $graphics = [System.Drawing.Graphics]::FromImage($bitmap); \
+             $graphics.CopyFromScreen({x}, {y}, 0, 0, $bitmap.Size); \
+             $bitmap.Save($env:FERROUS_SCREENSHOT_PATH, [System.Drawing.Imaging.ImageFormat]::Png)",
+            w = target.width,
+            h = target.height,
+            x = target.x,
+            y = target.y
+        );
+        capture_to_file(|path| {
+            std::process::Command::new("powershell")
+                .args(["-NoProfile", "-Command", &script])
+                .env("FERROUS_SCREENSHOT_PATH", path)
+                .output()
+        })
+    } else if cfg!(target_os = "macos") {
+        let display = (monitor + 1).to_string();
+        capture_to_file(|path| {
+            std::process::Command::new("screencapture")
+                .args(["-x", "-t", "png", "-D", &display, path])
+                .output()
+        })
+    } else {
+        Err("Unsupported operating system".to_string())
+    }
+}
+
+/// Run a capture tool that writes a PNG file and load the result
+fn capture_to_file<F>(run: F) -> Result<RgbaImage, String>
+where
+    F: FnOnce(&str) -> std::io::Result<std::process::Output>,
+{
+    use std::sync::atomic::{AtomicU64, Ordering};
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let path = std::env::temp_dir().join(format!(
+        "ferrous-control-screenshot-{}-{}.png",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let path_str = path.to_string_lossy().into_owned();
+
+    let output = run(&path_str).map_err(|e| format!("Failed to capture screen: {}", e))?;
+    let image = image::open(&path);
+    let _ = std::fs::remove_file(&path);
+
+    match image {
+        Ok(image) => Ok(image.to_rgba8()),
+        Err(e) => Err(format!(
+            "Failed to capture screen: {} {}",
+            e,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )),
+    }
+}
+
+fn windows_monitors() -> Result<Vec<Monitor>, String> {
+    let output = std::process::Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            "Add-Type -AssemblyName System.Windows.Forms; \
+             [System.Windows.Forms.Screen]::AllScreens | ForEach-Object { \
+             \"$($_.DeviceName)|$($_.Primary)|$($_.Bounds.X)|$($_.Bounds.Y)|$($_.Bounds.Width)|$($_.Bounds.Height)\" }",
+        ])
+        .output()
+        .map_err(|e| format!("Failed to list monitors: {}", e))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let monitors = stdout
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.trim().split('|').collect();
+            let [name, primary, x, y, width, height] = fields[..] else {
+                return None;
+            };
+            Some((
+                name.to_string(),
+                primary.eq_ignore_ascii_case("true"),
+                x.parse().ok()?,
+                y.parse().ok()?,
+                width.parse().ok()?,
+                height.parse().ok()?,
+            ))
+        })
+        .enumerate()
+        .map(|(index, (name, primary, x, y, width, height))| Monitor {
+            index,
+            name,
+            x,
+            y,
+            width,
+            height,
+            primary,
+        })
+        .collect();
+
+    Ok(monitors)
+}
+
+/// Scale an image down to `width` (keeping the aspect ratio) and encode it
+pub fn encode(
+    image: RgbaImage,
+    width: Option<u32>,
+    format: ImageFormat,
+    quality: u8,
+) -> Result<Vec<u8>, String> {
+    let mut image = DynamicImage::ImageRgba8(image);
+
+    if let Some(width) = width.filter(|width| *width > 0 && *width < image.width()) {
+        let height = (image.height() as u64 * width as u64 / image.width() as u64).max(1) as u32;
+        image = image.resize_exact(width, height, FilterType::Triangle);
+    }
+
+    let mut buffer = Cursor::new(Vec::new());
+    match format {
+        ImageFormat::Png => image
+            .write_to(&mut buffer, EncodedFormat::Png)
+            .map_err(|e| format!("Failed to encode PNG: {}", e))?,
+        ImageFormat::Jpeg => image
+            .to_rgb8()
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut buffer, quality.clamp(1, 100)))
+            .map_err(|e| format!("Failed to encode JPEG: {}", e))?,
+    }
+
+    Ok(buffer.into_inner())
+}
+
+/// Capture a monitor and return it encoded as PNG or JPEG
+pub fn screenshot(
+    monitor: usize,
+    width: Option<u32>,
+    format: ImageFormat,
+    quality: u8,
+) -> Result<Vec<u8>, String> {
+    encode(capture(monitor)?, width, format, quality)
+}
+
+/// X11 capture, which also covers headless servers such as Xvfb (`DISPLAY=:99`)
+#[cfg(target_os = "linux")]
+mod x11 {
+    use super::Monitor;
+    use image::RgbaImage;
+    use x11rb::connection::Connection;
+    use x11rb::protocol::randr::ConnectionExt as _;
+    use x11rb::protocol::xproto::{ConnectionExt as _, ImageFormat, ImageOrder};
+
+    pub fn monitors() -> Result<Vec<Monitor>, String> {
+        let (conn, screen_num) =
+            x11rb::connect(None).map_err(|e| format!("Failed to connect to X server: {}", e))?;
+        let screen = &conn.setup().roots[screen_num];
+
+        let randr_monitors = conn
+            .randr_get_monitors(screen.root, true)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .map(|reply| reply.monitors)
+            .unwrap_or_default();
+
+        if randr_monitors.is_empty() {
+            // No RandR (e.g. a bare Xvfb): treat the whole screen as one monitor
+            return Ok(vec![Monitor {
+                index: 0,
+                name: format!("Screen {}", screen_num),
+                x: 0,
+                y: 0,
+                width: screen.width_in_pixels as u32,
+                height: screen.height_in_pixels as u32,
+                primary: true,
+            }]);
+        }
+
+        Ok(randr_monitors
+            .into_iter()
+            .enumerate()
+            .map(|(index, monitor)| {
+                let name = conn
+                    .get_atom_name(monitor.name)
+                    .ok()
+                    .and_then(|cookie| cookie.reply().ok())
+                    .map(|reply| String::from_utf8_lossy(&reply.name).into_owned())
+                    .unwrap_or_else(|| format!("Monitor {}", index));
+
+                Monitor {
+                    index,
+                    name,
+                    x: monitor.x as i32,
+                    y: monitor.y as i32,
+                    width: monitor.width as u32,
+                    height: monitor.height as u32,
+                    primary: monitor.primary,
+                }
+            })
+            .collect())
+    }
+
+    pub fn capture(monitor: usize) -> Result<RgbaImage, String> {
+        let target = monitors()?
+            .into_iter()
+            .find(|m| m.index == monitor)
+            .ok_or_else(|| format!("Monitor {} not found", monitor))?;
+
+        let (conn, screen_num) =
+            x11rb::connect(None).map_err(|e| format!("Failed to connect to X server: {}", e))?;
+        let setup = conn.setup();
+        let screen = &setup.roots[screen_num];
+
+        let reply = conn
+            .get_image(
+                ImageFormat::Z_PIXMAP,
+                screen.root,
+                target.x as i16,
+                target.y as i16,
+                target.width as u16,
+                target.height as u16,
+                !0,
+            )
+            .map_err(|e| format!("Failed to capture screen: {}", e))?
+            .reply()
+            .map_err(|e| format!("Failed to capture screen: {}", e))?;
+
+        let format = setup
+            .pixmap_formats
+            .iter()
+            .find(|format| format.depth == reply.depth)
+            .ok_or_else(|| format!("Unsupported pixmap depth {}", reply.depth))?;
+        let visual = screen
+            .allowed_depths
+            .iter()
+            .flat_map(|depth| depth.visuals.iter())
+            .find(|visual| visual.visual_id == reply.visual)
+            .ok_or("Unknown visual")?;
+
+        let bytes_per_pixel = (format.bits_per_pixel / 8) as usize;
+        if !(2..=4).contains(&bytes_per_pixel) {
+            return Err(format!("Unsupported pixel size of {} bits", format.bits_per_pixel));
+        }
+
+        let pad = format.scanline_pad as usize;
+        let stride = (target.width as usize * format.bits_per_pixel as usize).div_ceil(pad) * pad / 8;
+        let big_endian = setup.image_byte_order == ImageOrder::MSB_FIRST;
+
+        let channel = |pixel: u32, mask: u32| -> u8 {
+            if mask == 0 {
+                return 0;
+            }
+            let value = (pixel & mask) >> mask.trailing_zeros();
+            let max = mask >> mask.trailing_zeros();
+            (value * 255 / max) as u8
+        };
+
+        let mut image = RgbaImage::new(target.width, target.height);
+        for (y, row) in reply.data.chunks(stride).take(target.height as usize).enumerate() {
+            for x in 0..target.width as usize {
+                let Some(bytes) = row.get(x * bytes_per_pixel..(x + 1) * bytes_per_pixel) else {
+                    break;
+                };
+                let pixel = if big_endian {
+                    bytes.iter().fold(0u32, |acc, byte| (acc << 8) | *byte as u32)
+                } else {
+                    bytes.iter().rev().fold(0u32, |acc, byte| (acc << 8) | *byte as u32)
+                };
+
+                image.put_pixel(
+                    x as u32,
+                    y as u32,
+                    image::Rgba([
+                        channel(pixel, visual.red_mask),
+                        channel(pixel, visual.green_mask),
+                        channel(pixel, visual.blue_mask),
+                        255,
+                    ]),
+                );
+            }
+        }
+
+        Ok(image)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gradient(width: u32, height: u32) -> RgbaImage {
+        RgbaImage::from_fn(width, height, |x, y| {
+            image::Rgba([(x % 256) as u8, (y % 256) as u8, 128, 255])
+        })
+    }
+
+    #[test]
+    fn encodes_png_and_jpeg() {
+        let png = encode(gradient(64, 48), None, ImageFormat::Png, 80).unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+        let decoded = image::load_from_memory(&png).unwrap().to_rgba8();
+        assert_eq!(decoded, gradient(64, 48));
+
+        let jpeg = encode(gradient(64, 48), None, ImageFormat::Jpeg, 0).unwrap();
+        assert!(jpeg.starts_with(&[0xff, 0xd8]));
+    }
+
+    #[test]
+    fn scales_down_keeping_the_aspect_ratio() {
+        let scaled = encode(gradient(640, 480), Some(160), ImageFormat::Png, 80).unwrap();
+        let scaled = image::load_from_memory(&scaled).unwrap();
+        assert_eq!((scaled.width(), scaled.height()), (160, 120));
+
+        // Never scales up, and ignores a zero width
+        for width in [Some(0), Some(1000), None] {
+            let image = encode(gradient(64, 48), width, ImageFormat::Png, 80).unwrap();
+            let image = image::load_from_memory(&image).unwrap();
+            assert_eq!((image.width(), image.height()), (64, 48));
+        }
+    }
+
+    /// An Xvfb server on an unused display, stopped when dropped
+    #[cfg(target_os = "linux")]
+    struct Xvfb(std::process::Child);
+
+    #[cfg(target_os = "linux")]
+    impl Xvfb {
+        fn start(width: u32, height: u32) -> Option<(Xvfb, String)> {
+            use std::path::Path;
+            use std::process::{Command, Stdio};
+            use std::time::{Duration, Instant};
+
+            let number = (90..200).find(|n| {
+                !Path::new(&format!("/tmp/.X{}-lock", n)).exists()
+                    && !Path::new(&format!("/tmp/.X11-unix/X{}", n)).exists()
+            })?;
+            let display = format!(":{}", number);
+            let child = Command::new("Xvfb")
+                .args([
+                    &display,
+                    "-screen",
+                    "0",
+                    &format!("{}x{}x24", width, height),
+                ])
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()
+                .ok()?;
+            let server = Xvfb(child);
+
+            let socket = format!("/tmp/.X11-unix/X{}", number);
+            let started = Instant::now();
+            while !Path::new(&socket).exists() {
+                if started.elapsed() > Duration::from_secs(10) {
+                    return None;
+                }
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            Some((server, display))
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    impl Drop for Xvfb {
+        fn drop(&mut self) {
+            let _ = self.0.kill();
+            let _ = self.0.wait();
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    #[ignore = "needs Xvfb; run with `cargo test -- --ignored`"]
+    fn captures_a_headless_x_server() {
+        let (_server, display) = Xvfb::start(320, 240).expect("Xvfb failed to start");
+        // Only this test talks to an X server
+        std::env::set_var("DISPLAY", &display);
+
+        let monitors = monitors().unwrap();
+        assert_eq!(monitors.len(), 1);
+        assert_eq!((monitors[0].width, monitors[0].height), (320, 240));
+
+        let image = capture(0).unwrap();
+        assert_eq!((image.width(), image.height()), (320, 240));
+        // A fresh Xvfb screen is black
+        assert_eq!(*image.get_pixel(10, 10), image::Rgba([0, 0, 0, 255]));
+
+        let jpeg = screenshot(0, Some(160), ImageFormat::Jpeg, 70).unwrap();
+        let jpeg = image::load_from_memory(&jpeg).unwrap();
+        assert_eq!((jpeg.width(), jpeg.height()), (160, 120));
+
+        assert_eq!(capture(1), Err("Monitor 1 not found".to_string()));
+    }
+}
@@ -0,0 +1,248 @@
+use crate::screen::{self, ImageFormat};
+use futures_util::stream::{self, Stream};
+use image::imageops::{self, FilterType};
+use image::RgbaImage;
+use std::time::Duration;
+use tracing::warn;
+
+/// Multipart boundary used between frames
+pub const BOUNDARY: &str = "frame";
+
+/// Highest frame rate a client may request
+pub const MAX_FPS: u32 = 10;
+
+/// Size of the tiles compared to find the changed part of a frame
+const TILE_SIZE: u32 = 32;
+
+#[derive(Clone, Copy)]
+pub struct StreamOptions {
+    pub monitor: usize,
+    pub width: Option<u32>,
+    pub fps: u32,
+    pub quality: u8,
+    /// Send only the changed region of each frame instead of the whole frame
+    pub regions: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Region {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+/// Stream screen frames as `multipart/x-mixed-replace` parts.
+///
+/// Frames that did not change are skipped. In region mode every part after
+/// the first only contains the bounding box of the changed tiles, described by
+/// the `X-Region` header (`x,y,width,height`) and the `X-Frame-Size` header
+/// (`width,height`) of the full frame.
+pub fn frames(options: StreamOptions) -> impl Stream<Item = Vec<u8>> {
+    let interval = Duration::from_millis(1000 / options.fps.clamp(1, MAX_FPS) as u64);
+
+    stream::unfold(None::<RgbaImage>, move |mut previous| async move {
+        loop {
+            if previous.is_some() {
+                tokio::time::sleep(interval).await;
+            }
+
+            let captured =
+                tokio::task::spawn_blocking(move || grab(options.monitor, options.width)).await;
+            let frame = match captured {
+                Ok(Ok(frame)) => frame,
+                Ok(Err(e)) => {
+                    warn!("Screen stream stopped: {}", e);
+                    return None;
+                }
+                Err(e) => {
+                    warn!("Screen stream stopped: {}", e);
+                    return None;
+                }
+            };
+
+            let full = Region {
+                x: 0,
+                y: 0,
+                width: frame.width(),
+                height: frame.height(),
+            };
+            let region = match &previous {
+                None => Some(full),
+                Some(previous) => changed_region(previous, &frame).map(|changed| {
+                    if options.regions {
+                        changed
+                    } else {
+                        full
+                    }
+                }),
+            };
+
+            if let Some(region) = region {
+                match part(&frame, region, options.quality) {
+                    Ok(part) => return Some((part, Some(frame))),
+                    Err(e) => {
+                        warn!("Screen stream stopped: {}", e);
+                        return None;
+                    }
+                }
+            }
+
+            previous = Some(frame);
+        }
+    })
+}
+
+fn grab(monitor: usize, width: Option<u32>) -> Result<RgbaImage, String> {
+    let image = screen::capture(monitor)?;
+
+    match width.filter(|width| *width > 0 && *width < image.width()) {
+        Some(width) => {
+            let height =
+                (image.height() as u64 * width as u64 / image.width() as u64).max(1) as u32;
+            Ok(imageops::resize(
+                &image,
+                width,
+                height,
+                FilterType::Triangle,
+            ))
+        }
+        None => Ok(image),
+    }
+}
+
+/// Bounding box of the tiles that differ between two frames
+fn changed_region(previous: &RgbaImage, current: &RgbaImage) -> Option<Region> {
+    let (width, height) = current.dimensions();
+    if previous.dimensions() != (width, height) {
+        return Some(Region {
+            x: 0,
+            y: 0,
+            width,
+            height,
+        });
+    }
+
+    let row_bytes = width as usize * 4;
+    let (old, new) = (previous.as_raw(), current.as_raw());
+    let mut bounds: Option<(u32, u32, u32, u32)> = None;
+
+    for tile_y in (0..height).step_by(TILE_SIZE as usize) {
+        for tile_x in (0..width).step_by(TILE_SIZE as usize) {
+            let tile_right = (tile_x + TILE_SIZE).min(width);
+            let tile_bottom = (tile_y + TILE_SIZE).min(height);
+
+            let changed = (tile_y..tile_bottom).any(|y| {
+                let start = y as usize * row_bytes + tile_x as usize * 4;
+                let end = y as usize * row_bytes + tile_right as usize * 4;
+                old[start..end] != new[start..end]
+            });
+
+            if changed {
+                bounds = Some(match bounds {
+                    None => (tile_x, tile_y, tile_right, tile_bottom),
+                    Some((left, top, right, bottom)) => (
+                        left.min(tile_x),
+                        top.min(tile_y),
+                        right.max(tile_right),
+                        bottom.max(tile_bottom),
+                    ),
+                });
+            }
+        }
+    }
+
+    bounds.map(|(left, top, right, bottom)| Region {
+        x: left,
+        y: top,
+        width: right - left,
+        height: bottom - top,
+    })
+}
+
+fn part(frame: &RgbaImage, region: Region, quality: u8) -> Result<Vec<u8>, String> {
+    let pixels =
+        imageops::crop_imm(frame, region.x, region.y, region.width, region.height).to_image();
+    let jpeg = screen::encode(pixels, None, ImageFormat::Jpeg, quality)?;
+
+    let mut part = format!(
+        "--{}\r\nContent-Type: image/jpeg\r\nContent-Length: {}\r\nX-Region: {},{},{},{}\r\nX-Frame-Size: {},{}\r\n\r\n",
+        BOUNDARY,
+        jpeg.len(),
+        region.x,
+        region.y,
+        region.width,
+        region.height,
+        frame.width(),
+        frame.height()
+    )
+    .into_bytes();
+    part.extend_from_slice(&jpeg);
+    part.extend_from_slice(b"\r\n");
+
+    Ok(part)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    fn frame(width: u32, height: u32) -> RgbaImage {
+        RgbaImage::from_pixel(width, height, Rgba([10, 20, 30, 255]))
+    }
+
+    fn region(x: u32, y: u32, width: u32, height: u32) -> Option<Region> {
+        Some(Region {
+            x,
+            y,
+            width,
+            height,
+        })
+    }
+
+    #[test]
+    fn identical_frames_have_no_changed_region() {
+        assert_eq!(changed_region(&frame(128, 96), &frame(128, 96)), None);
+    }
+
+    #[test]
+    fn reports_the_tile_around_a_changed_pixel() {
+        let previous = frame(128, 96);
+        let mut current = previous.clone();
+        current.put_pixel(40, 70, Rgba([255, 0, 0, 255]));
+
+        assert_eq!(changed_region(&previous, &current), region(32, 64, 32, 32));
+    }
+
+    #[test]
+    fn bounds_every_changed_tile() {
+        let previous = frame(128, 96);
+        let mut current = previous.clone();
+        current.put_pixel(5, 40, Rgba([255, 0, 0, 255]));
+        current.put_pixel(100, 10, Rgba([0, 255, 0, 255]));
+
+        assert_eq!(changed_region(&previous, &current), region(0, 0, 128, 64));
+    }
+
+    #[test]
+    fn clips_edge_tiles_to_the_frame() {
+        // Neither dimension is a multiple of the tile size
+        let previous = frame(100, 70);
+        let mut current = previous.clone();
+        current.put_pixel(99, 69, Rgba([255, 0, 0, 255]));
+        assert_eq!(changed_region(&previous, &current), region(96, 64, 4, 6));
+
+        let mut current = previous.clone();
+        current.put_pixel(0, 69, Rgba([255, 0, 0, 255]));
+        assert_eq!(changed_region(&previous, &current), region(0, 64, 32, 6));
+    }
+
+    #[test]
+    fn a_size_change_resends_the_whole_frame() {
+        assert_eq!(
+            changed_region(&frame(128, 96), &frame(100, 70)),
+            region(0, 0, 100, 70)
+        );
+    }
+}
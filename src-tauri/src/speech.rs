@@ -0,0 +1,228 @@
+use crate::{config, metrics, runs};
+use serde::Deserialize;
+use std::process::Command;
+use std::sync::Mutex;
+use tracing::info;
+
+/// Longest message that will be spoken, in characters
+const MAX_TEXT_LEN: usize = 1000;
+
+/// Default speaking rate of espeak and `say`, in words per minute
+const DEFAULT_WPM: i32 = 175;
+
+/// Messages kept by the mock backend
+const MOCK_HISTORY_LIMIT: usize = 50;
+
+#[derive(Clone, Default, Deserialize)]
+pub struct SpeakOptions {
+    /// Backend-specific voice name, e.g. `Microsoft Zira Desktop`, `en-us` or `Samantha`
+    pub voice: Option<String>,
+    /// Relative speed from -100 (slowest) to 100 (fastest), 0 being the normal rate
+    pub rate: Option<i32>,
+}
+
+#[derive(Clone, Copy)]
+enum Backend {
+    Sapi,
+    SpeechDispatcher,
+    Espeak,
+    Say,
+    Mock,
+}
+
+static MOCK_SPOKEN: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Keep text starting with `-` from being parsed as an option
+fn as_argument(text: &str) -> String {
+    if text.starts_with('-') {
+        format!(" {}", text)
+    } else {
+        text.to_string()
+    }
+}
+
+fn on_path(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+        .unwrap_or(false)
+}
+
+/// Pick the backend from the `tts_backend` setting, or the platform default for `auto`
+fn backend() -> Result<Backend, String> {
+    match config::get().tts_backend.as_str() {
+        "mock" => Ok(Backend::Mock),
+        "sapi" => Ok(Backend::Sapi),
+        "speech-dispatcher" => Ok(Backend::SpeechDispatcher),
+        "espeak" => Ok(Backend::Espeak),
+        "say" => Ok(Backend::Say),
+        "auto" | "" => {
+            if cfg!(target_os = "windows") {
+                Ok(Backend::Sapi)
+            } else if cfg!(target_os = "macos") {
+                Ok(Backend::Say)
+            } else if cfg!(target_os = "linux") {
+                if on_path("spd-say") {
+                    Ok(Backend::SpeechDispatcher)
+                } else if on_path("espeak-ng") || on_path("espeak") {
+                    Ok(Backend::Espeak)
+                } else {
+                    Err(
+                        "No speech engine found, install speech-dispatcher or espeak-ng"
+                            .to_string(),
+                    )
+                }
+            } else {
+                Err("Unsupported operating system".to_string())
+            }
+        }
+        other => Err(format!("Unknown TTS backend {}", other)),
+    }
+}
+
+/// Speak `text` through the PC speakers.
+///
+/// Real backends run as a tracked run (see `runs`), so speech can be followed or
+/// stopped through `/api/runs`; the returned id is `None` for the mock backend.
+pub fn speak(text: &str, options: &SpeakOptions) -> Result<Option<u64>, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("Nothing to say".to_string());
+    }
+    if text.chars().count() > MAX_TEXT_LEN {
+        return Err(format!("Message is limited to {} characters", MAX_TEXT_LEN));
+    }
+
+    let rate = options.rate.unwrap_or(0).clamp(-100, 100);
+    let voice = options.voice.as_deref().filter(|voice| !voice.is_empty());
+    let wpm = (DEFAULT_WPM * (100 + rate) / 100)
+        .clamp(80, 450)
+        .to_string();
+
+    let mut command = match backend()? {
+        Backend::Mock => {
+            info!("[mock tts] {}", text);
+            metrics::record_action("speak", true);
+            let mut spoken = MOCK_SPOKEN.lock().unwrap();
+            spoken.push(text.to_string());
+            if spoken.len() > MOCK_HISTORY_LIMIT {
+                spoken.remove(0);
+            }
+            return Ok(None);
+        }
+        Backend::Sapi => {
+            // Text and voice go through the environment so they never need escaping
+            let mut command = Command::new("powershell");
+            command
+                .args([
+                    "-NoProfile",
+                    "-Command",
+                    &format!(
+                        "Add-Type -AssemblyName System.Speech; \
+                         $speech = New-Object System.Speech.Synthesis.SpeechSynthesizer; \
+                         if ($env:FERROUS_TTS_VOICE) {{ $speech.SelectVoice($env:FERROUS_TTS_VOICE) }}; \
+                         $speech.Rate = {}; \
+                         $speech.Speak($env:FERROUS_TTS_TEXT)",
+                        rate / 10
+                    ),
+                ])
+                .env("FERROUS_TTS_TEXT", text)
+                .env("FERROUS_TTS_VOICE", voice.unwrap_or_default());
+            command
+        }
+        Backend::SpeechDispatcher => {
+            let mut command = Command::new("spd-say");
+            // --wait keeps the run alive until speech finishes, so it can be stopped
+            command.args(["--wait", "--rate", &rate.to_string()]);
+            if let Some(voice) = voice {
+                command.args(["--synthesis-voice", voice]);
+            }
+            command.arg(as_argument(text));
+            command
+        }
+        Backend::Espeak => {
+            let program = if on_path("espeak-ng") {
+                "espeak-ng"
+            } else {
+                "espeak"
+            };
+            let mut command = Command::new(program);
+            command.args(["-s", &wpm]);
+            if let Some(voice) = voice {
+                command.args(["-v", voice]);
+            }
+            command.arg(as_argument(text));
+            command
+        }
+        Backend::Say => {
+            let mut command = Command::new("say");
+            command.args(["-r", &wpm]);
+            if let Some(voice) = voice {
+                command.args(["-v", voice]);
+            }
+            command.arg(as_argument(text));
+            command
+        }
+    };
+
+    runs::spawn("speak", &mut command)
+        .map(Some)
+        .map_err(|e| format!("Failed to start speech: {}", e))
+}
+
+/// Messages "spoken" by the mock backend, oldest first
+pub fn mock_spoken() -> Vec<String> {
+    MOCK_SPOKEN.lock().unwrap().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn use_mock_backend() {
+        config::use_test_dir();
+        config::update(|config| config.tts_backend = "mock".to_string()).unwrap();
+    }
+
+    #[test]
+    fn rejects_empty_text() {
+        let options = SpeakOptions::default();
+        assert_eq!(speak("", &options), Err("Nothing to say".to_string()));
+        assert_eq!(
+            speak("  \n\t ", &options),
+            Err("Nothing to say".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_text_over_the_limit() {
+        use_mock_backend();
+        let options = SpeakOptions::default();
+
+        let too_long = "a".repeat(MAX_TEXT_LEN + 1);
+        assert_eq!(
+            speak(&too_long, &options),
+            Err(format!("Message is limited to {} characters", MAX_TEXT_LEN))
+        );
+        // The limit counts characters, not bytes
+        assert_eq!(speak(&"é".repeat(MAX_TEXT_LEN), &options), Ok(None));
+    }
+
+    #[test]
+    fn mock_records_trimmed_messages_and_caps_history() {
+        use_mock_backend();
+        let options = SpeakOptions {
+            voice: Some("en-us".to_string()),
+            rate: Some(500),
+        };
+
+        assert_eq!(speak("  Dinner is ready  ", &options), Ok(None));
+        assert!(mock_spoken().contains(&"Dinner is ready".to_string()));
+
+        for i in 0..MOCK_HISTORY_LIMIT + 5 {
+            speak(&format!("message {}", i), &options).unwrap();
+        }
+        let spoken = mock_spoken();
+        assert!(spoken.len() <= MOCK_HISTORY_LIMIT);
+        assert!(spoken.contains(&format!("message {}", MOCK_HISTORY_LIMIT + 4)));
+    }
+}
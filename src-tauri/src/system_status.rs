@@ -0,0 +1,684 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+#[derive(Clone, Serialize)]
+pub struct SystemStatus {
+    pub hostname: String,
+    pub os: String,
+    pub kernel: Option<String>,
+    pub uptime_seconds: u64,
+    pub cpu: CpuStatus,
+    pub memory: MemoryStatus,
+    pub disks: Vec<DiskStatus>,
+    pub battery: Option<BatteryStatus>,
+    pub temperatures: Vec<TemperatureSensor>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct CpuStatus {
+    pub cores: usize,
+    pub usage_percent: f32,
+    pub load_average: [f64; 3],
+}
+
+#[derive(Clone, Default, Serialize)]
+pub struct MemoryStatus {
+    pub total: u64,
+    pub available: u64,
+    pub used: u64,
+    pub swap_total: u64,
+    pub swap_used: u64,
+}
+
+#[derive(Clone, Serialize)]
+pub struct DiskStatus {
+    pub device: String,
+    pub mount_point: String,
+    pub file_system: String,
+    pub total: u64,
+    pub available: u64,
+    pub used: u64,
+}
+
+#[derive(Clone, Serialize)]
+pub struct BatteryStatus {
+    pub level: u8,
+    pub charging: bool,
+    pub status: String,
+}
+
+#[derive(Clone, Serialize)]
+pub struct TemperatureSensor {
+    pub name: String,
+    pub celsius: f32,
+}
+
+/// Get the status of this machine
+pub fn status() -> SystemStatus {
+    if cfg!(target_os = "linux") {
+        read_status(Path::new("/"))
+    } else {
+        sysinfo_status(None)
+    }
+}
+
+/// Get the status of this machine with the CPU usage measured by `cpu`, so
+/// that polling it does not move the baseline [`status`] measures from
+pub fn sampled_status(cpu: &mut CpuSampler) -> SystemStatus {
+    let usage = cpu.usage();
+    if cfg!(target_os = "linux") {
+        read_status_with(Path::new("/"), usage)
+    } else {
+        sysinfo_status(Some(usage))
+    }
+}
+
+/// Read the status from the `/proc` and `/sys` trees below `root`.
+///
+/// Passing a fixture directory instead of `/` allows reading a fake system.
+pub fn read_status(root: &Path) -> SystemStatus {
+    read_status_with(root, read_cpu_usage(root))
+}
+
+fn read_status_with(root: &Path, cpu_usage: f32) -> SystemStatus {
+    let hostname = read_trimmed(root, "proc/sys/kernel/hostname")
+        .or_else(|| read_trimmed(root, "etc/hostname"))
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let os = read_os_name(root).unwrap_or_else(|| "Linux".to_string());
+
+    let uptime_seconds = read_trimmed(root, "proc/uptime")
+        .and_then(|uptime| {
+            uptime
+                .split_whitespace()
+                .next()
+                .and_then(|secs| secs.parse::<f64>().ok())
+        })
+        .map(|secs| secs as u64)
+        .unwrap_or(0);
+
+    SystemStatus {
+        hostname,
+        os,
+        kernel: read_trimmed(root, "proc/sys/kernel/osrelease"),
+        uptime_seconds,
+        cpu: read_cpu(root, cpu_usage),
+        memory: read_memory(root),
+        disks: read_disks(root),
+        battery: read_battery(root),
+        temperatures: read_temperatures(root),
+    }
+}
+
+fn read_trimmed(root: &Path, path: &str) -> Option<String> {
+    fs::read_to_string(root.join(path))
+        .ok()
+        .map(|contents| contents.trim().to_string())
+        .filter(|contents| !contents.is_empty())
+}
+
+fn read_os_name(root: &Path) -> Option<String> {
+    let os_release = read_trimmed(root, "etc/os-release")
+        .or_else(|| read_trimmed(root, "usr/lib/os-release"))?;
+
+    os_release.lines().find_map(|line| {
+        line.strip_prefix("PRETTY_NAME=")
+            .map(|name| name.trim_matches('"').to_string())
+    })
+}
+
+fn read_cpu(root: &Path, usage_percent: f32) -> CpuStatus {
+    let cores = read_trimmed(root, "proc/cpuinfo")
+        .map(|cpuinfo| {
+            cpuinfo
+                .lines()
+                .filter(|line| line.starts_with("processor"))
+                .count()
+        })
+        .unwrap_or(0);
+
+    let mut load_average = [0.0; 3];
+    if let Some(loadavg) = read_trimmed(root, "proc/loadavg") {
+        for (slot, value) in load_average.iter_mut().zip(loadavg.split_whitespace()) {
+            *slot = value.parse().unwrap_or(0.0);
+        }
+    }
+
+    CpuStatus {
+        cores,
+        usage_percent,
+        load_average,
+    }
+}
+
+/// CPU usage since the previous call for the same root (or since boot on the first call)
+fn read_cpu_usage(root: &Path) -> f32 {
+    static PREVIOUS: OnceLock<Mutex<HashMap<PathBuf, (u64, u64)>>> = OnceLock::new();
+
+    let Some(times) = read_cpu_times(root) else {
+        return 0.0;
+    };
+    let mut previous = PREVIOUS.get_or_init(Default::default).lock().unwrap();
+    let before = previous.insert(root.to_path_buf(), times).unwrap_or((0, 0));
+    usage_between(before, times)
+}
+
+/// Total and idle jiffies from the aggregate line of `/proc/stat`
+fn read_cpu_times(root: &Path) -> Option<(u64, u64)> {
+    let stat = read_trimmed(root, "proc/stat")?;
+    let line = stat.lines().find(|line| line.starts_with("cpu "))?;
+
+    // user nice system idle iowait irq softirq steal
+    let fields: Vec<u64> = line
+        .split_whitespace()
+        .skip(1)
+        .take(8)
+        .map(|value| value.parse().unwrap_or(0))
+        .collect();
+    let total: u64 = fields.iter().sum();
+    let idle = fields.get(3).copied().unwrap_or(0) + fields.get(4).copied().unwrap_or(0);
+    Some((total, idle))
+}
+
+fn usage_between((prev_total, prev_idle): (u64, u64), (total, idle): (u64, u64)) -> f32 {
+    let total_delta = total.saturating_sub(prev_total);
+    let idle_delta = idle.saturating_sub(prev_idle);
+    if total_delta == 0 {
+        return 0.0;
+    }
+
+    (total_delta - idle_delta.min(total_delta)) as f32 * 100.0 / total_delta as f32
+}
+
+/// Measures total CPU usage on its own, without reading the rest of the status.
+///
+/// Each sampler keeps its own baseline, so polling one does not skew the usage
+/// the status page reports.
+#[derive(Default)]
+pub struct CpuSampler {
+    previous: (u64, u64),
+    system: Option<sysinfo::System>,
+}
+
+impl CpuSampler {
+    /// Usage since the previous call (or since boot on the first call on Linux)
+    pub fn usage(&mut self) -> f32 {
+        if cfg!(target_os = "linux") {
+            self.usage_at(Path::new("/"))
+        } else {
+            let system = self.system.get_or_insert_with(sysinfo::System::new);
+            system.refresh_cpu_usage();
+            system.global_cpu_usage()
+        }
+    }
+
+    /// Usage from the `/proc/stat` below `root`, see [`read_status`]
+    fn usage_at(&mut self, root: &Path) -> f32 {
+        let Some(times) = read_cpu_times(root) else {
+            return 0.0;
+        };
+        usage_between(std::mem::replace(&mut self.previous, times), times)
+    }
+}
+
+fn read_memory(root: &Path) -> MemoryStatus {
+    let Some(meminfo) = read_trimmed(root, "proc/meminfo") else {
+        return MemoryStatus::default();
+    };
+
+    // Values in /proc/meminfo are in kB
+    let values: HashMap<&str, u64> = meminfo
+        .lines()
+        .filter_map(|line| {
+            let (key, rest) = line.split_once(':')?;
+            let kb = rest.split_whitespace().next()?.parse::<u64>().ok()?;
+            Some((key, kb * 1024))
+        })
+        .collect();
+
+    let total = values.get("MemTotal").copied().unwrap_or(0);
+    let available = values
+        .get("MemAvailable")
+        .or_else(|| values.get("MemFree"))
+        .copied()
+        .unwrap_or(0);
+    let swap_total = values.get("SwapTotal").copied().unwrap_or(0);
+    let swap_free = values.get("SwapFree").copied().unwrap_or(0);
+
+    MemoryStatus {
+        total,
+        available,
+        used: total.saturating_sub(available),
+        swap_total,
+        swap_used: swap_total.saturating_sub(swap_free),
+    }
+}
+
+fn read_disks(root: &Path) -> Vec<DiskStatus> {
+    let Some(mounts) = read_trimmed(root, "proc/mounts") else {
+        return Vec::new();
+    };
+
+    let mut disks: Vec<DiskStatus> = Vec::new();
+    for line in mounts.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [device, mount_point, file_system, ..] = fields[..] else {
+            continue;
+        };
+
+        // Only report block devices, not tmpfs, proc, cgroups and friends
+        if !device.starts_with("/dev/") || file_system == "squashfs" {
+            continue;
+        }
+
+        // Spaces and other special characters are octal-escaped in /proc/mounts
+        let mount_point = mount_point.replace("\\040", " ").replace("\\011", "\t");
+        if disks.iter().any(|disk| disk.mount_point == mount_point) {
+            continue;
+        }
+
+        let path = root.join(mount_point.trim_start_matches('/'));
+        let Some((total, available, used)) = disk_space(&path) else {
+            continue;
+        };
+
+        disks.push(DiskStatus {
+            device: device.to_string(),
+            mount_point,
+            file_system: file_system.to_string(),
+            total,
+            available,
+            used,
+        });
+    }
+
+    disks
+}
+
+#[cfg(unix)]
+fn disk_space(path: &Path) -> Option<(u64, u64, u64)> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+
+    let block_size = stat.f_frsize as u64;
+    let total = stat.f_blocks as u64 * block_size;
+    let available = stat.f_bavail as u64 * block_size;
+    let free = stat.f_bfree as u64 * block_size;
+    Some((total, available, total.saturating_sub(free)))
+}
+
+#[cfg(not(unix))]
+fn disk_space(_path: &Path) -> Option<(u64, u64, u64)> {
+    None
+}
+
+fn read_battery(root: &Path) -> Option<BatteryStatus> {
+    let entries = fs::read_dir(root.join("sys/class/power_supply")).ok()?;
+
+    for entry in entries.flatten() {
+        let supply = entry.path();
+        let is_battery = fs::read_to_string(supply.join("type"))
+            .map(|kind| kind.trim() == "Battery")
+            .unwrap_or(false);
+        if !is_battery {
+            continue;
+        }
+
+        let Some(level) = fs::read_to_string(supply.join("capacity"))
+            .ok()
+            .and_then(|capacity| capacity.trim().parse::<u8>().ok())
+        else {
+            continue;
+        };
+        let status = fs::read_to_string(supply.join("status"))
+            .map(|status| status.trim().to_string())
+            .unwrap_or_else(|_| "Unknown".to_string());
+
+        return Some(BatteryStatus {
+            level: level.min(100),
+            charging: status == "Charging",
+            status,
+        });
+    }
+
+    None
+}
+
+fn read_temperatures(root: &Path) -> Vec<TemperatureSensor> {
+    let mut sensors = Vec::new();
+
+    // Thermal zones: type + temp in millidegrees Celsius
+    if let Ok(entries) = fs::read_dir(root.join("sys/class/thermal")) {
+        let mut zones: Vec<_> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with("thermal_zone"))
+            })
+            .collect();
+        zones.sort();
+
+        for zone in zones {
+            if let Some(celsius) = read_millidegrees(&zone.join("temp")) {
+                let name = fs::read_to_string(zone.join("type"))
+                    .map(|name| name.trim().to_string())
+                    .unwrap_or_else(|_| zone.file_name().unwrap().to_string_lossy().into_owned());
+                sensors.push(TemperatureSensor { name, celsius });
+            }
+        }
+    }
+
+    // hwmon chips: name + temp*_input (+ optional temp*_label)
+    if let Ok(entries) = fs::read_dir(root.join("sys/class/hwmon")) {
+        let mut chips: Vec<_> = entries.flatten().map(|entry| entry.path()).collect();
+        chips.sort();
+
+        for chip in chips {
+            let chip_name = fs::read_to_string(chip.join("name"))
+                .map(|name| name.trim().to_string())
+                .unwrap_or_else(|_| chip.file_name().unwrap().to_string_lossy().into_owned());
+
+            let Ok(files) = fs::read_dir(&chip) else {
+                continue;
+            };
+            let mut inputs: Vec<String> = files
+                .flatten()
+                .map(|file| file.file_name().to_string_lossy().into_owned())
+                .filter(|file| file.starts_with("temp") && file.ends_with("_input"))
+                .collect();
+            inputs.sort();
+
+            for input in inputs {
+                let Some(celsius) = read_millidegrees(&chip.join(&input)) else {
+                    continue;
+                };
+                let label_file = input.replace("_input", "_label");
+                let name = match fs::read_to_string(chip.join(label_file)) {
+                    Ok(label) => format!("{} {}", chip_name, label.trim()),
+                    Err(_) => chip_name.clone(),
+                };
+                sensors.push(TemperatureSensor { name, celsius });
+            }
+        }
+    }
+
+    sensors
+}
+
+fn read_millidegrees(path: &Path) -> Option<f32> {
+    fs::read_to_string(path)
+        .ok()?
+        .trim()
+        .parse::<i64>()
+        .ok()
+        .map(|millidegrees| millidegrees as f32 / 1000.0)
+}
+
+/// Fallback for platforms without `/proc` and `/sys`
+fn sysinfo_status(cpu_usage: Option<f32>) -> SystemStatus {
+    use sysinfo::{Components, CpuRefreshKind, Disks, System};
+
+    static SYSTEM: OnceLock<Mutex<System>> = OnceLock::new();
+    let mut system = SYSTEM
+        .get_or_init(|| Mutex::new(System::new()))
+        .lock()
+        .unwrap();
+    if cpu_usage.is_none() {
+        system.refresh_cpu_usage();
+    } else if system.cpus().is_empty() {
+        system.refresh_cpu_list(CpuRefreshKind::nothing());
+    }
+    system.refresh_memory();
+
+    let load = System::load_average();
+
+    let disks = Disks::new_with_refreshed_list()
+        .iter()
+        .map(|disk| DiskStatus {
+            device: disk.name().to_string_lossy().into_owned(),
+            mount_point: disk.mount_point().to_string_lossy().into_owned(),
+            file_system: disk.file_system().to_string_lossy().into_owned(),
+            total: disk.total_space(),
+            available: disk.available_space(),
+            used: disk.total_space().saturating_sub(disk.available_space()),
+        })
+        .collect();
+
+    let temperatures = Components::new_with_refreshed_list()
+        .iter()
+        .filter_map(|component| {
+            Some(TemperatureSensor {
+                name: component.label().to_string(),
+                celsius: component.temperature()?,
+            })
+        })
+        .collect();
+
+    SystemStatus {
+        hostname: System::host_name().unwrap_or_else(|| "unknown".to_string()),
+        os: System::long_os_version().unwrap_or_else(|| std::env::consts::OS.to_string()),
+        kernel: System::kernel_version(),
+        uptime_seconds: System::uptime(),
+        cpu: CpuStatus {
+            cores: system.cpus().len(),
+            usage_percent: cpu_usage.unwrap_or_else(|| system.global_cpu_usage()),
+            load_average: [load.one, load.five, load.fifteen],
+        },
+        memory: MemoryStatus {
+            total: system.total_memory(),
+            available: system.available_memory(),
+            used: system.used_memory(),
+            swap_total: system.total_swap(),
+            swap_used: system.used_swap(),
+        },
+        disks,
+        battery: None,
+        temperatures,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, path: &str, contents: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    fn fake_system() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        write(root, "proc/sys/kernel/hostname", "desk\n");
+        write(root, "proc/sys/kernel/osrelease", "6.8.0-test\n");
+        write(
+            root,
+            "etc/os-release",
+            "NAME=Test\nPRETTY_NAME=\"Test Linux 1.0\"\n",
+        );
+        write(root, "proc/uptime", "12345.67 40000.00\n");
+        write(
+            root,
+            "proc/cpuinfo",
+            "processor\t: 0\nmodel name\t: Test\n\nprocessor\t: 1\nmodel name\t: Test\n",
+        );
+        write(root, "proc/loadavg", "0.50 0.25 0.10 1/100 1234\n");
+        write(
+            root,
+            "proc/stat",
+            "cpu  100 0 100 700 100 0 0 0 0 0\ncpu0 50 0 50 350 50 0 0 0 0 0\n",
+        );
+        write(
+            root,
+            "proc/meminfo",
+            "MemTotal:       8000 kB\nMemFree:        1000 kB\nMemAvailable:   3000 kB\n\
+             SwapTotal:      2000 kB\nSwapFree:       1500 kB\n",
+        );
+        dir
+    }
+
+    #[test]
+    fn reads_host_details() {
+        let dir = fake_system();
+        let status = read_status(dir.path());
+
+        assert_eq!(status.hostname, "desk");
+        assert_eq!(status.os, "Test Linux 1.0");
+        assert_eq!(status.kernel.as_deref(), Some("6.8.0-test"));
+        assert_eq!(status.uptime_seconds, 12345);
+    }
+
+    #[test]
+    fn reads_cpu_cores_load_and_usage() {
+        let dir = fake_system();
+        let root = dir.path();
+
+        let cpu = read_status(root).cpu;
+        assert_eq!(cpu.cores, 2);
+        assert_eq!(cpu.load_average, [0.5, 0.25, 0.1]);
+        // Since boot: 200 busy out of 1000
+        assert!((cpu.usage_percent - 20.0).abs() < 0.01);
+
+        // 150 busy and 50 idle jiffies later
+        write(root, "proc/stat", "cpu  200 0 150 750 100 0 0 0 0 0\n");
+        assert!((read_status(root).cpu.usage_percent - 75.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn cpu_sampler_keeps_its_own_baseline() {
+        let dir = fake_system();
+        let root = dir.path();
+        let mut sampler = CpuSampler::default();
+        assert_eq!(sampler.usage_at(root), 20.0);
+
+        write(root, "proc/stat", "cpu  200 0 150 750 100 0 0 0 0 0\n");
+        // Reading the status in between must not reset the sampler
+        read_status(root);
+        assert_eq!(sampler.usage_at(root), 75.0);
+
+        // No time passed
+        assert_eq!(sampler.usage_at(root), 0.0);
+    }
+
+    #[test]
+    fn reads_memory_in_bytes() {
+        let dir = fake_system();
+        let memory = read_memory(dir.path());
+
+        assert_eq!(memory.total, 8000 * 1024);
+        assert_eq!(memory.available, 3000 * 1024);
+        assert_eq!(memory.used, 5000 * 1024);
+        assert_eq!(memory.swap_total, 2000 * 1024);
+        assert_eq!(memory.swap_used, 500 * 1024);
+    }
+
+    #[test]
+    fn memory_falls_back_to_free_without_available() {
+        let dir = TempDir::new().unwrap();
+        write(
+            dir.path(),
+            "proc/meminfo",
+            "MemTotal: 4000 kB\nMemFree: 1000 kB\n",
+        );
+
+        let memory = read_memory(dir.path());
+        assert_eq!(memory.available, 1000 * 1024);
+        assert_eq!(memory.used, 3000 * 1024);
+    }
+
+    #[test]
+    fn reads_battery_and_skips_other_supplies() {
+        let dir = fake_system();
+        let root = dir.path();
+        write(root, "sys/class/power_supply/AC/type", "Mains\n");
+        write(root, "sys/class/power_supply/AC/online", "1\n");
+        write(root, "sys/class/power_supply/BAT0/type", "Battery\n");
+        write(root, "sys/class/power_supply/BAT0/capacity", "87\n");
+        write(root, "sys/class/power_supply/BAT0/status", "Charging\n");
+
+        let battery = read_battery(root).unwrap();
+        assert_eq!(battery.level, 87);
+        assert!(battery.charging);
+        assert_eq!(battery.status, "Charging");
+    }
+
+    #[test]
+    fn no_battery_without_power_supplies() {
+        let dir = fake_system();
+        assert!(read_battery(dir.path()).is_none());
+    }
+
+    #[test]
+    fn reads_thermal_zones_and_hwmon_sensors() {
+        let dir = fake_system();
+        let root = dir.path();
+        write(
+            root,
+            "sys/class/thermal/thermal_zone0/type",
+            "x86_pkg_temp\n",
+        );
+        write(root, "sys/class/thermal/thermal_zone0/temp", "45500\n");
+        write(root, "sys/class/thermal/cooling_device0/type", "Fan\n");
+        write(root, "sys/class/hwmon/hwmon0/name", "nvme\n");
+        write(root, "sys/class/hwmon/hwmon0/temp1_input", "38000\n");
+        write(root, "sys/class/hwmon/hwmon0/temp1_label", "Composite\n");
+        write(root, "sys/class/hwmon/hwmon0/temp2_input", "not a number\n");
+
+        let sensors = read_temperatures(root);
+        let readings: Vec<(&str, f32)> = sensors
+            .iter()
+            .map(|sensor| (sensor.name.as_str(), sensor.celsius))
+            .collect();
+        assert_eq!(readings, [("x86_pkg_temp", 45.5), ("nvme Composite", 38.0)]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn reads_block_device_mounts_only() {
+        let dir = fake_system();
+        let root = dir.path();
+        fs::create_dir_all(root.join("mnt/my disk")).unwrap();
+        write(
+            root,
+            "proc/mounts",
+            "/dev/sda1 / ext4 rw 0 0\n\
+             proc /proc proc rw 0 0\n\
+             tmpfs /tmp tmpfs rw 0 0\n\
+             /dev/loop0 /snap/core squashfs ro 0 0\n\
+             /dev/sdb1 /mnt/my\\040disk ext4 rw 0 0\n\
+             /dev/sda1 / ext4 rw 0 0\n",
+        );
+
+        let disks = read_disks(root);
+        let mounts: Vec<&str> = disks.iter().map(|disk| disk.mount_point.as_str()).collect();
+        assert_eq!(mounts, ["/", "/mnt/my disk"]);
+        assert_eq!(disks[0].device, "/dev/sda1");
+        assert_eq!(disks[0].file_system, "ext4");
+        assert!(disks[0].total > 0);
+    }
+
+    #[test]
+    fn missing_files_give_empty_status() {
+        let dir = TempDir::new().unwrap();
+        let status = read_status(dir.path());
+
+        assert_eq!(status.hostname, "unknown");
+        assert_eq!(status.cpu.cores, 0);
+        assert_eq!(status.memory.total, 0);
+        assert!(status.disks.is_empty());
+        assert!(status.battery.is_none());
+        assert!(status.temperatures.is_empty());
+    }
+}
@@ -0,0 +1,198 @@
+use crate::config::{self, WolMachine};
+use std::fmt;
+use std::net::{Ipv4Addr, UdpSocket};
+
+pub enum WakeError {
+    /// No saved machine has this name
+    UnknownMachine(String),
+    Failed(String),
+}
+
+impl fmt::Display for WakeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WakeError::UnknownMachine(name) => write!(f, "Unknown machine {}", name),
+            WakeError::Failed(message) => f.write_str(message),
+        }
+    }
+}
+
+/// Parse a MAC address written with `:` or `-` separators, or as 12 hex digits
+pub fn parse_mac(mac: &str) -> Result<[u8; 6], String> {
+    let digits: String = mac
+        .trim()
+        .chars()
+        .filter(|c| !matches!(c, ':' | '-' | '.'))
+        .collect();
+    if digits.len() != 12 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("Invalid MAC address {}", mac));
+    }
+
+    let mut bytes = [0u8; 6];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&digits[i * 2..i * 2 + 2], 16)
+            .map_err(|_| format!("Invalid MAC address {}", mac))?;
+    }
+    Ok(bytes)
+}
+
+/// Six 0xFF bytes followed by the MAC address repeated 16 times
+pub fn magic_packet(mac: [u8; 6]) -> [u8; 102] {
+    let mut packet = [0xFF; 102];
+    for chunk in packet[6..].chunks_mut(6) {
+        chunk.copy_from_slice(&mac);
+    }
+    packet
+}
+
+fn validate(machine: &WolMachine) -> Result<(), String> {
+    if machine.name.trim().is_empty() {
+        return Err("Machine name is required".to_string());
+    }
+    parse_mac(&machine.mac)?;
+    machine
+        .broadcast
+        .parse::<Ipv4Addr>()
+        .map_err(|_| format!("Invalid broadcast address {}", machine.broadcast))?;
+    if machine.port == 0 {
+        return Err("Port must not be 0".to_string());
+    }
+    Ok(())
+}
+
+/// Known machines, in the order they were saved
+pub fn machines() -> Vec<WolMachine> {
+    config::get().wol_machines
+}
+
+/// Validate and save the machine list
+pub fn set_machines(machines: Vec<WolMachine>) -> Result<(), String> {
+    for (i, machine) in machines.iter().enumerate() {
+        validate(machine)?;
+        if machines[..i]
+            .iter()
+            .any(|other| other.name.eq_ignore_ascii_case(&machine.name))
+        {
+            return Err(format!("Duplicate machine name {}", machine.name));
+        }
+    }
+
+    config::update(|config| config.wol_machines = machines).map(|_| ())
+}
+
+/// Send a magic packet to a known machine
+pub fn wake(name: &str) -> Result<String, WakeError> {
+    let machine = machines()
+        .into_iter()
+        .find(|machine| machine.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| WakeError::UnknownMachine(name.to_string()))?;
+    send(&machine).map_err(WakeError::Failed)
+}
+
+fn send(machine: &WolMachine) -> Result<String, String> {
+    let mac = parse_mac(&machine.mac)?;
+    let broadcast: Ipv4Addr = machine
+        .broadcast
+        .parse()
+        .map_err(|_| format!("Invalid broadcast address {}", machine.broadcast))?;
+
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .map_err(|e| format!("Failed to open socket: {}", e))?;
+    socket
+        .set_broadcast(true)
+        .map_err(|e| format!("Failed to enable broadcast: {}", e))?;
+    socket
+        .send_to(&magic_packet(mac), (broadcast, machine.port))
+        .map_err(|e| format!("Failed to send magic packet: {}", e))?;
+
+    Ok(format!(
+        "Magic packet sent to {} ({})",
+        machine.name, machine.mac
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAC: [u8; 6] = [0x00, 0x1a, 0x2b, 0x3c, 0x4d, 0x5e];
+
+    #[test]
+    fn builds_the_magic_packet() {
+        let packet = magic_packet(MAC);
+
+        assert_eq!(packet.len(), 102);
+        assert_eq!(packet[..6], [0xFF; 6]);
+        for repeat in packet[6..].chunks(6) {
+            assert_eq!(repeat, MAC);
+        }
+        assert_eq!(packet[6..].chunks(6).count(), 16);
+    }
+
+    #[test]
+    fn parses_common_mac_forms() {
+        for mac in [
+            "00:1a:2b:3c:4d:5e",
+            "00-1A-2B-3C-4D-5E",
+            "001a2b3c4d5e",
+            "001A.2B3C.4D5E",
+            "  00:1A:2b:3C:4d:5E\n",
+        ] {
+            assert_eq!(parse_mac(mac), Ok(MAC), "{:?}", mac);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_macs() {
+        for mac in [
+            "",
+            "00:1a:2b:3c:4d",
+            "00:1a:2b:3c:4d:5e:6f",
+            "0:1:2:3:4:5",
+            "00:1a:2b:3c:4d:5g",
+            "00 1a 2b 3c 4d 5e",
+            "+01a2b3c4d5e",
+            // 12 bytes, but not 12 hex digits
+            "٠٠1a2b3c4d",
+        ] {
+            assert_eq!(
+                parse_mac(mac),
+                Err(format!("Invalid MAC address {}", mac)),
+                "{:?}",
+                mac
+            );
+        }
+    }
+
+    #[test]
+    fn validates_machines() {
+        let machine = WolMachine {
+            name: "Server".to_string(),
+            mac: "00:1a:2b:3c:4d:5e".to_string(),
+            broadcast: "192.168.1.255".to_string(),
+            port: 9,
+        };
+        assert!(validate(&machine).is_ok());
+
+        for invalid in [
+            WolMachine {
+                name: " ".to_string(),
+                ..machine.clone()
+            },
+            WolMachine {
+                mac: "nope".to_string(),
+                ..machine.clone()
+            },
+            WolMachine {
+                broadcast: "ff02::1".to_string(),
+                ..machine.clone()
+            },
+            WolMachine {
+                port: 0,
+                ..machine.clone()
+            },
+        ] {
+            assert!(validate(&invalid).is_err());
+        }
+    }
+}
@@ -1,28 +1,55 @@
-import { useState, useEffect } from "react";
+import { useState, useEffect, useRef } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { QRCodeSVG } from "qrcode.react";
 import "./App.css";
+import ApiAllowlist from "./components/ApiAllowlist";
+import AppLogs from "./components/AppLogs";
+import AuditLog from "./components/AuditLog";
 import AutoStartToggle from "./components/AutoStartToggle";
+import ClientActivity from "./components/ClientActivity";
+import DeviceRoles from "./components/DeviceRoles";
+import ScheduleEditor from "./components/ScheduleEditor";
 
 function App() {
   const [status, setStatus] = useState({ message: "", type: "" });
   const [buttonsDisabled, setButtonsDisabled] = useState(false);
   const [modal, setModal] = useState({ show: false, title: "", message: "", onConfirm: null });
-  const [localIp, setLocalIp] = useState("");
+  const [interfaces, setInterfaces] = useState([]);
   const [volume, setVolume] = useState(null);
+  const [brightness, setBrightness] = useState(null);
+  const brightnessTimeout = useRef(null);
 
   useEffect(() => {
-    async function fetchLocalIp() {
+    fetchInterfaces();
+    fetchVolume();
+    fetchBrightness();
+  }, []);
+
+  useEffect(() => {
+    // Uploads from the web UI are announced by the backend
+    const unlisten = listen("file-received", (event) => {
+      setStatus({ message: `Received ${event.payload.name} in ${event.payload.path}`, type: "success" });
+    });
+    return () => {
+      unlisten.then((stop) => stop());
+    };
+  }, []);
+
+  useEffect(() => {
+    // Sent when "confirm before opening links" is enabled and a phone asks to open a URL
+    const unlisten = listen("open-url-request", async (event) => {
+      const { id, url } = event.payload;
+      const approved = await showModal("Open Link", `A device wants to open ${url}. Open it?`);
       try {
-        const ip = await invoke("get_local_ip");
-        setLocalIp(ip);
+        await invoke("respond_open_url", { id, approved });
       } catch (error) {
-        console.error("Failed to get local IP:", error);
-        setLocalIp("Unable to get IP");
+        console.error("Failed to answer open URL request:", error);
       }
-    }
-    fetchLocalIp();
-    fetchVolume();
+    });
+    return () => {
+      unlisten.then((stop) => stop());
+    };
   }, []);
 
   async function fetchVolume() {
@@ -37,6 +64,36 @@ function App() {
     }
   }
 
+  async function fetchBrightness() {
+    try {
+      const displays = await invoke("get_brightness");
+      // The slider controls all displays together and shows the first one
+      if (displays.length > 0) {
+        setBrightness(displays[0].brightness);
+      }
+    } catch (error) {
+      console.error("Failed to get brightness:", error);
+    }
+  }
+
+  function handleBrightnessChange(level) {
+    setBrightness(level);
+    // Debounce to avoid flooding slow backends such as DDC/CI
+    clearTimeout(brightnessTimeout.current);
+    brightnessTimeout.current = setTimeout(() => applyBrightness(level), 150);
+  }
+
+  async function applyBrightness(level) {
+    try {
+      const result = await invoke("set_brightness", { level });
+      if (!result.success) {
+        console.error("Failed to set brightness:", result.message);
+      }
+    } catch (error) {
+      console.error("Failed to set brightness:", error);
+    }
+  }
+
   function showModal(title, message) {
     return new Promise((resolve) => {
       setModal({ show: true, title, message, onConfirm: resolve });
@@ -143,17 +200,49 @@ function App() {
     }
   }
 
+  async function fetchInterfaces() {
+    try {
+      setInterfaces(await invoke("get_local_ip"));
+    } catch (error) {
+      console.error("Failed to list network interfaces:", error);
+    }
+  }
+
+  async function handleInterfaceChange(name) {
+    const result = await invoke("set_preferred_interface", { name: name || null });
+    if (!result.success) {
+      setStatus({ message: result.message, type: "error" });
+    }
+    fetchInterfaces();
+  }
+
+  const preferred = interfaces.find((iface) => iface.preferred);
+  // url_host already has IPv6 addresses in brackets
+  const serverUrl = preferred?.url_host ? `http://${preferred.url_host}:7777` : null;
+
   return (
     <main className="container">
       <AutoStartToggle />
       <h1>Ferrous Control</h1>
       <p className="subtitle">Remote PC Control Panel</p>
-      {localIp && (
+      {serverUrl && (
         <div className="network-info">
-          <p className="ip-address">Network Address: http://{localIp}:7777</p>
+          <p className="ip-address">Network Address: {serverUrl}</p>
+          <select
+            className="interface-select"
+            value={preferred.name}
+            onChange={(e) => handleInterfaceChange(e.target.value)}
+          >
+            {interfaces.map((iface) => (
+              <option key={iface.name} value={iface.name}>
+                {iface.name} — {[...iface.ipv4, ...iface.ipv6].join(", ") || "no address"}
+                {iface.is_loopback ? " (loopback)" : iface.is_virtual ? " (virtual)" : ""}
+              </option>
+            ))}
+          </select>
           <div className="qr-code-container">
             <QRCodeSVG
-              value={`http://${localIp}:7777`}
+              value={serverUrl}
               size={180}
               level="H"
             />
@@ -220,6 +309,36 @@ function App() {
         </div>
       )}
 
+      {brightness !== null && (
+        <div className="volume-display">
+          <div className="volume-level">
+            <span className="volume-icon">☀️</span>
+            <span className="volume-percentage">{brightness}%</span>
+          </div>
+          <input
+            type="range"
+            className="brightness-slider"
+            min="0"
+            max="100"
+            step="1"
+            value={brightness}
+            onChange={(e) => handleBrightnessChange(parseInt(e.target.value))}
+          />
+        </div>
+      )}
+
+      <ScheduleEditor />
+
+      <ApiAllowlist />
+
+      <DeviceRoles serverUrl={serverUrl} />
+
+      <ClientActivity />
+
+      <AuditLog />
+
+      <AppLogs />
+
       {status.message && (
         <div className={`status ${status.type}`}>
           {status.message}
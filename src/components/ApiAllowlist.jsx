@@ -0,0 +1,81 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+
+const DEFAULT_RANGES = [
+  "127.0.0.0/8",
+  "::1/128",
+  "10.0.0.0/8",
+  "172.16.0.0/12",
+  "192.168.0.0/16",
+  "169.254.0.0/16",
+  "fc00::/7",
+  "fe80::/10",
+];
+
+export default function ApiAllowlist() {
+  const [text, setText] = useState("");
+  const [denials, setDenials] = useState([]);
+  const [result, setResult] = useState({ message: "", type: "" });
+
+  async function fetchDenials() {
+    try {
+      setDenials(await invoke("list_api_denials"));
+    } catch (err) {
+      console.error("Failed to list denied requests:", err);
+    }
+  }
+
+  useEffect(() => {
+    invoke("get_api_allowlist")
+      .then((ranges) => setText(ranges.join("\n")))
+      .catch((err) => console.error("Failed to get API allowlist:", err));
+    fetchDenials();
+    const timer = setInterval(fetchDenials, 10000);
+    return () => clearInterval(timer);
+  }, []);
+
+  async function handleSave() {
+    const ranges = text.split(/[\n,]/).map((range) => range.trim()).filter(Boolean);
+    const response = await invoke("set_api_allowlist", { ranges });
+    setResult({ message: response.message, type: response.success ? "success" : "error" });
+  }
+
+  return (
+    <div className="allowlist-editor">
+      <h2>Allowed networks</h2>
+      <p className="schedule-hint">
+        Only these address ranges can use the remote API. One CIDR range per line, e.g.
+        192.168.1.0/24.
+      </p>
+      <textarea rows={6} value={text} onChange={(e) => setText(e.target.value)} />
+
+      {result.message && <div className={`status ${result.type}`}>{result.message}</div>}
+
+      <div className="schedule-buttons">
+        <button onClick={handleSave}>Save</button>
+        <button onClick={() => setText(DEFAULT_RANGES.join("\n"))}>Reset to defaults</button>
+      </div>
+
+      {denials.length > 0 && (
+        <table className="schedule-table">
+          <thead>
+            <tr>
+              <th>Denied</th>
+              <th>From</th>
+              <th>Request</th>
+            </tr>
+          </thead>
+          <tbody>
+            {denials.map((denial, i) => (
+              <tr key={i}>
+                <td>{new Date(denial.time).toLocaleString()}</td>
+                <td>{denial.ip}</td>
+                <td><code>{denial.method} {denial.path}</code></td>
+              </tr>
+            ))}
+          </tbody>
+        </table>
+      )}
+    </div>
+  );
+}
@@ -0,0 +1,36 @@
+import { useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+
+export default function AppLogs() {
+  const [result, setResult] = useState({ message: "", type: "" });
+
+  async function handleOpen() {
+    const response = await invoke("open_log_folder");
+    setResult({ message: response.success ? "" : response.message, type: "error" });
+  }
+
+  async function handleExport() {
+    try {
+      const path = await invoke("export_logs");
+      setResult({ message: `Logs exported to ${path}`, type: "success" });
+    } catch (err) {
+      setResult({ message: String(err), type: "error" });
+    }
+  }
+
+  return (
+    <div className="allowlist-editor">
+      <h2>Application logs</h2>
+      <p className="schedule-hint">
+        Daily log files from the app and the web server, useful when reporting a problem.
+      </p>
+
+      {result.message && <div className={`status ${result.type}`}>{result.message}</div>}
+
+      <div className="schedule-buttons">
+        <button onClick={handleOpen}>Open log folder</button>
+        <button onClick={handleExport}>Export to Downloads</button>
+      </div>
+    </div>
+  );
+}
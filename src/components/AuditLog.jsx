@@ -0,0 +1,112 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+
+const ORIGINS = [
+  ["", "Any origin"],
+  ["desktop", "Desktop"],
+  ["web", "Web"],
+  ["schedule", "Schedule"],
+  ["power_rule", "Power rule"],
+];
+
+function formatParams(params) {
+  if (params === null || params === undefined) {
+    return "";
+  }
+  const text = JSON.stringify(params);
+  return text === "{}" ? "" : text;
+}
+
+export default function AuditLog() {
+  const [entries, setEntries] = useState([]);
+  const [origin, setOrigin] = useState("");
+  const [action, setAction] = useState("");
+  const [client, setClient] = useState("");
+  const [outcome, setOutcome] = useState("");
+  const [since, setSince] = useState("");
+
+  async function fetchEntries() {
+    const query = {
+      origin: origin || null,
+      action: action.trim() || null,
+      client: client.trim() || null,
+      success: outcome === "" ? null : outcome === "success",
+      since: since ? new Date(since).getTime() : null,
+      limit: 200,
+    };
+    try {
+      setEntries(await invoke("query_audit", { query }));
+    } catch (err) {
+      console.error("Failed to query audit log:", err);
+    }
+  }
+
+  useEffect(() => {
+    fetchEntries();
+  }, [origin, outcome, since]);
+
+  return (
+    <div className="allowlist-editor">
+      <h2>Audit log</h2>
+      <div className="schedule-form">
+        <select value={origin} onChange={(e) => setOrigin(e.target.value)}>
+          {ORIGINS.map(([value, label]) => (
+            <option key={value} value={value}>
+              {label}
+            </option>
+          ))}
+        </select>
+        <select value={outcome} onChange={(e) => setOutcome(e.target.value)}>
+          <option value="">Any result</option>
+          <option value="success">Succeeded</option>
+          <option value="failure">Failed</option>
+        </select>
+        <input
+          type="text"
+          placeholder="Action"
+          value={action}
+          onChange={(e) => setAction(e.target.value)}
+        />
+        <input
+          type="text"
+          placeholder="Client IP"
+          value={client}
+          onChange={(e) => setClient(e.target.value)}
+        />
+        <input type="datetime-local" value={since} onChange={(e) => setSince(e.target.value)} />
+        <div className="schedule-buttons">
+          <button onClick={fetchEntries}>Search</button>
+        </div>
+      </div>
+
+      {entries.length === 0 ? (
+        <p className="schedule-hint">No matching actions.</p>
+      ) : (
+        <table className="schedule-table">
+          <thead>
+            <tr>
+              <th>Time</th>
+              <th>Origin</th>
+              <th>Action</th>
+              <th>Client</th>
+              <th>Result</th>
+            </tr>
+          </thead>
+          <tbody>
+            {entries.map((entry, i) => (
+              <tr key={i} className={entry.success ? "" : "disabled"}>
+                <td>{new Date(entry.time).toLocaleString()}</td>
+                <td>{entry.origin}</td>
+                <td>
+                  <code>{entry.action}</code> {formatParams(entry.params)}
+                </td>
+                <td title={entry.client || ""}>{entry.device || entry.client || "—"}</td>
+                <td>{entry.result}</td>
+              </tr>
+            ))}
+          </tbody>
+        </table>
+      )}
+    </div>
+  );
+}
@@ -0,0 +1,75 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+
+function formatRecent(recent) {
+  const parts = Object.entries(recent)
+    .filter(([, count]) => count > 0)
+    .map(([budget, count]) => `${budget} ${count}`);
+  return parts.length > 0 ? parts.join(", ") : "—";
+}
+
+export default function ClientActivity() {
+  const [clients, setClients] = useState([]);
+  const [error, setError] = useState("");
+
+  async function fetchClients() {
+    try {
+      setClients(await invoke("get_rate_limit_stats"));
+    } catch (err) {
+      console.error("Failed to get rate limit counters:", err);
+    }
+  }
+
+  useEffect(() => {
+    fetchClients();
+    const timer = setInterval(fetchClients, 5000);
+    return () => clearInterval(timer);
+  }, []);
+
+  async function handleUnlock(ip) {
+    const result = await invoke("unlock_client", { ip });
+    setError(result.success ? "" : result.message);
+    fetchClients();
+  }
+
+  return (
+    <div className="allowlist-editor">
+      <h2>Remote clients</h2>
+      {clients.length === 0 ? (
+        <p className="schedule-hint">No API requests since startup.</p>
+      ) : (
+        <table className="schedule-table">
+          <thead>
+            <tr>
+              <th>Client</th>
+              <th>Requests</th>
+              <th>Last minute</th>
+              <th>Rate limited</th>
+              <th>Failed sign-ins</th>
+              <th></th>
+            </tr>
+          </thead>
+          <tbody>
+            {clients.map((client) => (
+              <tr key={client.ip}>
+                <td>{client.ip}</td>
+                <td>{client.requests}</td>
+                <td>{formatRecent(client.recent)}</td>
+                <td>{client.limited}</td>
+                <td>{client.auth_failures}</td>
+                <td className="schedule-buttons">
+                  {client.locked_for !== null && (
+                    <button onClick={() => handleUnlock(client.ip)}>
+                      Unlock ({Math.ceil(client.locked_for / 60)} min left)
+                    </button>
+                  )}
+                </td>
+              </tr>
+            ))}
+          </tbody>
+        </table>
+      )}
+      {error && <div className="status error">{error}</div>}
+    </div>
+  );
+}
@@ -0,0 +1,153 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { QRCodeSVG } from "qrcode.react";
+
+const ROLES = [
+  ["viewer", "Viewer — status only"],
+  ["media", "Media — volume, screen, clipboard, files"],
+  ["power", "Power — also shutdown, sleep, wake"],
+  ["admin", "Admin — everything"],
+];
+
+function RoleSelect({ value, onChange }) {
+  return (
+    <select value={value} onChange={(e) => onChange(e.target.value)}>
+      {ROLES.map(([role, label]) => (
+        <option key={role} value={role}>
+          {label}
+        </option>
+      ))}
+    </select>
+  );
+}
+
+export default function DeviceRoles({ serverUrl }) {
+  const [defaultRole, setDefaultRole] = useState("viewer");
+  const [devices, setDevices] = useState([]);
+  const [pairing, setPairing] = useState(null);
+  const [result, setResult] = useState({ message: "", type: "" });
+
+  function fetchRoles() {
+    invoke("get_device_roles")
+      .then((settings) => {
+        setDefaultRole(settings.default_role);
+        setDevices(settings.devices);
+      })
+      .catch((err) => console.error("Failed to get device roles:", err));
+  }
+
+  useEffect(fetchRoles, []);
+
+  function updateDevice(index, changes) {
+    setDevices(devices.map((device, i) => (i === index ? { ...device, ...changes } : device)));
+  }
+
+  async function handleSave() {
+    const response = await invoke("set_device_roles", {
+      settings: { default_role: defaultRole, devices },
+    });
+    setResult({ message: response.message, type: response.success ? "success" : "error" });
+    if (response.success) {
+      // Pick up the tokens generated for new devices
+      fetchRoles();
+    }
+  }
+
+  return (
+    <div className="allowlist-editor">
+      <h2>Device roles</h2>
+      <p className="schedule-hint">
+        Limit what each phone, tablet or PC can do. Phones and tablets pair by scanning the code
+        under Pair; another PC sends the token set for it here as its peer token. This PC itself
+        always has full access.
+      </p>
+
+      <div className="schedule-form">
+        <label>
+          Unpaired devices <RoleSelect value={defaultRole} onChange={setDefaultRole} />
+        </label>
+      </div>
+
+      {devices.length > 0 && (
+        <table className="schedule-table">
+          <thead>
+            <tr>
+              <th>Name</th>
+              <th>Token</th>
+              <th>Role</th>
+              <th></th>
+            </tr>
+          </thead>
+          <tbody>
+            {devices.map((device, i) => (
+              <tr key={i}>
+                <td>
+                  <input
+                    type="text"
+                    placeholder="Kids' tablet"
+                    value={device.name}
+                    onChange={(e) => updateDevice(i, { name: e.target.value })}
+                  />
+                </td>
+                <td>
+                  <input
+                    type="text"
+                    readOnly
+                    placeholder="Generated when saved"
+                    value={device.token}
+                    onFocus={(e) => e.target.select()}
+                  />
+                </td>
+                <td>
+                  <RoleSelect value={device.role} onChange={(role) => updateDevice(i, { role })} />
+                </td>
+                <td className="schedule-buttons">
+                  <button
+                    disabled={!device.token || !serverUrl}
+                    onClick={() => setPairing(pairing === device.token ? null : device.token)}
+                  >
+                    Pair
+                  </button>
+                  <button
+                    title="Unpair this device; a new token is made when saved"
+                    onClick={() => {
+                      updateDevice(i, { token: "" });
+                      setPairing(null);
+                    }}
+                  >
+                    New token
+                  </button>
+                  <button onClick={() => setDevices(devices.filter((_, j) => j !== i))}>
+                    Remove
+                  </button>
+                </td>
+              </tr>
+            ))}
+          </tbody>
+        </table>
+      )}
+
+      {pairing && serverUrl && (
+        <div className="qr-code-container">
+          <QRCodeSVG
+            value={`${serverUrl}/api/pair?token=${encodeURIComponent(pairing)}`}
+            size={180}
+            level="H"
+          />
+          <p className="qr-label">Scan on the device to pair it</p>
+        </div>
+      )}
+
+      {result.message && <div className={`status ${result.type}`}>{result.message}</div>}
+
+      <div className="schedule-buttons">
+        <button
+          onClick={() => setDevices([...devices, { name: "", token: "", role: "media" }])}
+        >
+          Add device
+        </button>
+        <button onClick={handleSave}>Save</button>
+      </div>
+    </div>
+  );
+}
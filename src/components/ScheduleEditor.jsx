@@ -0,0 +1,288 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+
+const ACTION_TYPES = [
+  { type: "shutdown", label: "Shutdown" },
+  { type: "restart", label: "Restart" },
+  { type: "sleep", label: "Sleep" },
+  { type: "cancel_shutdown", label: "Cancel shutdown" },
+  { type: "set_volume", label: "Set volume" },
+  { type: "set_brightness", label: "Set brightness" },
+  { type: "launch", label: "Launch application" },
+  { type: "notify", label: "Show notification" },
+  { type: "speak", label: "Speak" },
+];
+
+const EMPTY_FORM = {
+  name: "",
+  cron: "0 1 * * 1-5",
+  enabled: true,
+  missed: "skip",
+  action: { type: "shutdown" },
+};
+
+function describeAction(action) {
+  switch (action.type) {
+    case "set_volume":
+      return `Set volume to ${action.level}%`;
+    case "set_brightness":
+      return `Set brightness to ${action.level}%`;
+    case "launch":
+      return `Launch ${action.name}`;
+    case "notify":
+      return `Notify "${action.title}"`;
+    case "speak":
+      return `Say "${action.text}"`;
+    default:
+      return ACTION_TYPES.find((entry) => entry.type === action.type)?.label ?? action.type;
+  }
+}
+
+function formatTime(millis) {
+  return millis ? new Date(millis).toLocaleString() : "—";
+}
+
+export default function ScheduleEditor() {
+  const [schedules, setSchedules] = useState([]);
+  const [form, setForm] = useState(EMPTY_FORM);
+  const [editingId, setEditingId] = useState(null);
+  const [preview, setPreview] = useState({ runs: [], error: "" });
+  const [launchable, setLaunchable] = useState([]);
+  const [error, setError] = useState("");
+
+  async function fetchSchedules() {
+    try {
+      setSchedules(await invoke("list_schedules"));
+    } catch (err) {
+      console.error("Failed to list schedules:", err);
+    }
+  }
+
+  useEffect(() => {
+    fetchSchedules();
+    invoke("get_launch_allowlist")
+      .then((entries) => setLaunchable(entries.map((entry) => entry.name)))
+      .catch((err) => console.error("Failed to get launch allowlist:", err));
+    // Refresh next/last run times as schedules fire
+    const timer = setInterval(fetchSchedules, 30000);
+    return () => clearInterval(timer);
+  }, []);
+
+  useEffect(() => {
+    // Debounce so the preview follows typing without a request per key
+    const timeout = setTimeout(async () => {
+      try {
+        setPreview({ runs: await invoke("preview_schedule", { cron: form.cron }), error: "" });
+      } catch (err) {
+        setPreview({ runs: [], error: String(err) });
+      }
+    }, 300);
+    return () => clearTimeout(timeout);
+  }, [form.cron]);
+
+  function setAction(changes) {
+    setForm({ ...form, action: { ...form.action, ...changes } });
+  }
+
+  function handleActionType(type) {
+    const defaults = {
+      set_volume: { level: 20 },
+      set_brightness: { level: 50 },
+      launch: { name: launchable[0] ?? "" },
+      notify: { title: "", body: "" },
+      speak: { text: "" },
+    };
+    setForm({ ...form, action: { type, ...defaults[type] } });
+  }
+
+  function startEdit(schedule) {
+    setEditingId(schedule.id);
+    setForm({
+      name: schedule.name,
+      cron: schedule.cron,
+      enabled: schedule.enabled,
+      missed: schedule.missed,
+      action: schedule.action,
+    });
+    setError("");
+  }
+
+  function resetForm() {
+    setEditingId(null);
+    setForm(EMPTY_FORM);
+    setError("");
+  }
+
+  async function handleSave() {
+    try {
+      if (editingId === null) {
+        await invoke("create_schedule", { schedule: form });
+      } else {
+        await invoke("update_schedule", { id: editingId, schedule: form });
+      }
+      resetForm();
+      fetchSchedules();
+    } catch (err) {
+      setError(String(err));
+    }
+  }
+
+  async function handleToggle(schedule) {
+    const { id, name, cron, action, missed } = schedule;
+    try {
+      await invoke("update_schedule", {
+        id,
+        schedule: { name, cron, action, missed, enabled: !schedule.enabled },
+      });
+      fetchSchedules();
+    } catch (err) {
+      setError(String(err));
+    }
+  }
+
+  async function handleDelete(id) {
+    const result = await invoke("delete_schedule", { id });
+    if (!result.success) {
+      setError(result.message);
+    }
+    if (editingId === id) {
+      resetForm();
+    }
+    fetchSchedules();
+  }
+
+  return (
+    <div className="schedule-editor">
+      <h2>Schedules</h2>
+
+      {schedules.length > 0 && (
+        <table className="schedule-table">
+          <thead>
+            <tr>
+              <th>Name</th>
+              <th>When</th>
+              <th>Action</th>
+              <th>Next run</th>
+              <th>Last result</th>
+              <th></th>
+            </tr>
+          </thead>
+          <tbody>
+            {schedules.map((schedule) => (
+              <tr key={schedule.id} className={schedule.enabled ? "" : "disabled"}>
+                <td>{schedule.name}</td>
+                <td><code>{schedule.cron}</code></td>
+                <td>{describeAction(schedule.action)}</td>
+                <td>{schedule.enabled ? formatTime(schedule.next_run) : "Disabled"}</td>
+                <td title={formatTime(schedule.last_run)}>{schedule.last_result ?? "—"}</td>
+                <td className="schedule-buttons">
+                  <button onClick={() => handleToggle(schedule)}>
+                    {schedule.enabled ? "Disable" : "Enable"}
+                  </button>
+                  <button onClick={() => startEdit(schedule)}>Edit</button>
+                  <button onClick={() => handleDelete(schedule.id)}>Delete</button>
+                </td>
+              </tr>
+            ))}
+          </tbody>
+        </table>
+      )}
+
+      <div className="schedule-form">
+        <input
+          type="text"
+          placeholder="Name, e.g. Weeknight shutdown"
+          value={form.name}
+          onChange={(e) => setForm({ ...form, name: e.target.value })}
+        />
+        <input
+          type="text"
+          placeholder="minute hour day month weekday"
+          value={form.cron}
+          onChange={(e) => setForm({ ...form, cron: e.target.value })}
+        />
+        <p className="schedule-hint">
+          {preview.error
+            ? preview.error
+            : preview.runs.length > 0
+              ? `Next: ${preview.runs.map((run) => new Date(run).toLocaleString()).join(", ")}`
+              : "Never runs"}
+        </p>
+
+        <select value={form.action.type} onChange={(e) => handleActionType(e.target.value)}>
+          {ACTION_TYPES.map((entry) => (
+            <option key={entry.type} value={entry.type}>{entry.label}</option>
+          ))}
+        </select>
+
+        {(form.action.type === "set_volume" || form.action.type === "set_brightness") && (
+          <input
+            type="number"
+            min="0"
+            max="100"
+            value={form.action.level}
+            onChange={(e) => setAction({ level: parseInt(e.target.value) || 0 })}
+          />
+        )}
+
+        {form.action.type === "launch" && (
+          <select value={form.action.name} onChange={(e) => setAction({ name: e.target.value })}>
+            {launchable.map((name) => (
+              <option key={name} value={name}>{name}</option>
+            ))}
+          </select>
+        )}
+
+        {form.action.type === "notify" && (
+          <>
+            <input
+              type="text"
+              placeholder="Title"
+              value={form.action.title}
+              onChange={(e) => setAction({ title: e.target.value })}
+            />
+            <input
+              type="text"
+              placeholder="Message"
+              value={form.action.body}
+              onChange={(e) => setAction({ body: e.target.value })}
+            />
+          </>
+        )}
+
+        {form.action.type === "speak" && (
+          <input
+            type="text"
+            placeholder="Text to speak"
+            value={form.action.text}
+            onChange={(e) => setAction({ text: e.target.value })}
+          />
+        )}
+
+        <label>
+          If missed while asleep or closed:{" "}
+          <select value={form.missed} onChange={(e) => setForm({ ...form, missed: e.target.value })}>
+            <option value="skip">Skip it</option>
+            <option value="run_once">Run once on wake</option>
+          </select>
+        </label>
+
+        <label>
+          <input
+            type="checkbox"
+            checked={form.enabled}
+            onChange={(e) => setForm({ ...form, enabled: e.target.checked })}
+          />{" "}
+          Enabled
+        </label>
+
+        {error && <div className="status error">{error}</div>}
+
+        <div className="schedule-buttons">
+          <button onClick={handleSave}>{editingId === null ? "Add schedule" : "Save changes"}</button>
+          {editingId !== null && <button onClick={resetForm}>Cancel</button>}
+        </div>
+      </div>
+    </div>
+  );
+}
@@ -0,0 +1,69 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { getCurrentWindow } from "@tauri-apps/api/window";
+
+export default function ShutdownWarning() {
+  const [warning, setWarning] = useState(null);
+  const [now, setNow] = useState(Date.now());
+  const [error, setError] = useState("");
+
+  async function fetchWarning() {
+    try {
+      const current = await invoke("get_shutdown_warning");
+      if (!current) {
+        // The shutdown was cancelled or already happened
+        await getCurrentWindow().close();
+        return;
+      }
+      setWarning(current);
+    } catch (err) {
+      console.error("Failed to get shutdown warning:", err);
+    }
+  }
+
+  useEffect(() => {
+    fetchWarning();
+    const timer = setInterval(() => setNow(Date.now()), 1000);
+    // Sent when another shutdown is scheduled while this window is open
+    const unlisten = listen("shutdown-warning-changed", fetchWarning);
+    return () => {
+      clearInterval(timer);
+      unlisten.then((stop) => stop());
+    };
+  }, []);
+
+  async function handleCancel() {
+    try {
+      const result = await invoke("cancel_shutdown");
+      // On success the backend closes this window
+      if (!result.success) {
+        setError(result.message);
+      }
+    } catch (err) {
+      setError(String(err));
+    }
+  }
+
+  if (!warning) {
+    return null;
+  }
+
+  const seconds = Math.max(0, Math.ceil((warning.deadline - now) / 1000));
+
+  return (
+    <main className="shutdown-warning">
+      <h2>Shutting down in {seconds}s</h2>
+      <p>{warning.message}</p>
+      {error && <div className="status error">{error}</div>}
+      <div className="modal-buttons">
+        <button className="modal-btn modal-btn-confirm" onClick={handleCancel}>
+          Cancel Shutdown
+        </button>
+        <button className="modal-btn modal-btn-cancel" onClick={() => getCurrentWindow().close()}>
+          Dismiss
+        </button>
+      </div>
+    </main>
+  );
+}
@@ -1,12 +1,18 @@
 import React from "react";
 import ReactDOM from "react-dom/client";
 import App from "./App";
+import ShutdownWarning from "./components/ShutdownWarning";
 import { InitTray } from "./utils/Tray";
 
-InitTray();
+// The shutdown countdown window loads this same page with its own hash
+const isShutdownWarning = window.location.hash === "#shutdown-warning";
+
+if (!isShutdownWarning) {
+  InitTray();
+}
 
 ReactDOM.createRoot(document.getElementById("root")).render(
   <React.StrictMode>
-    <App />
+    {isShutdownWarning ? <ShutdownWarning /> : <App />}
   </React.StrictMode>,
 );
@@ -11,10 +11,13 @@ export const InitTray = async () => {
             case 'quit':
                 await exit(0);
                 break;
-            case 'show':
-                await Window.getAll().then(win => win[0].show());
-                await Window.getAll().then(win => win[0].setFocus());
+            case 'show': {
+                // Other windows (e.g. the shutdown warning) may exist, so look up the main one
+                const main = await Window.getByLabel('main');
+                await main?.show();
+                await main?.setFocus();
                 break;
+            }
         }
     }
     const menu = await Menu.new({